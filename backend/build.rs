@@ -15,6 +15,9 @@ fn main() {
 
     generate_protos(repo_root);
 
+    // sqlx::migrate! embeds the migrations at compile time.
+    println!("cargo:rerun-if-changed=migrations");
+
     let build_version = capture_git_hash(repo_root);
     println!("cargo:rustc-env=BUILD_VERSION={build_version}");
 
@@ -0,0 +1,13 @@
+-- Rows skipped during import, so uploaders can see why they're missing
+
+ALTER TABLE uploads ADD COLUMN format TEXT;
+ALTER TABLE uploads ADD COLUMN skipped_rows INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS import_issues (
+    upload_id BLOB NOT NULL,
+    row_number INTEGER NOT NULL,
+    column_name TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    PRIMARY KEY (upload_id, row_number),
+    FOREIGN KEY(upload_id) REFERENCES uploads(id) ON DELETE CASCADE
+) STRICT;
@@ -0,0 +1,20 @@
+-- Collections merge several uploads into one timeline. A collection is an
+-- uploads row of its own holding copies of its members' sightings, so every
+-- read endpoint accepts a collection ID wherever it takes an upload ID.
+
+ALTER TABLE uploads ADD COLUMN is_collection INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS collection_members (
+    collection_id BLOB NOT NULL,
+    upload_id BLOB NOT NULL,
+    PRIMARY KEY (collection_id, upload_id),
+    FOREIGN KEY(collection_id) REFERENCES uploads(id) ON DELETE CASCADE,
+    FOREIGN KEY(upload_id) REFERENCES uploads(id) ON DELETE CASCADE
+) STRICT;
+
+CREATE INDEX IF NOT EXISTS idx_collection_members_upload
+    ON collection_members(upload_id);
+
+-- For de-duplicating sightings that appear in more than one member
+CREATE INDEX IF NOT EXISTS idx_sightings_uuid
+    ON sightings(upload_id, sighting_uuid);
@@ -0,0 +1,60 @@
+-- A sighting_uuid identifies one sighting within an upload. Earlier imports
+-- didn't enforce that, so keep the first copy (lowest id) of any duplicates
+-- before adding the constraint. The copies removed are kept in
+-- removed_duplicate_sightings so an operator can see what went and put rows
+-- back by hand; the table can be dropped once reviewed. Ticks and bitmaps of
+-- the affected uploads can't be worked out in SQL, so those uploads are listed
+-- in pending_tick_recomputes for the server to recompute at startup.
+
+CREATE TABLE IF NOT EXISTS removed_duplicate_sightings (
+    id INTEGER PRIMARY KEY,
+    upload_id BLOB NOT NULL,
+    sighting_uuid BLOB NOT NULL,
+    -- The row with the same sighting_uuid that was kept
+    kept_id INTEGER NOT NULL,
+    species_id INTEGER NOT NULL,
+    count INTEGER,
+    latitude REAL NOT NULL,
+    longitude REAL NOT NULL,
+    country_code TEXT,
+    region_code TEXT,
+    observed_at TEXT NOT NULL,
+    year INTEGER,
+    removed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    FOREIGN KEY(upload_id) REFERENCES uploads(id) ON DELETE CASCADE
+) STRICT;
+
+INSERT INTO removed_duplicate_sightings
+    (id, upload_id, sighting_uuid, kept_id, species_id, count, latitude, longitude, country_code, region_code, observed_at, year)
+SELECT s.id, s.upload_id, s.sighting_uuid, k.kept_id, s.species_id, s.count, s.latitude, s.longitude, s.country_code, s.region_code, s.observed_at, s.year
+FROM sightings s
+JOIN (
+    SELECT upload_id, sighting_uuid, MIN(id) AS kept_id
+    FROM sightings
+    GROUP BY upload_id, sighting_uuid
+    HAVING COUNT(*) > 1
+) k ON k.upload_id = s.upload_id AND k.sighting_uuid = s.sighting_uuid
+WHERE s.id != k.kept_id;
+
+UPDATE uploads SET data_version = data_version + 1
+WHERE id IN (SELECT DISTINCT upload_id FROM removed_duplicate_sightings);
+
+CREATE TABLE IF NOT EXISTS pending_tick_recomputes (
+    upload_id BLOB PRIMARY KEY,
+    FOREIGN KEY(upload_id) REFERENCES uploads(id) ON DELETE CASCADE
+) STRICT;
+
+INSERT OR IGNORE INTO pending_tick_recomputes (upload_id)
+SELECT DISTINCT upload_id FROM removed_duplicate_sightings;
+
+DELETE FROM sightings
+WHERE id IN (SELECT id FROM removed_duplicate_sightings);
+
+UPDATE uploads
+SET row_count = (SELECT COUNT(*) FROM sightings WHERE sightings.upload_id = uploads.id)
+WHERE id IN (SELECT DISTINCT upload_id FROM removed_duplicate_sightings);
+
+DROP INDEX IF EXISTS idx_sightings_uuid;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sightings_upload_uuid
+    ON sightings(upload_id, sighting_uuid);
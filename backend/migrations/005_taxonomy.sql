@@ -0,0 +1,22 @@
+-- Bundled taxonomy, mirrored from backend/data/taxonomy.csv at startup.
+-- Species rows point at their taxon once their names have been matched;
+-- names the taxonomy does not know keep a NULL taxon_id.
+
+CREATE TABLE IF NOT EXISTS taxa (
+    id INTEGER PRIMARY KEY,
+    category TEXT NOT NULL,
+    common_name TEXT NOT NULL,
+    scientific_name TEXT NOT NULL,
+    order_name TEXT,
+    family TEXT,
+    report_as INTEGER
+) STRICT;
+
+ALTER TABLE species ADD COLUMN taxon_id INTEGER REFERENCES taxa(id);
+
+CREATE INDEX IF NOT EXISTS idx_species_taxon
+    ON species(taxon_id);
+
+-- For moving sightings off a species stored under a synonym
+CREATE INDEX IF NOT EXISTS idx_sightings_species
+    ON sightings(species_id);
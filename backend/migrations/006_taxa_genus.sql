@@ -0,0 +1,4 @@
+-- Genus of each taxon, filled in by the startup taxonomy sync. NULL for
+-- entries that span genera (intergeneric hybrids, family-level spuhs).
+
+ALTER TABLE taxa ADD COLUMN genus TEXT;
@@ -0,0 +1,7 @@
+-- Spuhs ("gull sp."), slashes, hybrids and domestic forms are shown on the map
+-- but never tick. Subspecies groups tick as their parent species. Both are
+-- filled in from the taxonomy on insert, and for existing rows by the startup
+-- reconcile.
+
+ALTER TABLE species ADD COLUMN countable INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE species ADD COLUMN parent_species_id INTEGER REFERENCES species(id);
@@ -0,0 +1,5 @@
+-- Calendar date of each sighting in the local time of where it was made,
+-- which ticks, streaks and timelines go by instead of the UTC date. Existing
+-- rows are filled in (and their year corrected) at startup.
+
+ALTER TABLE sightings ADD COLUMN local_date TEXT;
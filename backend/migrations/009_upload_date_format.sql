@@ -0,0 +1,5 @@
+-- Day/month order given for an upload's dates ('dmy' or 'mdy'), reused when
+-- the upload is later replaced or appended to without one. NULL means the
+-- order is worked out per date.
+
+ALTER TABLE uploads ADD COLUMN date_format TEXT;
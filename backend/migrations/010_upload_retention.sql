@@ -0,0 +1,6 @@
+-- Pinned uploads are never removed by the retention task. An expiry date
+-- (same format as last_accessed_at) replaces the access-based retention
+-- period for unpinned uploads; NULL means the upload follows it.
+
+ALTER TABLE uploads ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE uploads ADD COLUMN expires_at TEXT;
@@ -0,0 +1,20 @@
+-- Read-only share links. Each token stands in for the upload ID on read
+-- endpoints; only its hash is kept, like edit_token_hash. A share can carry
+-- a filter (same JSON as the filter query parameter) that every request made
+-- with it is restricted to, can snap coordinates to a coarse grid, and stops
+-- working after expires_at (same format as uploads.expires_at) if set.
+
+CREATE TABLE IF NOT EXISTS share_tokens (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    upload_id BLOB NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    label TEXT,
+    filter TEXT,
+    hide_locations INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    expires_at TEXT,
+    FOREIGN KEY(upload_id) REFERENCES uploads(id) ON DELETE CASCADE
+) STRICT;
+
+CREATE INDEX IF NOT EXISTS idx_share_tokens_upload
+    ON share_tokens(upload_id);
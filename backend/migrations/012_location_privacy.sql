@@ -0,0 +1,30 @@
+-- How precisely sightings are shown to anyone without the edit token.
+-- location_mode is 'exact', 'grid' (snapped to cells location_km across),
+-- 'jitter' (moved up to location_km in a fixed direction per place) or 'hide'.
+-- sensitive_species applies the bundled sensitive species list, and
+-- species_location_rules overrides the mode for individual species.
+-- location_salt keeps jittered positions from being worked back to the
+-- original ones; the trigger gives every new upload its own.
+
+ALTER TABLE uploads ADD COLUMN location_mode TEXT NOT NULL DEFAULT 'exact';
+ALTER TABLE uploads ADD COLUMN location_km REAL;
+ALTER TABLE uploads ADD COLUMN sensitive_species INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE uploads ADD COLUMN location_salt BLOB;
+
+UPDATE uploads SET location_salt = randomblob(16);
+
+CREATE TRIGGER IF NOT EXISTS uploads_location_salt
+AFTER INSERT ON uploads
+WHEN NEW.location_salt IS NULL
+BEGIN
+    UPDATE uploads SET location_salt = randomblob(16) WHERE id = NEW.id;
+END;
+
+CREATE TABLE IF NOT EXISTS species_location_rules (
+    upload_id BLOB NOT NULL,
+    scientific_name TEXT NOT NULL,
+    mode TEXT NOT NULL,
+    km REAL,
+    PRIMARY KEY (upload_id, scientific_name),
+    FOREIGN KEY(upload_id) REFERENCES uploads(id) ON DELETE CASCADE
+) STRICT;
@@ -0,0 +1,6 @@
+-- Optional password for reading an upload. Only an Argon2id hash is kept, as
+-- a PHC string carrying its own salt and cost parameters, so equal passwords
+-- on different uploads don't share a hash.
+-- NULL means anyone with the upload ID or a share link can read it.
+
+ALTER TABLE uploads ADD COLUMN view_password_hash TEXT;
@@ -0,0 +1,15 @@
+-- Extra named edit tokens, so an upload can be edited from several places
+-- and each one revoked on its own. The token handed out at upload time stays
+-- in uploads.edit_token_hash; either kind can edit the upload. Only hashes
+-- are kept, made the same way as edit_token_hash. Tokens are always looked
+-- up by upload and hash, which the unique constraint also indexes.
+
+CREATE TABLE IF NOT EXISTS edit_tokens (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    upload_id BLOB NOT NULL,
+    token_hash TEXT NOT NULL,
+    name TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    UNIQUE (upload_id, token_hash),
+    FOREIGN KEY(upload_id) REFERENCES uploads(id) ON DELETE CASCADE
+) STRICT;
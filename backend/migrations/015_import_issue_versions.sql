@@ -0,0 +1,23 @@
+-- Appends add their skipped rows to the import report instead of replacing
+-- it, so row numbers from different files can repeat. Each issue is keyed
+-- by the data_version its import produced as well; existing issues all came
+-- from the latest import.
+
+CREATE TABLE IF NOT EXISTS import_issues_new (
+    upload_id BLOB NOT NULL,
+    data_version INTEGER NOT NULL,
+    row_number INTEGER NOT NULL,
+    column_name TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    PRIMARY KEY (upload_id, data_version, row_number),
+    FOREIGN KEY(upload_id) REFERENCES uploads(id) ON DELETE CASCADE
+) STRICT;
+
+INSERT INTO import_issues_new (upload_id, data_version, row_number, column_name, reason)
+SELECT i.upload_id, u.data_version, i.row_number, i.column_name, i.reason
+FROM import_issues i
+JOIN uploads u ON u.id = i.upload_id;
+
+DROP TABLE import_issues;
+
+ALTER TABLE import_issues_new RENAME TO import_issues;
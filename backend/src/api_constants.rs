@@ -4,9 +4,33 @@ pub const UPLOAD_ROUTE: &str = "/api/uploads";
 pub const UPLOAD_DETAILS_ROUTE: &str = "/api/uploads/{upload_id}";
 pub const UPLOAD_COUNT_ROUTE: &str = "/api/uploads/{upload_id}/count";
 pub const UPLOAD_BBOX_ROUTE: &str = "/api/uploads/{upload_id}/bbox";
+pub const UPLOAD_APPEND_ROUTE: &str = "/api/uploads/{upload_id}/append";
+pub const UPLOAD_BACKUP_ROUTE: &str = "/api/uploads/{upload_id}/backup";
+pub const UPLOAD_RESTORE_ROUTE: &str = "/api/uploads/restore";
+pub const UPLOAD_RETENTION_ROUTE: &str = "/api/uploads/{upload_id}/retention";
+pub const UPLOAD_SHARES_ROUTE: &str = "/api/uploads/{upload_id}/shares";
+pub const UPLOAD_SHARE_ROUTE: &str = "/api/uploads/{upload_id}/shares/{share_id}";
+pub const UPLOAD_LOCATION_PRIVACY_ROUTE: &str = "/api/uploads/{upload_id}/location-privacy";
+pub const UPLOAD_EDIT_TOKENS_ROUTE: &str = "/api/uploads/{upload_id}/edit-tokens";
+pub const UPLOAD_EDIT_TOKEN_ROUTE: &str = "/api/uploads/{upload_id}/edit-tokens/{token_id}";
+pub const UPLOAD_EDIT_TOKEN_ROTATE_ROUTE: &str = "/api/uploads/{upload_id}/edit-tokens/rotate";
+pub const UPLOAD_PASSWORD_ROUTE: &str = "/api/uploads/{upload_id}/password";
+pub const UPLOAD_UNLOCK_ROUTE: &str = "/api/uploads/{upload_id}/unlock";
 pub const UPLOAD_SIGHTINGS_ROUTE: &str = "/api/uploads/{upload_id}/sightings";
+pub const UPLOAD_SIGHTING_ROUTE: &str = "/api/uploads/{upload_id}/sightings/{sighting_id}";
 pub const UPLOAD_STATS_ROUTE: &str = "/api/uploads/{upload_id}/stats";
+pub const UPLOAD_IMPORT_REPORT_ROUTE: &str = "/api/uploads/{upload_id}/import-report";
+pub const UPLOAD_EXPORT_GEOJSON_ROUTE: &str = "/api/uploads/{upload_id}/export.geojson";
+pub const UPLOAD_EXPORT_KML_ROUTE: &str = "/api/uploads/{upload_id}/export.kml";
+pub const UPLOAD_EXPORT_CSV_ROUTE: &str = "/api/uploads/{upload_id}/export.csv";
+pub const UPLOAD_MAP_PNG_ROUTE: &str = "/api/uploads/{upload_id}/map.png";
+pub const UPLOAD_MAP_SVG_ROUTE: &str = "/api/uploads/{upload_id}/map.svg";
+pub const COLLECTIONS_ROUTE: &str = "/api/collections";
+pub const COLLECTION_DETAILS_ROUTE: &str = "/api/collections/{collection_id}";
+pub const COLLECTION_MEMBER_ROUTE: &str = "/api/collections/{collection_id}/members/{upload_id}";
 pub const TILE_ROUTE: &str = "/api/tiles/{upload_id}/{z}/{x}/{y}";
+pub const DENSITY_TILE_ROUTE: &str = "/api/tiles/{upload_id}/density/{z}/{x}/{y}";
+pub const CLUSTER_TILE_ROUTE: &str = "/api/tiles/{upload_id}/clusters/{z}/{x}/{y}";
 pub const FIELDS_ROUTE: &str = "/api/fields";
 pub const FIELD_VALUES_ROUTE: &str = "/api/uploads/{upload_id}/fields/{field}";
 
@@ -0,0 +1,468 @@
+//! Self-contained backups of an upload, so one that's about to be removed by
+//! the data retention sweep can be downloaded and restored later.
+//!
+//! An archive is a ZIP holding `manifest.json` with the upload's metadata,
+//! location privacy and view password, and JSON Lines files for its species,
+//! sightings and import issues, plus a `signature` over all of them: an HMAC
+//! keyed by `REDGROUSE_SESSION_SECRET`, so only archives this server wrote and
+//! nobody has changed since can be restored. A restore takes the original
+//! upload ID back if it's free and keeps the stored tick flags, but always
+//! issues new edit tokens and a new location salt.
+
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Response};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::FromRow;
+use std::io::{Cursor, Read, Write};
+use std::time::Duration;
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::db::{self, DbPools};
+use crate::error::ApiError;
+use crate::pipeline::{geocode, local_day, MAX_UPLOAD_ROWS};
+use crate::sources::dates::normalise_observed_at;
+use crate::sources::DateFormat;
+use crate::tiles::LatLng;
+use crate::upload::{check_edit_token, is_collection, parse_upload_id};
+use crate::view_password::session_secret;
+
+/// Bumped whenever the archive layout changes. Only archives of the current
+/// version can be restored.
+pub const BACKUP_FORMAT_VERSION: u32 = 3;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const SPECIES_FILE: &str = "species.jsonl";
+const SIGHTINGS_FILE: &str = "sightings.jsonl";
+const ISSUES_FILE: &str = "import_issues.jsonl";
+const SIGNATURE_FILE: &str = "signature";
+const SIGNED_FILES: [&str; 4] = [MANIFEST_FILE, SPECIES_FILE, SIGHTINGS_FILE, ISSUES_FILE];
+
+// A full 250,000 row upload is around 80 MB of JSON; the cap stops a small
+// archive from decompressing into something much larger.
+const MAX_ENTRY_BYTES: u64 = 256 * 1024 * 1024;
+const ARCHIVE_TIMEOUT: Duration = Duration::from_secs(60);
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct BackupManifest {
+    #[sqlx(skip)]
+    pub version: u32,
+    #[sqlx(skip)]
+    pub upload_id: String,
+    pub filename: String,
+    pub display_name: Option<String>,
+    pub created_at: String,
+    pub row_count: i64,
+    pub data_version: i64,
+    pub format: Option<String>,
+    pub skipped_rows: i64,
+    pub date_format: Option<String>,
+    pub pinned: bool,
+    pub expires_at: Option<String>,
+    pub location_mode: String,
+    pub location_km: Option<f64>,
+    pub sensitive_species: bool,
+    pub view_password_hash: Option<String>,
+    #[sqlx(skip)]
+    pub species_location_rules: Vec<BackupLocationRule>,
+    #[sqlx(skip)]
+    pub edit_tokens: Vec<BackupEditToken>,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct BackupLocationRule {
+    pub scientific_name: String,
+    pub mode: String,
+    pub km: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct BackupEditToken {
+    pub name: String,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct BackupSpecies {
+    pub id: i64,
+    pub common_name: String,
+    pub scientific_name: String,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct BackupSighting {
+    #[serde(with = "uuid_bytes")]
+    pub sighting_uuid: Vec<u8>,
+    pub species_id: i64,
+    pub count: Option<i64>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub country_code: Option<String>,
+    pub region_code: Option<String>,
+    pub observed_at: String,
+    pub local_date: Option<String>,
+    pub year: Option<i64>,
+    pub lifer: bool,
+    pub year_tick: bool,
+    pub country_tick: bool,
+    pub vis_rank: i64,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct BackupIssue {
+    pub data_version: i64,
+    pub row_number: i64,
+    pub column_name: String,
+    pub reason: String,
+}
+
+pub struct BackupArchive {
+    pub manifest: BackupManifest,
+    pub species: Vec<BackupSpecies>,
+    pub sightings: Vec<BackupSighting>,
+    pub issues: Vec<BackupIssue>,
+    /// Whether every sighting was located where the archive says it was, so
+    /// its stored tick flags and `vis_rank` still hold.
+    pub located_as_stored: bool,
+}
+
+// Sighting IDs are written as UUID strings so the archive stays readable.
+mod uuid_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use uuid::Uuid;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        let uuid = Uuid::from_slice(bytes).map_err(serde::ser::Error::custom)?;
+        serializer.collect_str(&uuid)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        let uuid = Uuid::parse_str(&value).map_err(serde::de::Error::custom)?;
+        Ok(uuid.as_bytes().to_vec())
+    }
+}
+
+pub async fn download_backup(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    check_edit_token(pools.read(), &headers, &upload_id).await?;
+
+    let upload_uuid = parse_upload_id(&upload_id)?;
+    if is_collection(pools.read(), &upload_uuid).await? {
+        return Err(ApiError::bad_request(
+            "Collections can't be backed up; back up their member uploads instead",
+        ));
+    }
+
+    let archive = load_archive(&pools, &upload_uuid).await?;
+    let bytes = run_blocking(move || write_archive(&archive)).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"backup-{upload_uuid}.zip\""),
+            ),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+async fn load_archive(pools: &DbPools, upload_uuid: &Uuid) -> Result<BackupArchive, ApiError> {
+    let upload_id_blob = &upload_uuid.as_bytes()[..];
+
+    let mut manifest = db::query_with_timeout(
+        sqlx::query_as::<_, BackupManifest>(
+            "SELECT filename, display_name, created_at, row_count, data_version, format,
+                skipped_rows, date_format, pinned, expires_at, location_mode,
+                location_km, sensitive_species, view_password_hash
+             FROM uploads WHERE id = ?",
+        )
+        .bind(upload_id_blob)
+        .fetch_optional(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading upload for backup", "Database error"))?
+    .ok_or_else(|| ApiError::not_found("Upload not found"))?;
+    manifest.version = BACKUP_FORMAT_VERSION;
+    manifest.upload_id = upload_uuid.to_string();
+
+    manifest.species_location_rules = db::query_with_timeout(
+        sqlx::query_as::<_, BackupLocationRule>(
+            "SELECT scientific_name, mode, km FROM species_location_rules
+             WHERE upload_id = ?
+             ORDER BY scientific_name",
+        )
+        .bind(upload_id_blob)
+        .fetch_all(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading location rules for backup", "Database error"))?;
+
+    manifest.edit_tokens = db::query_with_timeout(
+        sqlx::query_as::<_, BackupEditToken>(
+            "SELECT name, created_at FROM edit_tokens
+             WHERE upload_id = ?
+             ORDER BY id",
+        )
+        .bind(upload_id_blob)
+        .fetch_all(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading edit tokens for backup", "Database error"))?;
+
+    let species = db::query_with_timeout(
+        sqlx::query_as::<_, BackupSpecies>(
+            "SELECT id, common_name, scientific_name FROM species
+             WHERE id IN (SELECT species_id FROM sightings WHERE upload_id = ?)
+             ORDER BY id",
+        )
+        .bind(upload_id_blob)
+        .fetch_all(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading species for backup", "Database error"))?;
+
+    let sightings = db::query_with_timeout(
+        sqlx::query_as::<_, BackupSighting>(
+            "SELECT sighting_uuid, species_id, count, latitude, longitude, country_code,
+                region_code, observed_at, local_date, year, lifer, year_tick,
+                country_tick, vis_rank
+             FROM sightings WHERE upload_id = ?
+             ORDER BY id",
+        )
+        .bind(upload_id_blob)
+        .fetch_all(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading sightings for backup", "Database error"))?;
+
+    let issues = db::query_with_timeout(
+        sqlx::query_as::<_, BackupIssue>(
+            "SELECT data_version, row_number, column_name, reason FROM import_issues
+             WHERE upload_id = ?
+             ORDER BY data_version, row_number",
+        )
+        .bind(upload_id_blob)
+        .fetch_all(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading import issues for backup", "Database error"))?;
+
+    Ok(BackupArchive {
+        manifest,
+        species,
+        sightings,
+        issues,
+        located_as_stored: true,
+    })
+}
+
+fn write_archive(archive: &BackupArchive) -> Result<Vec<u8>, ApiError> {
+    build_zip(archive).map_err(|e| ApiError::internal(format!("Failed to write backup: {e}")))
+}
+
+fn build_zip(archive: &BackupArchive) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let entries = [
+        serde_json::to_vec_pretty(&archive.manifest)?,
+        to_lines(&archive.species)?,
+        to_lines(&archive.sightings)?,
+        to_lines(&archive.issues)?,
+    ];
+    let signature = archive_mac(&entries).finalize().into_bytes();
+
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in SIGNED_FILES.iter().zip(&entries) {
+        zip.start_file(*name, options)?;
+        zip.write_all(data)?;
+    }
+    zip.start_file(SIGNATURE_FILE, options)?;
+    zip.write_all(hex::encode(signature).as_bytes())?;
+
+    Ok(zip.finish()?.into_inner())
+}
+
+fn to_lines<T: Serialize>(rows: &[T]) -> Result<Vec<u8>, serde_json::Error> {
+    let mut data = Vec::new();
+    for row in rows {
+        serde_json::to_writer(&mut data, row)?;
+        data.push(b'\n');
+    }
+    Ok(data)
+}
+
+/// HMAC of the [`SIGNED_FILES`] contents, in that order, with each one's
+/// length written first so bytes can't be moved from one file to the next.
+fn archive_mac(entries: &[Vec<u8>]) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(session_secret()).expect("HMAC accepts keys of any length");
+    for data in entries {
+        mac.update(&(data.len() as u64).to_be_bytes());
+        mac.update(data);
+    }
+    mac
+}
+
+/// Reads and checks an archive made by [`download_backup`].
+pub(crate) async fn read_archive(bytes: Vec<u8>) -> Result<BackupArchive, ApiError> {
+    run_blocking(move || read_archive_sync(bytes)).await
+}
+
+fn read_archive_sync(bytes: Vec<u8>) -> Result<BackupArchive, ApiError> {
+    let mut zip = ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| ApiError::bad_request(format!("Invalid backup archive: {e}")))?;
+
+    let entries = SIGNED_FILES
+        .iter()
+        .map(|name| read_entry(&mut zip, name))
+        .collect::<Result<Vec<_>, _>>()?;
+    let [manifest, species, sightings, issues] = &entries[..] else {
+        unreachable!("one entry is read per signed file");
+    };
+
+    let manifest: BackupManifest = serde_json::from_slice(manifest)
+        .map_err(|e| ApiError::bad_request(format!("Invalid {MANIFEST_FILE}: {e}")))?;
+    if manifest.version != BACKUP_FORMAT_VERSION {
+        return Err(ApiError::bad_request(format!(
+            "Unsupported backup version {}",
+            manifest.version
+        )));
+    }
+
+    let signature = hex::decode(read_entry(&mut zip, SIGNATURE_FILE)?.trim_ascii())
+        .map_err(|_| ApiError::bad_request(format!("Invalid {SIGNATURE_FILE}")))?;
+    archive_mac(&entries)
+        .verify_slice(&signature)
+        .map_err(|_| {
+            ApiError::bad_request(
+                "Backup archive wasn't made by this server or has been changed since",
+            )
+        })?;
+
+    let species = parse_lines(SPECIES_FILE, species)?;
+    let mut sightings: Vec<BackupSighting> = parse_lines(SIGHTINGS_FILE, sightings)?;
+    if sightings.len() > MAX_UPLOAD_ROWS {
+        return Err(ApiError::bad_request(format!(
+            "Backup has {} sightings; maximum supported is {MAX_UPLOAD_ROWS}",
+            sightings.len()
+        )));
+    }
+    let located_as_stored = locate_sightings(&mut sightings)?;
+    let issues = parse_lines(ISSUES_FILE, issues)?;
+
+    Ok(BackupArchive {
+        manifest,
+        species,
+        sightings,
+        issues,
+        located_as_stored,
+    })
+}
+
+/// Checks each sighting's time and coordinates, then works out its country,
+/// region and local date from them the way an import does, rather than
+/// trusting what the archive says. Returns whether every sighting ended up
+/// where the archive had it.
+fn locate_sightings(sightings: &mut [BackupSighting]) -> Result<bool, ApiError> {
+    let mut located_as_stored = true;
+    for sighting in sightings {
+        if !(-90.0..=90.0).contains(&sighting.latitude)
+            || !(-180.0..=180.0).contains(&sighting.longitude)
+        {
+            return Err(ApiError::bad_request(format!(
+                "Backup has a sighting outside valid coordinates ({}, {})",
+                sighting.latitude, sighting.longitude
+            )));
+        }
+        let observed_at =
+            normalise_observed_at(&sighting.observed_at, DateFormat::Auto).map_err(|_| {
+                ApiError::bad_request(format!(
+                    "Backup has a sighting with an invalid observed_at: {}",
+                    sighting.observed_at
+                ))
+            })?;
+        located_as_stored &= sighting.observed_at == observed_at;
+        sighting.observed_at = observed_at;
+
+        let (country_code, region_code) = geocode(LatLng {
+            lat: sighting.latitude,
+            lng: sighting.longitude,
+        });
+        let (local_date, year) = local_day(
+            &sighting.observed_at,
+            &country_code,
+            region_code.as_deref(),
+            sighting.latitude,
+            sighting.longitude,
+        );
+        let country_code = Some(country_code.into());
+        let region_code = region_code.map(Into::into);
+        let local_date = Some(local_date.into());
+        let year = Some(year.into());
+        located_as_stored &= sighting.country_code == country_code
+            && sighting.region_code == region_code
+            && sighting.local_date == local_date
+            && sighting.year == year;
+        sighting.country_code = country_code;
+        sighting.region_code = region_code;
+        sighting.local_date = local_date;
+        sighting.year = year;
+    }
+    Ok(located_as_stored)
+}
+
+fn read_entry(zip: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> Result<Vec<u8>, ApiError> {
+    let file = zip
+        .by_name(name)
+        .map_err(|_| ApiError::bad_request(format!("Backup archive is missing {name}")))?;
+    if file.size() > MAX_ENTRY_BYTES {
+        return Err(ApiError::bad_request(format!(
+            "{name} exceeds the backup size limit"
+        )));
+    }
+
+    let mut data = Vec::new();
+    file.take(MAX_ENTRY_BYTES + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| ApiError::bad_request(format!("Failed to read {name}: {e}")))?;
+    if data.len() as u64 > MAX_ENTRY_BYTES {
+        return Err(ApiError::bad_request(format!(
+            "{name} exceeds the backup size limit"
+        )));
+    }
+    Ok(data)
+}
+
+fn parse_lines<T: for<'de> Deserialize<'de>>(name: &str, data: &[u8]) -> Result<Vec<T>, ApiError> {
+    data.split(|&b| b == b'\n')
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_slice(line)
+                .map_err(|e| ApiError::bad_request(format!("Invalid {name} line {}: {e}", idx + 1)))
+        })
+        .collect()
+}
+
+async fn run_blocking<T, F>(task: F) -> Result<T, ApiError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ApiError> + Send + 'static,
+{
+    tokio::time::timeout(ARCHIVE_TIMEOUT, tokio::task::spawn_blocking(task))
+        .await
+        .map_err(|_| ApiError::service_unavailable("Backup archive timed out"))?
+        .map_err(|e| ApiError::internal(format!("Failed to spawn backup task: {e}")))?
+}
@@ -20,9 +20,33 @@ fn main() {
          export const UPLOAD_DETAILS_ROUTE = \"{}\";\n\
          export const UPLOAD_COUNT_ROUTE = \"{}\";\n\
          export const UPLOAD_BBOX_ROUTE = \"{}\";\n\
+         export const UPLOAD_APPEND_ROUTE = \"{}\";\n\
+         export const UPLOAD_BACKUP_ROUTE = \"{}\";\n\
+         export const UPLOAD_RESTORE_ROUTE = \"{}\";\n\
+         export const UPLOAD_RETENTION_ROUTE = \"{}\";\n\
+         export const UPLOAD_SHARES_ROUTE = \"{}\";\n\
+         export const UPLOAD_SHARE_ROUTE = \"{}\";\n\
+         export const UPLOAD_LOCATION_PRIVACY_ROUTE = \"{}\";\n\
+         export const UPLOAD_EDIT_TOKENS_ROUTE = \"{}\";\n\
+         export const UPLOAD_EDIT_TOKEN_ROUTE = \"{}\";\n\
+         export const UPLOAD_EDIT_TOKEN_ROTATE_ROUTE = \"{}\";\n\
+         export const UPLOAD_PASSWORD_ROUTE = \"{}\";\n\
+         export const UPLOAD_UNLOCK_ROUTE = \"{}\";\n\
          export const UPLOAD_SIGHTINGS_ROUTE = \"{}\";\n\
+         export const UPLOAD_SIGHTING_ROUTE = \"{}\";\n\
          export const UPLOAD_STATS_ROUTE = \"{}\";\n\
+         export const UPLOAD_IMPORT_REPORT_ROUTE = \"{}\";\n\
+         export const UPLOAD_EXPORT_GEOJSON_ROUTE = \"{}\";\n\
+         export const UPLOAD_EXPORT_KML_ROUTE = \"{}\";\n\
+         export const UPLOAD_EXPORT_CSV_ROUTE = \"{}\";\n\
+         export const UPLOAD_MAP_PNG_ROUTE = \"{}\";\n\
+         export const UPLOAD_MAP_SVG_ROUTE = \"{}\";\n\
+         export const COLLECTIONS_ROUTE = \"{}\";\n\
+         export const COLLECTION_DETAILS_ROUTE = \"{}\";\n\
+         export const COLLECTION_MEMBER_ROUTE = \"{}\";\n\
          export const TILE_ROUTE = \"{}\";\n\
+         export const DENSITY_TILE_ROUTE = \"{}\";\n\
+         export const CLUSTER_TILE_ROUTE = \"{}\";\n\
          export const FIELDS_ROUTE = \"{}\";\n\
          export const FIELD_VALUES_ROUTE = \"{}\";\n\
          export const DEFAULT_PAGE_SIZE = {};\n\
@@ -33,9 +57,33 @@ fn main() {
         api_constants::UPLOAD_DETAILS_ROUTE,
         api_constants::UPLOAD_COUNT_ROUTE,
         api_constants::UPLOAD_BBOX_ROUTE,
+        api_constants::UPLOAD_APPEND_ROUTE,
+        api_constants::UPLOAD_BACKUP_ROUTE,
+        api_constants::UPLOAD_RESTORE_ROUTE,
+        api_constants::UPLOAD_RETENTION_ROUTE,
+        api_constants::UPLOAD_SHARES_ROUTE,
+        api_constants::UPLOAD_SHARE_ROUTE,
+        api_constants::UPLOAD_LOCATION_PRIVACY_ROUTE,
+        api_constants::UPLOAD_EDIT_TOKENS_ROUTE,
+        api_constants::UPLOAD_EDIT_TOKEN_ROUTE,
+        api_constants::UPLOAD_EDIT_TOKEN_ROTATE_ROUTE,
+        api_constants::UPLOAD_PASSWORD_ROUTE,
+        api_constants::UPLOAD_UNLOCK_ROUTE,
         api_constants::UPLOAD_SIGHTINGS_ROUTE,
+        api_constants::UPLOAD_SIGHTING_ROUTE,
         api_constants::UPLOAD_STATS_ROUTE,
+        api_constants::UPLOAD_IMPORT_REPORT_ROUTE,
+        api_constants::UPLOAD_EXPORT_GEOJSON_ROUTE,
+        api_constants::UPLOAD_EXPORT_KML_ROUTE,
+        api_constants::UPLOAD_EXPORT_CSV_ROUTE,
+        api_constants::UPLOAD_MAP_PNG_ROUTE,
+        api_constants::UPLOAD_MAP_SVG_ROUTE,
+        api_constants::COLLECTIONS_ROUTE,
+        api_constants::COLLECTION_DETAILS_ROUTE,
+        api_constants::COLLECTION_MEMBER_ROUTE,
         api_constants::TILE_ROUTE,
+        api_constants::DENSITY_TILE_ROUTE,
+        api_constants::CLUSTER_TILE_ROUTE,
         api_constants::FIELDS_ROUTE,
         api_constants::FIELD_VALUES_ROUTE,
         api_constants::DEFAULT_PAGE_SIZE,
@@ -0,0 +1,549 @@
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use serde::Deserialize;
+use sqlx::{FromRow, Sqlite};
+use std::collections::HashSet;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::bitmaps::compute_and_store_bitmaps;
+use crate::db::{self, DbPools, DbQueryError};
+use crate::error::ApiError;
+use crate::proto::{pb, Proto};
+use crate::shares::resolve_upload_access;
+use crate::sightings::invalidate_name_index_cache;
+use crate::ticks::recompute_tick_flags;
+use crate::tiles::invalidate_upload_cache;
+use crate::upload::{
+    check_edit_token, compute_grid_cell_visibility_tx, effective_display_name, hash_token,
+    normalise_display_name, parse_upload_id, parse_upload_id_field, verify_upload_access,
+    INITIAL_DATA_VERSION,
+};
+
+pub const MAX_COLLECTION_MEMBERS: usize = 10;
+pub const MAX_COLLECTION_ROWS: i64 = 500_000;
+const COLLECTION_FILENAME: &str = "Collection";
+// Member sightings are copied in id ranges so no single statement runs into
+// the query timeout on large uploads.
+const COPY_CHUNK_IDS: i64 = 20_000;
+
+#[derive(Deserialize)]
+pub struct MemberCredentials {
+    upload_id: String,
+    edit_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateCollectionPayload {
+    display_name: Option<String>,
+    members: Vec<MemberCredentials>,
+}
+
+#[derive(Deserialize)]
+pub struct AddMemberPayload {
+    edit_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct CollectionMemberPath {
+    collection_id: String,
+    upload_id: String,
+}
+
+// A collection shows its sightings with its own view password and location
+// privacy, not its members', and can't take on several members' passwords or
+// jitter salts. So members have to be unprotected: no password, and no
+// location privacy stricter than the defaults a new collection starts with.
+const PROTECTED_UPLOAD_SQL: &str = "view_password_hash IS NOT NULL OR location_mode != 'exact'
+    OR EXISTS (SELECT 1 FROM species_location_rules r WHERE r.upload_id = uploads.id)";
+
+#[derive(FromRow)]
+struct MemberRow {
+    is_collection: bool,
+    row_count: i64,
+    protected: bool,
+}
+
+#[derive(FromRow)]
+struct CollectionRow {
+    filename: String,
+    display_name: Option<String>,
+    row_count: i64,
+    data_version: i64,
+    is_collection: bool,
+}
+
+/// Checks that the caller owns a prospective member and returns its row count.
+async fn authorise_member(
+    pool: &sqlx::SqlitePool,
+    upload_uuid: &Uuid,
+    edit_token: &str,
+) -> Result<i64, ApiError> {
+    let sql = format!(
+        "SELECT is_collection, row_count, ({PROTECTED_UPLOAD_SQL}) AS protected
+        FROM uploads WHERE id = ?"
+    );
+    let member = db::query_with_timeout(
+        sqlx::query_as::<_, MemberRow>(&sql)
+            .bind(&upload_uuid.as_bytes()[..])
+            .fetch_optional(pool),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading collection member", "Database error"))?
+    .ok_or_else(|| ApiError::not_found(format!("Upload {upload_uuid} not found")))?;
+
+    let authorised = verify_upload_access(pool, &upload_uuid.to_string(), edit_token)
+        .await
+        .map_err(|e| e.into_api_error("verifying member edit token", "Database error"))?;
+    if !authorised {
+        return Err(ApiError::forbidden(format!(
+            "Invalid edit token for upload {upload_uuid}"
+        )));
+    }
+
+    if member.is_collection {
+        return Err(ApiError::bad_request(
+            "Collections can't contain other collections",
+        ));
+    }
+    if member.protected {
+        return Err(ApiError::bad_request(format!(
+            "Upload {upload_uuid} has a view password or location privacy settings, so it can't be added to a collection"
+        )));
+    }
+
+    Ok(member.row_count)
+}
+
+/// Refuses to protect an upload that's in a collection, since the collection
+/// would go on showing its sightings without that protection.
+pub(crate) async fn ensure_not_collection_member(
+    pool: &sqlx::SqlitePool,
+    upload_uuid: &Uuid,
+) -> Result<(), ApiError> {
+    let is_member = db::query_with_timeout(
+        sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM collection_members WHERE upload_id = ?)",
+        )
+        .bind(&upload_uuid.as_bytes()[..])
+        .fetch_one(pool),
+    )
+    .await
+    .map_err(|e| e.into_api_error("checking collection membership", "Database error"))?;
+    if is_member {
+        return Err(ApiError::bad_request(
+            "Remove this upload from its collections before giving it a view password or location privacy settings",
+        ));
+    }
+    Ok(())
+}
+
+async fn load_collection(
+    pool: &sqlx::SqlitePool,
+    collection_uuid: &Uuid,
+) -> Result<CollectionRow, ApiError> {
+    db::query_with_timeout(
+        sqlx::query_as::<_, CollectionRow>(
+            "SELECT filename, display_name, row_count, data_version, is_collection FROM uploads WHERE id = ?",
+        )
+        .bind(&collection_uuid.as_bytes()[..])
+        .fetch_optional(pool),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading collection", "Database error"))?
+    .filter(|row| row.is_collection)
+    .ok_or_else(|| ApiError::not_found("Collection not found"))
+}
+
+async fn load_member_ids<'c, E>(
+    executor: E,
+    collection_id_blob: &[u8],
+) -> Result<Vec<Vec<u8>>, DbQueryError>
+where
+    E: sqlx::Executor<'c, Database = Sqlite>,
+{
+    db::query_with_timeout(
+        sqlx::query_scalar::<_, Vec<u8>>(
+            "SELECT cm.upload_id FROM collection_members cm
+            JOIN uploads u ON u.id = cm.upload_id
+            WHERE cm.collection_id = ?
+            ORDER BY u.created_at, u.id",
+        )
+        .bind(collection_id_blob)
+        .fetch_all(executor),
+    )
+    .await
+}
+
+async fn collection_response(
+    pool: &sqlx::SqlitePool,
+    collection_uuid: &Uuid,
+    edit_token: Option<String>,
+) -> Result<pb::CollectionResponse, ApiError> {
+    let row = load_collection(pool, collection_uuid).await?;
+    let member_upload_ids = load_member_ids(pool, &collection_uuid.as_bytes()[..])
+        .await
+        .map_err(|e| e.into_api_error("loading collection members", "Database error"))?
+        .iter()
+        .filter_map(|id| Uuid::from_slice(id).ok())
+        .map(|id| id.to_string())
+        .collect();
+
+    Ok(pb::CollectionResponse {
+        collection_id: collection_uuid.to_string(),
+        title: effective_display_name(row.display_name, &row.filename),
+        row_count: row.row_count,
+        data_version: row.data_version,
+        member_upload_ids,
+        edit_token,
+    })
+}
+
+pub async fn create_collection(
+    State(pools): State<DbPools>,
+    Json(payload): Json<CreateCollectionPayload>,
+) -> Result<Proto<pb::CollectionResponse>, ApiError> {
+    if payload.members.is_empty() {
+        return Err(ApiError::bad_request(
+            "A collection needs at least one member upload",
+        ));
+    }
+    if payload.members.len() > MAX_COLLECTION_MEMBERS {
+        return Err(ApiError::bad_request(format!(
+            "A collection can have at most {MAX_COLLECTION_MEMBERS} member uploads"
+        )));
+    }
+
+    let display_name = payload
+        .display_name
+        .map(|name| normalise_display_name(Some(name)))
+        .transpose()?;
+
+    let mut member_ids = Vec::with_capacity(payload.members.len());
+    let mut seen = HashSet::new();
+    let mut total_rows = 0i64;
+    for member in &payload.members {
+        let upload_uuid = parse_upload_id(&member.upload_id)?;
+        if !seen.insert(upload_uuid) {
+            continue;
+        }
+        total_rows += authorise_member(pools.read(), &upload_uuid, &member.edit_token).await?;
+        member_ids.push(upload_uuid);
+    }
+    if total_rows > MAX_COLLECTION_ROWS {
+        return Err(ApiError::bad_request(format!(
+            "Collections are limited to {MAX_COLLECTION_ROWS} sightings in total"
+        )));
+    }
+
+    let collection_uuid = Uuid::new_v4();
+    let collection_id_blob = collection_uuid.as_bytes();
+    let edit_token = Uuid::new_v4().to_string();
+
+    let mut tx = db::query_with_timeout(pools.write().begin())
+        .await
+        .map_err(|e| e.into_api_error("starting collection transaction", "Database error"))?;
+
+    db::query_with_timeout(
+        sqlx::query(
+            "INSERT INTO uploads (id, filename, display_name, edit_token_hash, data_version, is_collection) VALUES (?, ?, ?, ?, ?, 1)",
+        )
+        .bind(&collection_id_blob[..])
+        .bind(COLLECTION_FILENAME)
+        .bind(&display_name)
+        .bind(hash_token(&edit_token))
+        .bind(INITIAL_DATA_VERSION)
+        .execute(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("creating collection record", "Database error"))?;
+
+    for member_id in &member_ids {
+        db::query_with_timeout(
+            sqlx::query("INSERT INTO collection_members (collection_id, upload_id) VALUES (?, ?)")
+                .bind(&collection_id_blob[..])
+                .bind(&member_id.as_bytes()[..])
+                .execute(&mut *tx),
+        )
+        .await
+        .map_err(|e| e.into_api_error("adding collection member", "Database error"))?;
+    }
+
+    db::query_with_timeout(tx.commit())
+        .await
+        .map_err(|e| e.into_api_error("committing collection transaction", "Database error"))?;
+
+    rebuild_collection(pools.write(), &collection_uuid).await?;
+
+    info!(
+        "Created collection {} with {} member(s)",
+        collection_uuid,
+        member_ids.len()
+    );
+
+    Ok(Proto::new(
+        collection_response(pools.write(), &collection_uuid, Some(edit_token)).await?,
+    ))
+}
+
+pub async fn get_collection(
+    State(pools): State<DbPools>,
+    Path(collection_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Proto<pb::CollectionResponse>, ApiError> {
+    let access = resolve_upload_access(pools.read(), &collection_id, &headers).await?;
+    let mut response = collection_response(pools.read(), access.upload_uuid(), None).await?;
+    // Member IDs would open the full uploads behind a share link
+    if access.is_shared() {
+        response.collection_id = collection_id;
+        response.member_upload_ids.clear();
+    }
+    Ok(Proto::new(response))
+}
+
+pub async fn add_collection_member(
+    State(pools): State<DbPools>,
+    Path(path): Path<CollectionMemberPath>,
+    headers: HeaderMap,
+    Json(payload): Json<AddMemberPayload>,
+) -> Result<Proto<pb::CollectionResponse>, ApiError> {
+    let collection_uuid = parse_upload_id_field(&path.collection_id, "collection_id")?;
+    let upload_uuid = parse_upload_id(&path.upload_id)?;
+    check_edit_token(pools.read(), &headers, &path.collection_id).await?;
+    let collection = load_collection(pools.read(), &collection_uuid).await?;
+
+    let member_ids = load_member_ids(pools.read(), &collection_uuid.as_bytes()[..])
+        .await
+        .map_err(|e| e.into_api_error("loading collection members", "Database error"))?;
+    let already_member = member_ids
+        .iter()
+        .any(|id| id.as_slice() == upload_uuid.as_bytes());
+    if !already_member && member_ids.len() >= MAX_COLLECTION_MEMBERS {
+        return Err(ApiError::bad_request(format!(
+            "A collection can have at most {MAX_COLLECTION_MEMBERS} member uploads"
+        )));
+    }
+
+    let member_rows = authorise_member(pools.read(), &upload_uuid, &payload.edit_token).await?;
+    if !already_member && collection.row_count + member_rows > MAX_COLLECTION_ROWS {
+        return Err(ApiError::bad_request(format!(
+            "Collections are limited to {MAX_COLLECTION_ROWS} sightings in total"
+        )));
+    }
+
+    if !already_member {
+        db::query_with_timeout(
+            sqlx::query("INSERT INTO collection_members (collection_id, upload_id) VALUES (?, ?)")
+                .bind(&collection_uuid.as_bytes()[..])
+                .bind(&upload_uuid.as_bytes()[..])
+                .execute(pools.write()),
+        )
+        .await
+        .map_err(|e| e.into_api_error("adding collection member", "Database error"))?;
+
+        rebuild_collection(pools.write(), &collection_uuid).await?;
+    }
+
+    Ok(Proto::new(
+        collection_response(pools.write(), &collection_uuid, None).await?,
+    ))
+}
+
+pub async fn remove_collection_member(
+    State(pools): State<DbPools>,
+    Path(path): Path<CollectionMemberPath>,
+    headers: HeaderMap,
+) -> Result<Proto<pb::CollectionResponse>, ApiError> {
+    let collection_uuid = parse_upload_id_field(&path.collection_id, "collection_id")?;
+    let upload_uuid = parse_upload_id(&path.upload_id)?;
+    check_edit_token(pools.read(), &headers, &path.collection_id).await?;
+    load_collection(pools.read(), &collection_uuid).await?;
+
+    let removed = db::query_with_timeout(
+        sqlx::query("DELETE FROM collection_members WHERE collection_id = ? AND upload_id = ?")
+            .bind(&collection_uuid.as_bytes()[..])
+            .bind(&upload_uuid.as_bytes()[..])
+            .execute(pools.write()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("removing collection member", "Database error"))?
+    .rows_affected();
+
+    if removed == 0 {
+        return Err(ApiError::not_found(
+            "Upload is not a member of this collection",
+        ));
+    }
+
+    rebuild_collection(pools.write(), &collection_uuid).await?;
+
+    Ok(Proto::new(
+        collection_response(pools.write(), &collection_uuid, None).await?,
+    ))
+}
+
+/// Replaces a collection's sightings with fresh copies of its members' and
+/// recomputes ticks over the combined timeline.
+///
+/// A sighting that appears in more than one member (the same export uploaded
+/// twice, say) is only copied once, from the oldest member upload.
+pub async fn rebuild_collection(
+    pool: &sqlx::SqlitePool,
+    collection_uuid: &Uuid,
+) -> Result<(), ApiError> {
+    let collection_id_blob = &collection_uuid.as_bytes()[..];
+
+    let mut tx = db::query_with_timeout(pool.begin())
+        .await
+        .map_err(|e| e.into_api_error("starting collection rebuild", "Database error"))?;
+
+    // Members can grow past the limit after joining, by appends or edits;
+    // the collection is then left as it was until one is removed
+    let member_rows = db::query_with_timeout(
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM sightings
+            WHERE upload_id IN (SELECT upload_id FROM collection_members WHERE collection_id = ?)",
+        )
+        .bind(collection_id_blob)
+        .fetch_one(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("counting member sightings", "Database error"))?;
+    if member_rows > MAX_COLLECTION_ROWS {
+        return Err(ApiError::bad_request(format!(
+            "Collection members have {member_rows} sightings between them, but collections are limited to {MAX_COLLECTION_ROWS} in total; remove a member to rebuild it"
+        )));
+    }
+
+    db::query_with_timeout(
+        sqlx::query("DELETE FROM sightings WHERE upload_id = ?")
+            .bind(collection_id_blob)
+            .execute(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("clearing collection sightings", "Database error"))?;
+
+    let member_ids = load_member_ids(&mut *tx, collection_id_blob)
+        .await
+        .map_err(|e| e.into_api_error("loading collection members", "Database error"))?;
+
+    for member_id in &member_ids {
+        let (min_id, max_id) = db::query_with_timeout(
+            sqlx::query_as::<_, (Option<i64>, Option<i64>)>(
+                "SELECT MIN(id), MAX(id) FROM sightings WHERE upload_id = ?",
+            )
+            .bind(member_id)
+            .fetch_one(&mut *tx),
+        )
+        .await
+        .map_err(|e| e.into_api_error("loading member id range", "Database error"))?;
+        let (Some(min_id), Some(max_id)) = (min_id, max_id) else {
+            continue;
+        };
+
+        let mut start = min_id;
+        while start <= max_id {
+            let end = start.saturating_add(COPY_CHUNK_IDS - 1);
+            db::query_with_timeout(
+                sqlx::query(
+                    "INSERT INTO sightings (upload_id, sighting_uuid, species_id, count, latitude, longitude, country_code, region_code, observed_at, local_date, year, vis_rank)
+                    SELECT ?, s.sighting_uuid, s.species_id, s.count, s.latitude, s.longitude, s.country_code, s.region_code, s.observed_at, s.local_date, s.year, s.vis_rank
+                    FROM sightings s
+                    WHERE s.upload_id = ? AND s.id BETWEEN ? AND ?
+                    AND NOT EXISTS (
+                        SELECT 1 FROM sightings c WHERE c.upload_id = ? AND c.sighting_uuid = s.sighting_uuid
+                    )
+                    ORDER BY s.id",
+                )
+                .bind(collection_id_blob)
+                .bind(member_id)
+                .bind(start)
+                .bind(end)
+                .bind(collection_id_blob)
+                .execute(&mut *tx),
+            )
+            .await
+            .map_err(|e| e.into_api_error("copying member sightings", "Database error"))?;
+            start = end.saturating_add(1);
+        }
+    }
+
+    recompute_tick_flags(&mut tx, collection_id_blob, None)
+        .await
+        .map_err(|e| e.into_api_error("computing collection ticks", "Database error"))?;
+
+    compute_grid_cell_visibility_tx(&mut tx, collection_id_blob)
+        .await
+        .map_err(|e| e.into_api_error("computing grid cell visibility", "Database error"))?;
+
+    db::query_with_timeout(
+        sqlx::query(
+            "UPDATE uploads SET
+                row_count = (SELECT COUNT(*) FROM sightings WHERE upload_id = ?),
+                data_version = data_version + 1
+            WHERE id = ?",
+        )
+        .bind(collection_id_blob)
+        .bind(collection_id_blob)
+        .execute(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("updating collection metadata", "Database error"))?;
+
+    db::query_with_timeout(tx.commit())
+        .await
+        .map_err(|e| e.into_api_error("committing collection rebuild", "Database error"))?;
+
+    if let Err(e) = compute_and_store_bitmaps(pool, collection_id_blob).await {
+        error!(
+            "Failed to compute collection tick bitmaps: {}",
+            e.body.error
+        );
+    }
+
+    let collection_id = collection_uuid.to_string();
+    invalidate_upload_cache(&collection_id).await;
+    invalidate_name_index_cache(&collection_id);
+
+    Ok(())
+}
+
+/// Collections that include the given upload. Look these up before deleting
+/// an upload, since the membership rows go with it.
+pub async fn collections_containing(
+    pool: &sqlx::SqlitePool,
+    upload_id_blob: &[u8],
+) -> Result<Vec<Uuid>, DbQueryError> {
+    let ids = db::query_with_timeout(
+        sqlx::query_scalar::<_, Vec<u8>>(
+            "SELECT collection_id FROM collection_members WHERE upload_id = ?",
+        )
+        .bind(upload_id_blob)
+        .fetch_all(pool),
+    )
+    .await?;
+
+    Ok(ids
+        .iter()
+        .filter_map(|id| Uuid::from_slice(id).ok())
+        .collect())
+}
+
+/// Rebuilds collections in the background after one of their members changed.
+pub fn spawn_collection_rebuilds(pool: sqlx::SqlitePool, collection_ids: Vec<Uuid>) {
+    if collection_ids.is_empty() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        for collection_uuid in collection_ids {
+            if let Err(e) = rebuild_collection(&pool, &collection_uuid).await {
+                error!(
+                    "Failed to rebuild collection {}: {}",
+                    collection_uuid, e.body.error
+                );
+            }
+        }
+    });
+}
@@ -1,4 +1,9 @@
 use std::env;
+use std::path::PathBuf;
+
+use crate::sources::DateFormat;
+
+const DEFAULT_RETENTION_DAYS: i64 = 365;
 
 /// Parses the port number from environment variables.
 /// Checks PORT first, then REDGROUSE_BACKEND_PORT, defaulting to 3001.
@@ -15,3 +20,57 @@ pub fn parse_port() -> anyhow::Result<u16> {
         )
     })
 }
+
+/// Days an upload is kept after it was last viewed, from
+/// REDGROUSE_DATA_RETENTION_DAYS, defaulting to 365.
+pub fn retention_days() -> i64 {
+    env::var("REDGROUSE_DATA_RETENTION_DAYS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_RETENTION_DAYS)
+}
+
+/// Key for signing view password sessions, from REDGROUSE_SESSION_SECRET.
+/// Without one a random key is used, and sessions end when the server
+/// restarts.
+pub fn session_secret() -> Option<String> {
+    env::var("REDGROUSE_SESSION_SECRET")
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+/// The eBird/Clements checklist to match species names against, from
+/// REDGROUSE_TAXONOMY_PATH. Without one, the small bundled sample is used.
+pub fn taxonomy_path() -> Option<PathBuf> {
+    env::var_os("REDGROUSE_TAXONOMY_PATH")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Time zone boundary polygons (timezone-boundary-builder's GeoJSON), from
+/// REDGROUSE_TZ_BOUNDARIES_PATH. Without them, zones are looked up by
+/// country and region, which can be an hour out near zone lines.
+pub fn tz_boundaries_path() -> Option<PathBuf> {
+    env::var_os("REDGROUSE_TZ_BOUNDARIES_PATH")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Land polygons (Natural Earth's `ne_50m_land` GeoJSON) for the coastlines
+/// on static maps, from REDGROUSE_LAND_POLYGONS_PATH. Without them, land is
+/// taken from the country boundaries and only drawn on zoomed out maps.
+pub fn land_polygons_path() -> Option<PathBuf> {
+    env::var_os("REDGROUSE_LAND_POLYGONS_PATH")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Day/month order for year-last dates when a file is too long to wait for
+/// one that settles it, from REDGROUSE_DEFAULT_DATE_FORMAT (`dmy` or `mdy`).
+/// Without one, those dates are skipped as ambiguous.
+pub fn default_date_format() -> DateFormat {
+    env::var("REDGROUSE_DEFAULT_DATE_FORMAT")
+        .ok()
+        .and_then(|value| DateFormat::parse(&value))
+        .unwrap_or_default()
+}
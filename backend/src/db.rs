@@ -5,7 +5,7 @@ use std::future::Future;
 use std::str::FromStr;
 use std::time::Duration;
 use tokio::time;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 const WAL_AUTOCHECKPOINT_PAGES: usize = 1024;
 const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
@@ -78,6 +78,83 @@ pub async fn init_pool(database_url: &str) -> Result<DbPools, sqlx::Error> {
 pub async fn run_migrations(pools: &DbPools) -> Result<(), sqlx::Error> {
     sqlx::migrate!("./migrations").run(pools.write()).await?;
     info!("Database migrations completed");
+    warn_removed_duplicates(pools).await?;
+    // Species reference the bundled taxa, so they have to be in place before
+    // anything is uploaded
+    crate::taxonomy::sync_taxa(pools.write()).await
+}
+
+/// Migration 004 moves repeated sighting IDs aside rather than deleting them
+/// outright; keep reminding the operator until they've been looked at.
+async fn warn_removed_duplicates(pools: &DbPools) -> Result<(), sqlx::Error> {
+    let table_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'removed_duplicate_sightings')",
+    )
+    .fetch_one(pools.read())
+    .await?;
+    if !table_exists {
+        return Ok(());
+    }
+
+    let (removed, uploads): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*), COUNT(DISTINCT upload_id) FROM removed_duplicate_sightings",
+    )
+    .fetch_one(pools.read())
+    .await?;
+    if removed > 0 {
+        warn!(
+            "{} duplicate sightings in {} uploads were removed when sighting IDs became unique; \
+             review removed_duplicate_sightings, then drop it to silence this warning",
+            removed, uploads
+        );
+    }
+    Ok(())
+}
+
+/// Recomputes ticks, grid cell picks and bitmaps for the uploads migration
+/// 004 removed duplicate sightings from. An upload stays listed until its
+/// bitmaps are stored, so a failure is retried at the next startup.
+pub async fn recompute_deduplicated_uploads(pools: &DbPools) -> Result<(), DbQueryError> {
+    let upload_ids: Vec<Vec<u8>> = query_with_timeout(
+        sqlx::query_scalar("SELECT upload_id FROM pending_tick_recomputes").fetch_all(pools.read()),
+    )
+    .await?;
+
+    for upload_id_blob in &upload_ids {
+        let mut tx = query_with_timeout(pools.write().begin()).await?;
+        crate::ticks::recompute_tick_flags(&mut tx, upload_id_blob, None).await?;
+        crate::upload::compute_grid_cell_visibility_tx(&mut tx, upload_id_blob).await?;
+        query_with_timeout(
+            sqlx::query("UPDATE uploads SET data_version = data_version + 1 WHERE id = ?")
+                .bind(upload_id_blob)
+                .execute(&mut *tx),
+        )
+        .await?;
+        query_with_timeout(tx.commit()).await?;
+
+        if let Err(e) =
+            crate::bitmaps::compute_and_store_bitmaps(pools.write(), upload_id_blob).await
+        {
+            error!("Failed to compute tick bitmaps: {}", e.body.error);
+            continue;
+        }
+        query_with_timeout(
+            sqlx::query("DELETE FROM pending_tick_recomputes WHERE upload_id = ?")
+                .bind(upload_id_blob)
+                .execute(pools.write()),
+        )
+        .await?;
+        if let Ok(upload_uuid) = uuid::Uuid::from_slice(upload_id_blob) {
+            crate::tiles::invalidate_upload_cache(&upload_uuid.to_string()).await;
+        }
+    }
+
+    if !upload_ids.is_empty() {
+        info!(
+            "Recomputed ticks for {} uploads with removed duplicate sightings",
+            upload_ids.len()
+        );
+    }
     Ok(())
 }
 
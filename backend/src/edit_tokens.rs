@@ -0,0 +1,271 @@
+//! Managing who can edit an upload. Besides the token handed out when the
+//! upload was created, its holder can add named tokens (one per device or
+//! person) and revoke them individually. Any token can be rotated by whoever
+//! holds it, which swaps it for a new one and stops the old one working.
+//! Rotating the original token also revokes every named one, so a leaked
+//! original can be fully recovered from.
+
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use serde::Deserialize;
+use sqlx::{FromRow, Sqlite};
+use uuid::Uuid;
+
+use crate::db::{self, DbPools, DbQueryError};
+use crate::error::ApiError;
+use crate::proto::{pb, Proto};
+use crate::upload::{authorise_edit, hash_token, parse_upload_id, EditTokenMatch};
+
+pub(crate) const MAX_EDIT_TOKENS_PER_UPLOAD: i64 = 20;
+const MAX_NAME_LENGTH: usize = 64;
+
+#[derive(Deserialize)]
+pub struct EditTokenPayload {
+    name: String,
+}
+
+#[derive(Deserialize)]
+pub struct EditTokenPath {
+    upload_id: String,
+    token_id: i64,
+}
+
+#[derive(FromRow)]
+struct EditTokenRow {
+    id: i64,
+    name: String,
+    created_at: String,
+}
+
+impl EditTokenRow {
+    fn into_proto(self, token: Option<String>, current: bool) -> pb::EditToken {
+        pb::EditToken {
+            token_id: Some(self.id),
+            name: Some(self.name),
+            token,
+            created_at: self.created_at,
+            current,
+        }
+    }
+}
+
+/// Only the original token can hand out or take away access, so a named
+/// token can't mint others that outlive its own revocation.
+fn require_primary(current: EditTokenMatch) -> Result<(), ApiError> {
+    match current {
+        EditTokenMatch::Primary => Ok(()),
+        EditTokenMatch::Named(_) => Err(ApiError::forbidden(
+            "Only the upload's original edit token can manage named tokens",
+        )),
+    }
+}
+
+pub(crate) fn normalise_name(name: &str) -> Result<String, ApiError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ApiError::bad_request("Edit token name must not be empty"));
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(ApiError::bad_request(format!(
+            "Edit token name must be at most {MAX_NAME_LENGTH} characters"
+        )));
+    }
+    Ok(name.to_string())
+}
+
+pub async fn list_edit_tokens(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Proto<pb::EditTokenList>, ApiError> {
+    let current = authorise_edit(pools.read(), &headers, &upload_id).await?;
+    let upload_uuid = parse_upload_id(&upload_id)?;
+
+    let created_at = db::query_with_timeout(
+        sqlx::query_scalar::<_, String>("SELECT created_at FROM uploads WHERE id = ?")
+            .bind(&upload_uuid.as_bytes()[..])
+            .fetch_one(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading upload", "Database error"))?;
+    let rows = db::query_with_timeout(
+        sqlx::query_as::<_, EditTokenRow>(
+            "SELECT id, name, created_at FROM edit_tokens WHERE upload_id = ? ORDER BY id",
+        )
+        .bind(&upload_uuid.as_bytes()[..])
+        .fetch_all(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("listing edit tokens", "Database error"))?;
+
+    let primary = pb::EditToken {
+        token_id: None,
+        name: None,
+        token: None,
+        created_at,
+        current: current == EditTokenMatch::Primary,
+    };
+    let named = rows.into_iter().map(|row| {
+        let is_current = current == EditTokenMatch::Named(row.id);
+        row.into_proto(None, is_current)
+    });
+    Ok(Proto::new(pb::EditTokenList {
+        tokens: std::iter::once(primary).chain(named).collect(),
+    }))
+}
+
+pub async fn create_edit_token(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<EditTokenPayload>,
+) -> Result<Proto<pb::EditToken>, ApiError> {
+    require_primary(authorise_edit(pools.read(), &headers, &upload_id).await?)?;
+    let upload_uuid = parse_upload_id(&upload_id)?;
+    let name = normalise_name(&payload.name)?;
+
+    let existing = db::query_with_timeout(
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM edit_tokens WHERE upload_id = ?")
+            .bind(&upload_uuid.as_bytes()[..])
+            .fetch_one(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("counting edit tokens", "Database error"))?;
+    if existing >= MAX_EDIT_TOKENS_PER_UPLOAD {
+        return Err(ApiError::bad_request(format!(
+            "An upload can have at most {MAX_EDIT_TOKENS_PER_UPLOAD} named edit tokens"
+        )));
+    }
+
+    let token = issue_edit_token(pools.write(), &upload_uuid.as_bytes()[..], &name)
+        .await
+        .map_err(|e| e.into_api_error("creating edit token", "Database error"))?;
+
+    Ok(Proto::new(token))
+}
+
+/// Adds a named token with a new random value, returned only this once.
+pub(crate) async fn issue_edit_token<'c, E>(
+    executor: E,
+    upload_id_blob: &[u8],
+    name: &str,
+) -> Result<pb::EditToken, DbQueryError>
+where
+    E: sqlx::Executor<'c, Database = Sqlite>,
+{
+    let token = Uuid::new_v4().to_string();
+    let row = db::query_with_timeout(
+        sqlx::query_as::<_, EditTokenRow>(
+            "INSERT INTO edit_tokens (upload_id, token_hash, name) VALUES (?, ?, ?)
+            RETURNING id, name, created_at",
+        )
+        .bind(upload_id_blob)
+        .bind(hash_token(&token))
+        .bind(name)
+        .fetch_one(executor),
+    )
+    .await?;
+
+    Ok(row.into_proto(Some(token), false))
+}
+
+pub async fn revoke_edit_token(
+    State(pools): State<DbPools>,
+    Path(path): Path<EditTokenPath>,
+    headers: HeaderMap,
+) -> Result<Proto<pb::DeleteResponse>, ApiError> {
+    require_primary(authorise_edit(pools.read(), &headers, &path.upload_id).await?)?;
+    let upload_uuid = parse_upload_id(&path.upload_id)?;
+
+    let result = db::query_with_timeout(
+        sqlx::query("DELETE FROM edit_tokens WHERE id = ? AND upload_id = ?")
+            .bind(path.token_id)
+            .bind(&upload_uuid.as_bytes()[..])
+            .execute(pools.write()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("revoking edit token", "Database error"))?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("Edit token not found"));
+    }
+
+    Ok(Proto::new(pb::DeleteResponse { deleted: true }))
+}
+
+/// Replaces the token the request was made with. Rotating the original token
+/// revokes the named ones too, since whoever had it could have created them.
+pub async fn rotate_edit_token(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Proto<pb::EditToken>, ApiError> {
+    let current = authorise_edit(pools.read(), &headers, &upload_id).await?;
+    let upload_uuid = parse_upload_id(&upload_id)?;
+    let token = Uuid::new_v4().to_string();
+
+    let rotated = match current {
+        EditTokenMatch::Primary => rotate_primary_token(&pools, &upload_uuid, &token)
+            .await?
+            .map(|created_at| pb::EditToken {
+                token_id: None,
+                name: None,
+                token: Some(token),
+                created_at,
+                current: true,
+            }),
+        EditTokenMatch::Named(token_id) => db::query_with_timeout(
+            sqlx::query_as::<_, EditTokenRow>(
+                "UPDATE edit_tokens SET token_hash = ? WHERE id = ? AND upload_id = ?
+                RETURNING id, name, created_at",
+            )
+            .bind(hash_token(&token))
+            .bind(token_id)
+            .bind(&upload_uuid.as_bytes()[..])
+            .fetch_optional(pools.write()),
+        )
+        .await
+        .map_err(|e| e.into_api_error("rotating edit token", "Database error"))?
+        .map(|row| row.into_proto(Some(token), true)),
+    };
+
+    // The token may have been revoked since it was checked
+    rotated
+        .map(Proto::new)
+        .ok_or_else(|| ApiError::not_found("Edit token not found"))
+}
+
+async fn rotate_primary_token(
+    pools: &DbPools,
+    upload_uuid: &Uuid,
+    token: &str,
+) -> Result<Option<String>, ApiError> {
+    let upload_id_blob = &upload_uuid.as_bytes()[..];
+    let mut tx = db::query_with_timeout(pools.write().begin())
+        .await
+        .map_err(|e| e.into_api_error("starting edit token rotation", "Database error"))?;
+
+    let created_at = db::query_with_timeout(
+        sqlx::query_scalar::<_, String>(
+            "UPDATE uploads SET edit_token_hash = ? WHERE id = ? RETURNING created_at",
+        )
+        .bind(hash_token(token))
+        .bind(upload_id_blob)
+        .fetch_optional(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("rotating edit token", "Database error"))?;
+
+    db::query_with_timeout(
+        sqlx::query("DELETE FROM edit_tokens WHERE upload_id = ?")
+            .bind(upload_id_blob)
+            .execute(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("revoking named edit tokens", "Database error"))?;
+
+    db::query_with_timeout(tx.commit())
+        .await
+        .map_err(|e| e.into_api_error("committing edit token rotation", "Database error"))?;
+    Ok(created_at)
+}
@@ -54,6 +54,11 @@ impl ApiError {
         Self::with_code(StatusCode::FORBIDDEN, message, "FORBIDDEN")
     }
 
+    /// The upload has a view password and the request didn't unlock it.
+    pub fn password_required(message: impl Into<String>) -> Self {
+        Self::with_code(StatusCode::UNAUTHORIZED, message, "PASSWORD_REQUIRED")
+    }
+
     pub fn service_unavailable(message: impl Into<String>) -> Self {
         Self::with_code(
             StatusCode::SERVICE_UNAVAILABLE,
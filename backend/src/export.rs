@@ -0,0 +1,680 @@
+//! GeoJSON, KML and CSV downloads of an upload's sightings, filtered and
+//! sorted the same way as the sightings table. GeoJSON and KML are for desktop
+//! GIS tools and Google Earth; CSV comes in a Birda layout that can be
+//! uploaded again, or eBird's record format for submitting checklists.
+//!
+//! Exports can cover a whole upload, so rows are streamed from the database
+//! into the response in chunks rather than collected first.
+
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Response};
+use bytes::Bytes;
+use futures::channel::mpsc;
+use futures::SinkExt;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::db::{self, DbPools};
+use crate::error::ApiError;
+use crate::filter::{build_filter_clause, FilterRequest, FilterSql, TableAliases, TickVisibility};
+use crate::location_privacy::LocationPolicy;
+use crate::shares::{resolve_upload_access, UploadAccess};
+use crate::sightings::{parse_sort_direction, wrap_nullable_sort_column, SortField};
+use crate::timezone;
+use crate::upload::effective_display_name;
+
+// Flush to the client roughly every 64 KiB.
+const CHUNK_BYTES: usize = 64 * 1024;
+// Rows read per query; the read connection goes back to the pool in between
+const EXPORT_PAGE_ROWS: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    filter: Option<String>,
+    year_tick_year: Option<i32>,
+    country_tick_country: Option<String>,
+    tick_filter: Option<String>,
+    sort_field: Option<SortField>,
+    sort_dir: Option<String>,
+    layout: Option<CsvLayout>,
+}
+
+/// Column layout of a CSV export.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvLayout {
+    /// The columns `upload_csv` reads back for Birda exports
+    #[default]
+    Birda,
+    /// eBird Record Format (Extended), for eBird's checklist import
+    Ebird,
+}
+
+const BIRDA_HEADER: &str = "sightingId,date,longitude,latitude,commonName,scientificName,count\n";
+
+#[derive(Clone, Copy)]
+enum ExportFormat {
+    GeoJson,
+    Kml,
+    Csv(CsvLayout),
+}
+
+impl ExportFormat {
+    const fn content_type(self) -> &'static str {
+        match self {
+            Self::GeoJson => "application/geo+json",
+            Self::Kml => "application/vnd.google-earth.kml+xml",
+            Self::Csv(_) => "text/csv; charset=utf-8",
+        }
+    }
+
+    const fn extension(self) -> &'static str {
+        match self {
+            Self::GeoJson => "geojson",
+            Self::Kml => "kml",
+            Self::Csv(_) => "csv",
+        }
+    }
+
+    fn header(self, title: &str) -> String {
+        match self {
+            Self::GeoJson => format!(
+                "{{\"type\":\"FeatureCollection\",\"name\":{},\"features\":[\n",
+                json!(title)
+            ),
+            Self::Kml => format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n<name>{}</name>\n",
+                xml_escape(title)
+            ),
+            Self::Csv(CsvLayout::Birda) => BIRDA_HEADER.to_string(),
+            // eBird's import rejects files with a header row
+            Self::Csv(CsvLayout::Ebird) => String::new(),
+        }
+    }
+
+    fn write_row(self, out: &mut String, row: &ExportRow, first: bool) {
+        match self {
+            Self::GeoJson => {
+                if !first {
+                    out.push_str(",\n");
+                }
+                out.push_str(&geojson_feature(row).to_string());
+            }
+            Self::Kml => write_placemark(out, row),
+            Self::Csv(CsvLayout::Birda) => write_birda_record(out, row),
+            Self::Csv(CsvLayout::Ebird) => write_ebird_record(out, row),
+        }
+    }
+
+    const fn footer(self) -> &'static str {
+        match self {
+            Self::GeoJson => "\n]}\n",
+            Self::Kml => "</Document>\n</kml>\n",
+            Self::Csv(_) => "",
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct ExportRow {
+    id: i64,
+    sighting_uuid: Vec<u8>,
+    common_name: String,
+    scientific_name: String,
+    count: Option<i64>,
+    latitude: f64,
+    longitude: f64,
+    country_code: Option<String>,
+    region_code: Option<String>,
+    observed_at: String,
+    local_date: Option<String>,
+    lifer: bool,
+    year_tick: bool,
+    country_tick: bool,
+}
+
+impl ExportRow {
+    fn sighting_uuid(&self) -> String {
+        Uuid::from_slice(&self.sighting_uuid)
+            .map(|uuid| uuid.to_string())
+            .unwrap_or_default()
+    }
+}
+
+fn geojson_feature(row: &ExportRow) -> serde_json::Value {
+    json!({
+        "type": "Feature",
+        "id": row.id,
+        "geometry": {
+            "type": "Point",
+            "coordinates": [row.longitude, row.latitude],
+        },
+        "properties": {
+            "sighting_uuid": row.sighting_uuid(),
+            "common_name": row.common_name,
+            "scientific_name": row.scientific_name,
+            "count": row.count,
+            "observed_at": row.observed_at,
+            "local_date": row.local_date,
+            "country_code": row.country_code,
+            "region_code": row.region_code,
+            "lifer": row.lifer,
+            "year_tick": row.year_tick,
+            "country_tick": row.country_tick,
+        },
+    })
+}
+
+fn write_placemark(out: &mut String, row: &ExportRow) {
+    let data = [
+        ("sighting_uuid", row.sighting_uuid()),
+        ("scientific_name", row.scientific_name.clone()),
+        (
+            "count",
+            row.count.map(|count| count.to_string()).unwrap_or_default(),
+        ),
+        ("observed_at", row.observed_at.clone()),
+        ("local_date", row.local_date.clone().unwrap_or_default()),
+        ("country_code", row.country_code.clone().unwrap_or_default()),
+        ("region_code", row.region_code.clone().unwrap_or_default()),
+        ("lifer", row.lifer.to_string()),
+        ("year_tick", row.year_tick.to_string()),
+        ("country_tick", row.country_tick.to_string()),
+    ];
+
+    out.push_str("<Placemark id=\"s");
+    out.push_str(&row.id.to_string());
+    out.push_str("\">\n<name>");
+    out.push_str(&xml_escape(&row.common_name));
+    out.push_str("</name>\n<TimeStamp><when>");
+    out.push_str(&xml_escape(&row.observed_at));
+    out.push_str("</when></TimeStamp>\n<ExtendedData>\n");
+    for (name, value) in data {
+        out.push_str("<Data name=\"");
+        out.push_str(name);
+        out.push_str("\"><value>");
+        out.push_str(&xml_escape(&value));
+        out.push_str("</value></Data>\n");
+    }
+    out.push_str("</ExtendedData>\n<Point><coordinates>");
+    out.push_str(&format!("{},{}", row.longitude, row.latitude));
+    out.push_str("</coordinates></Point>\n</Placemark>\n");
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn write_birda_record(out: &mut String, row: &ExportRow) {
+    write_csv_record(
+        out,
+        &[
+            &row.sighting_uuid(),
+            &row.observed_at,
+            &row.longitude.to_string(),
+            &row.latitude.to_string(),
+            &row.common_name,
+            &row.scientific_name,
+            &row.count.map(|count| count.to_string()).unwrap_or_default(),
+        ],
+    );
+}
+
+/// Writes one line of eBird's record format. eBird groups lines into
+/// checklists by location, date and start time, and every sighting is
+/// submitted as an incomplete incidental observation.
+fn write_ebird_record(out: &mut String, row: &ExportRow) {
+    let (genus, species) = row
+        .scientific_name
+        .split_once(' ')
+        .unwrap_or((row.scientific_name.as_str(), ""));
+    let country_code = row
+        .country_code
+        .as_deref()
+        .filter(|code| *code != "XX")
+        .unwrap_or_default();
+    let (date, time) = timezone::local_datetime(
+        &row.observed_at,
+        country_code,
+        row.region_code.as_deref(),
+        row.latitude,
+        row.longitude,
+    )
+    .map(|(date, time)| {
+        (
+            date.format("%m/%d/%Y").to_string(),
+            time.map(|time| time.format("%H:%M").to_string())
+                .unwrap_or_default(),
+        )
+    })
+    .unwrap_or_default();
+    let state = row
+        .region_code
+        .as_deref()
+        .and_then(|code| code.split_once('-'))
+        .map(|(_, state)| state)
+        .unwrap_or_default();
+    // eBird takes "X" for present but not counted
+    let number = row
+        .count
+        .map_or_else(|| "X".to_string(), |count| count.to_string());
+
+    write_csv_record(
+        out,
+        &[
+            &row.common_name,
+            genus,
+            species,
+            &number,
+            "",
+            &format!("{:.6}, {:.6}", row.latitude, row.longitude),
+            &row.latitude.to_string(),
+            &row.longitude.to_string(),
+            &date,
+            &time,
+            state,
+            country_code,
+            "Incidental",
+            "",
+            "",
+            "N",
+            "",
+            "",
+            "",
+        ],
+    );
+}
+
+/// Spreadsheets run a cell starting with one of these as a formula.
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+fn write_csv_record(out: &mut String, fields: &[&str]) {
+    for (idx, field) in fields.iter().enumerate() {
+        if idx > 0 {
+            out.push(',');
+        }
+        // Prefix text such as a species name that would otherwise be run as a
+        // formula; negative coordinates are left as numbers.
+        let guarded;
+        let field = if field.starts_with(FORMULA_PREFIXES) && field.parse::<f64>().is_err() {
+            guarded = format!("'{field}");
+            guarded.as_str()
+        } else {
+            field
+        };
+        if field.contains([',', '"', '\r', '\n']) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push('\n');
+}
+
+pub async fn export_geojson(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    export(pools, &upload_id, &headers, query, ExportFormat::GeoJson).await
+}
+
+pub async fn export_kml(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    export(pools, &upload_id, &headers, query, ExportFormat::Kml).await
+}
+
+pub async fn export_csv(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let layout = query.layout.unwrap_or_default();
+    export(
+        pools,
+        &upload_id,
+        &headers,
+        query,
+        ExportFormat::Csv(layout),
+    )
+    .await
+}
+
+async fn export(
+    pools: DbPools,
+    upload_id: &str,
+    headers: &HeaderMap,
+    query: ExportQuery,
+    format: ExportFormat,
+) -> Result<Response, ApiError> {
+    let access = resolve_upload_access(pools.read(), upload_id, headers).await?;
+    let upload_id_blob = access.upload_id_blob();
+    let policy = LocationPolicy::load(pools.read(), &access).await?;
+
+    // Without a sort the export runs in date order, oldest first
+    let sort = match query.sort_field {
+        None => ExportSort {
+            keys: vec![
+                "COALESCE(s.local_date, '')".to_string(),
+                "s.observed_at".to_string(),
+            ],
+            dir: "ASC",
+        },
+        Some(SortField::SpeciesCount) => {
+            return Err(ApiError::bad_request(
+                "species_count sorting is only available for grouped sightings",
+            ));
+        }
+        Some(SortField::Count) => ExportSort {
+            keys: vec!["COALESCE(s.count, 0)".to_string()],
+            dir: parse_sort_direction(query.sort_dir.as_ref()),
+        },
+        Some(field) => ExportSort {
+            keys: vec![wrap_nullable_sort_column(field.as_sql_column())],
+            dir: parse_sort_direction(query.sort_dir.as_ref()),
+        },
+    };
+
+    let (filename, display_name) = db::query_with_timeout(
+        sqlx::query_as::<_, (String, Option<String>)>(
+            "SELECT filename, display_name FROM uploads WHERE id = ?",
+        )
+        .bind(upload_id_blob)
+        .fetch_optional(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading upload for export", "Database error"))?
+    .ok_or_else(|| ApiError::not_found("Upload not found"))?;
+    let title = effective_display_name(display_name, &filename);
+
+    let tick_visibility = TickVisibility::from_query(query.tick_filter.as_deref())?
+        .with_required(query.year_tick_year, query.country_tick_country.as_ref());
+    let filter_sql = build_filter_clause(FilterRequest {
+        pool: pools.read(),
+        upload_id: upload_id_blob,
+        filter_json: query.filter.as_ref(),
+        scope_filter: access.scope(),
+        year_tick_year: query.year_tick_year,
+        country_tick_country: query.country_tick_country.as_ref(),
+        aliases: TableAliases::new(Some("s"), Some("sp")),
+        tick_visibility: &tick_visibility,
+    })
+    .await?;
+
+    let (sender, receiver) = mpsc::channel(4);
+    let source = ExportSource {
+        access,
+        policy,
+        filter_sql,
+        sort,
+    };
+    tokio::spawn(stream_rows(pools, source, format, title, sender));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"sightings-{upload_id}.{}\"",
+                    format.extension()
+                ),
+            ),
+        ],
+        Body::from_stream(receiver),
+    )
+        .into_response())
+}
+
+type ChunkSender = mpsc::Sender<Result<Bytes, std::io::Error>>;
+
+/// The sightings an export covers and how their locations are shown.
+struct ExportSource {
+    access: UploadAccess,
+    policy: LocationPolicy,
+    filter_sql: FilterSql,
+    sort: ExportSort,
+}
+
+/// The export's order: `keys` are SQL expressions, none of them NULL, and
+/// `s.id` breaks ties. Pages pick up after the last row's keys, so the
+/// order has to be total.
+struct ExportSort {
+    keys: Vec<String>,
+    dir: &'static str,
+}
+
+/// A sort key value from the last row of a page.
+enum SortKey {
+    Integer(i64),
+    Text(String),
+}
+
+impl SortKey {
+    fn from_row(row: &SqliteRow, column: &str) -> Result<Self, sqlx::Error> {
+        row.try_get::<i64, _>(column)
+            .map(Self::Integer)
+            .or_else(|_| row.try_get::<String, _>(column).map(Self::Text))
+    }
+}
+
+/// Writes the export into `sender` chunk by chunk. Rows are read a page at
+/// a time, each page continuing after the last row's sort keys, so a read
+/// connection is only held while a page loads and not while a slow client
+/// takes the body. A database error part way through ends the body with an
+/// error, so the client sees a failed download rather than a silently
+/// truncated file.
+async fn stream_rows(
+    pools: DbPools,
+    source: ExportSource,
+    format: ExportFormat,
+    title: String,
+    mut sender: ChunkSender,
+) {
+    let ExportSource {
+        access,
+        policy,
+        filter_sql,
+        sort,
+    } = source;
+    let key_columns: Vec<String> = (0..sort.keys.len())
+        .map(|i| format!("sort_key_{i}"))
+        .collect();
+    let select_keys: String = sort
+        .keys
+        .iter()
+        .zip(&key_columns)
+        .map(|(key, column)| format!(", {key} AS {column}"))
+        .collect();
+    let order_by: String = sort
+        .keys
+        .iter()
+        .map(|key| format!("{key} {}, ", sort.dir))
+        .collect();
+    let sql = |after: bool| {
+        let keyset = if after {
+            let op = if sort.dir == "ASC" { ">" } else { "<" };
+            let placeholders = vec!["?"; sort.keys.len() + 1].join(", ");
+            format!(
+                " AND ({}, s.id) {op} ({placeholders})",
+                sort.keys.join(", ")
+            )
+        } else {
+            String::new()
+        };
+        format!(
+            "SELECT s.id, s.sighting_uuid, sp.common_name, sp.scientific_name, s.count,
+                s.latitude, s.longitude, s.country_code, s.region_code, s.observed_at,
+                s.local_date, s.lifer, s.year_tick, s.country_tick{select_keys}
+             FROM sightings s
+             JOIN species sp ON s.species_id = sp.id
+             WHERE s.upload_id = ?{}{keyset}
+             ORDER BY {order_by}s.id {}
+             LIMIT ?",
+            filter_sql.clause(),
+            sort.dir
+        )
+    };
+    let (first_sql, next_sql) = (sql(false), sql(true));
+
+    let mut buffer = format.header(&title);
+    let mut after: Option<PageEnd> = None;
+    let mut first = true;
+    loop {
+        let sql = if after.is_some() {
+            &next_sql
+        } else {
+            &first_sql
+        };
+        let page = fetch_page(
+            &pools,
+            sql,
+            &access,
+            &filter_sql,
+            &key_columns,
+            after.as_ref(),
+        );
+        let (rows, last) = match page.await {
+            Ok(page) => page,
+            Err(err) => {
+                match err {
+                    db::DbQueryError::Timeout => error!("Database timeout while streaming export"),
+                    db::DbQueryError::Sqlx(err) => {
+                        error!("Database error while streaming export: {}", err);
+                    }
+                }
+                let _ = sender
+                    .send(Err(std::io::Error::other("export failed")))
+                    .await;
+                return;
+            }
+        };
+        let done = rows.len() < EXPORT_PAGE_ROWS;
+
+        for mut row in rows {
+            // Sightings whose location is hidden are left out altogether
+            let Some(location) = policy.apply(&row.scientific_name, row.latitude, row.longitude)
+            else {
+                continue;
+            };
+            (row.latitude, row.longitude) = location;
+            format.write_row(&mut buffer, &row, first);
+            first = false;
+            if buffer.len() >= CHUNK_BYTES
+                && sender
+                    .send(Ok(Bytes::from(std::mem::take(&mut buffer))))
+                    .await
+                    .is_err()
+            {
+                // Client went away
+                return;
+            }
+        }
+
+        if done {
+            break;
+        }
+        after = last;
+    }
+
+    buffer.push_str(format.footer());
+    let _ = sender.send(Ok(Bytes::from(buffer))).await;
+}
+
+/// The sort keys and ID of the last row of a page.
+type PageEnd = (Vec<SortKey>, i64);
+
+/// Loads one page of the export, after `after`'s sort keys and ID if given.
+async fn fetch_page(
+    pools: &DbPools,
+    sql: &str,
+    access: &UploadAccess,
+    filter_sql: &FilterSql,
+    key_columns: &[String],
+    after: Option<&PageEnd>,
+) -> Result<(Vec<ExportRow>, Option<PageEnd>), db::DbQueryError> {
+    let mut query = sqlx::query(sql).bind(access.upload_id_blob());
+    for param in filter_sql.params() {
+        query = query.bind(param);
+    }
+    if let Some((keys, id)) = after {
+        for key in keys {
+            query = match key {
+                SortKey::Integer(value) => query.bind(*value),
+                SortKey::Text(value) => query.bind(value.as_str()),
+            };
+        }
+        query = query.bind(*id);
+    }
+    query = query.bind(EXPORT_PAGE_ROWS as i64);
+    let rows = db::query_with_timeout(query.fetch_all(pools.read())).await?;
+
+    let last = match rows.last() {
+        Some(row) => Some((
+            key_columns
+                .iter()
+                .map(|column| SortKey::from_row(row, column))
+                .collect::<Result<_, _>>()?,
+            row.try_get("id")?,
+        )),
+        None => None,
+    };
+    let rows = rows
+        .iter()
+        .map(ExportRow::from_row)
+        .collect::<Result<_, _>>()?;
+    Ok((rows, last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_cells_are_not_run_as_formulas() {
+        let mut out = String::new();
+        write_csv_record(
+            &mut out,
+            &[
+                "=HYPERLINK(\"http://example.com\")",
+                "+1",
+                "-3.25",
+                "-cmd",
+                "@SUM(A1)",
+                "\tTab",
+                "\rReturn",
+                "Robin, European",
+            ],
+        );
+        assert_eq!(
+            out,
+            "\"'=HYPERLINK(\"\"http://example.com\"\")\",+1,-3.25,'-cmd,'@SUM(A1),'\tTab,\"'\rReturn\",\"Robin, European\"\n"
+        );
+    }
+}
@@ -46,6 +46,9 @@ pub enum FilterField {
     Count,
     ObservedAt,
     Year,
+    Family,
+    Order,
+    Genus,
 }
 
 impl FilterField {
@@ -57,11 +60,22 @@ impl FilterField {
             Self::Count => "count",
             Self::ObservedAt => "observed_at",
             Self::Year => "year",
+            Self::Family => "family",
+            Self::Order => "order_name",
+            Self::Genus => "genus",
         }
     }
 
     pub fn as_str(&self) -> &'static str {
-        self.as_sql_column()
+        match self {
+            Self::Order => "order",
+            _ => self.as_sql_column(),
+        }
+    }
+
+    /// Fields read from the species' taxon rather than the sighting.
+    pub const fn is_taxonomic(&self) -> bool {
+        matches!(self, Self::Family | Self::Order | Self::Genus)
     }
 }
 
@@ -179,6 +193,16 @@ impl<'a> ColumnResolver<'a> {
             None => column.to_string(),
         }
     }
+
+    /// Taxonomic conditions match species through a subquery, so callers
+    /// don't need to join `taxa` themselves. Species the taxonomy didn't
+    /// recognise never match.
+    fn taxon_subquery(&self, predicate: &str) -> String {
+        format!(
+            "{} IN (SELECT tsp.id FROM species tsp JOIN taxa t ON t.id = tsp.taxon_id WHERE {predicate})",
+            self.format_with_alias(self.sightings_alias, "species_id")
+        )
+    }
 }
 
 impl FilterGroup {
@@ -249,8 +273,17 @@ impl Condition {
     }
 
     fn to_sql(&self, resolver: &ColumnResolver<'_>, params: &mut Vec<String>) -> Option<String> {
-        let field = resolver.column(self.field);
+        if self.field.is_taxonomic() {
+            let column = format!("t.{}", self.field.as_sql_column());
+            return self
+                .predicate(&column, params)
+                .map(|predicate| resolver.taxon_subquery(&predicate));
+        }
+
+        self.predicate(&resolver.column(self.field), params)
+    }
 
+    fn predicate(&self, field: &str, params: &mut Vec<String>) -> Option<String> {
         match (&self.operator, &self.value) {
             (Operator::Eq, FilterValue::String(v)) => {
                 params.push(v.clone());
@@ -381,6 +414,21 @@ pub fn get_field_metadata() -> Vec<FieldMetadata> {
             label: "Year".into(),
             field_type: "number".into(),
         },
+        FieldMetadata {
+            name: "family".into(),
+            label: "Family".into(),
+            field_type: "string".into(),
+        },
+        FieldMetadata {
+            name: "order".into(),
+            label: "Order".into(),
+            field_type: "string".into(),
+        },
+        FieldMetadata {
+            name: "genus".into(),
+            label: "Genus".into(),
+            field_type: "string".into(),
+        },
     ]
 }
 
@@ -554,6 +602,8 @@ pub struct FilterRequest<'a> {
     pub pool: &'a sqlx::SqlitePool,
     pub upload_id: &'a [u8],
     pub filter_json: Option<&'a String>,
+    /// A share link's filter, applied alongside the request's own
+    pub scope_filter: Option<&'a FilterGroup>,
     pub year_tick_year: Option<i32>,
     pub country_tick_country: Option<&'a String>,
     pub aliases: TableAliases<'a>,
@@ -573,6 +623,13 @@ impl<'a> FilterRequest<'a> {
             }
         }
 
+        if let Some(sql) = self
+            .scope_filter
+            .and_then(|scope| scope.to_sql(&resolver, &mut params))
+        {
+            clauses.push(sql);
+        }
+
         if self.year_tick_year.is_some() || self.country_tick_country.is_some() {
             if let Some(bitmap_clause) = build_bitmap_clause(
                 self.pool,
@@ -705,40 +762,57 @@ async fn build_bitmap_clause(
     }
 }
 
+const SPECIES_JOIN: &str = " JOIN species sp ON s.species_id = sp.id";
+const TAXA_JOIN: &str =
+    " JOIN species sp ON s.species_id = sp.id JOIN taxa t ON sp.taxon_id = t.id";
+
 struct FieldColumnInfo {
     column: &'static str,
-    needs_join: bool,
+    join: &'static str,
 }
 
 pub async fn get_distinct_values(
     pool: &sqlx::SqlitePool,
     upload_id: &[u8],
     field: &str,
+    scope_filter: Option<&FilterGroup>,
 ) -> Result<Vec<String>, DbQueryError> {
     let field_info = match field {
         "common_name" => FieldColumnInfo {
             column: "sp.common_name",
-            needs_join: true,
+            join: SPECIES_JOIN,
         },
         "scientific_name" => FieldColumnInfo {
             column: "sp.scientific_name",
-            needs_join: true,
+            join: SPECIES_JOIN,
         },
         "country_code" => FieldColumnInfo {
             column: "s.country_code",
-            needs_join: false,
+            join: "",
         },
         "count" => FieldColumnInfo {
             column: "s.count",
-            needs_join: false,
+            join: "",
         },
         "observed_at" => FieldColumnInfo {
             column: "s.observed_at",
-            needs_join: false,
+            join: "",
         },
         "year" => FieldColumnInfo {
             column: "s.year",
-            needs_join: false,
+            join: "",
+        },
+        "family" => FieldColumnInfo {
+            column: "t.family",
+            join: TAXA_JOIN,
+        },
+        "order" => FieldColumnInfo {
+            column: "t.order_name",
+            join: TAXA_JOIN,
+        },
+        "genus" => FieldColumnInfo {
+            column: "t.genus",
+            join: TAXA_JOIN,
         },
         _ => return Ok(vec![]),
     };
@@ -748,20 +822,34 @@ pub async fn get_distinct_values(
         value: String,
     }
 
-    let query = if field_info.needs_join {
-        format!(
-            "SELECT DISTINCT CAST({} AS TEXT) as value FROM sightings s JOIN species sp ON s.species_id = sp.id WHERE s.upload_id = ? AND {} IS NOT NULL ORDER BY {} LIMIT {}",
-            field_info.column, field_info.column, field_info.column, MAX_DISTINCT_FIELD_VALUES
-        )
+    // Values outside a share's filter aren't offered
+    let mut params = Vec::new();
+    let resolver = ColumnResolver::new(TableAliases::new(Some("s"), Some("sp")));
+    let scope_clause = scope_filter
+        .and_then(|scope| scope.to_sql(&resolver, &mut params))
+        .map(|sql| format!(" AND {sql}"))
+        .unwrap_or_default();
+    let join = if field_info.join.is_empty() && !scope_clause.is_empty() {
+        SPECIES_JOIN
     } else {
-        format!(
-            "SELECT DISTINCT CAST({} AS TEXT) as value FROM sightings s WHERE s.upload_id = ? AND {} IS NOT NULL ORDER BY {} LIMIT {}",
-            field_info.column, field_info.column, field_info.column, MAX_DISTINCT_FIELD_VALUES
-        )
+        field_info.join
     };
 
-    let rows: Vec<ValueRow> =
-        db::query_with_timeout(sqlx::query_as(&query).bind(upload_id).fetch_all(pool)).await?;
+    let query = format!(
+        "SELECT DISTINCT CAST({} AS TEXT) as value FROM sightings s{} WHERE s.upload_id = ? AND {} IS NOT NULL{} ORDER BY {} LIMIT {}",
+        field_info.column,
+        join,
+        field_info.column,
+        scope_clause,
+        field_info.column,
+        MAX_DISTINCT_FIELD_VALUES
+    );
+
+    let mut db_query = sqlx::query_as(&query).bind(upload_id);
+    for param in &params {
+        db_query = db_query.bind(param);
+    }
+    let rows: Vec<ValueRow> = db::query_with_timeout(db_query.fetch_all(pool)).await?;
 
     Ok(rows.into_iter().map(|row| row.value).collect())
 }
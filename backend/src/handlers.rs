@@ -1,4 +1,5 @@
 use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
 use sqlx::FromRow;
 use uuid::Uuid;
 
@@ -10,7 +11,11 @@ use crate::filter::{
     TableAliases,
 };
 use crate::proto::{pb, Proto};
-use crate::upload::{effective_display_name, get_upload_data_version};
+use crate::shares::resolve_upload_access;
+use crate::upload::{
+    deletion_scheduled_at, effective_display_name, get_upload_data_version,
+    RETENTION_TIMESTAMP_FORMAT,
+};
 
 #[derive(FromRow)]
 struct UploadRow {
@@ -19,6 +24,9 @@ struct UploadRow {
     row_count: i64,
     display_name: Option<String>,
     data_version: i64,
+    is_collection: bool,
+    pinned: bool,
+    expires_at: Option<String>,
 }
 
 #[derive(serde::Deserialize)]
@@ -30,12 +38,13 @@ pub struct FieldValuesPath {
 pub async fn get_upload(
     State(pools): State<DbPools>,
     Path(upload_id): Path<String>,
+    headers: HeaderMap,
 ) -> Result<Proto<pb::UploadMetadata>, ApiError> {
-    let upload_uuid = Uuid::parse_str(&upload_id)
-        .map_err(|_| ApiError::bad_request("Invalid upload_id format"))?;
+    let access = resolve_upload_access(pools.read(), &upload_id, &headers).await?;
+    let upload_uuid = *access.upload_uuid();
     let row = db::query_with_timeout(
         sqlx::query_as::<_, UploadRow>(
-            "SELECT id, filename, row_count, display_name, data_version FROM uploads WHERE id = ?",
+            "SELECT id, filename, row_count, display_name, data_version, is_collection, pinned, expires_at FROM uploads WHERE id = ?",
         )
         .bind(&upload_uuid.as_bytes()[..])
         .fetch_optional(pools.read()),
@@ -44,11 +53,22 @@ pub async fn get_upload(
     .map_err(|e| e.into_api_error("loading upload metadata", "Database error"))?
     .ok_or_else(|| ApiError::not_found("Upload not found"))?;
 
-    // Convert BLOB UUID back to string
+    // Convert BLOB UUID back to string; share links keep the upload ID private
     let id_uuid = Uuid::from_slice(&row.id)
         .map_err(|_| ApiError::internal("Invalid UUID format in database"))?;
+    let response_id = if access.is_shared() {
+        upload_id
+    } else {
+        id_uuid.to_string()
+    };
 
     let title = effective_display_name(row.display_name, &row.filename);
+    // This view renews the retention period, so it runs from now
+    let accessed_at = chrono::Utc::now()
+        .format(RETENTION_TIMESTAMP_FORMAT)
+        .to_string();
+    let deletion_scheduled_at =
+        deletion_scheduled_at(row.pinned, row.expires_at.as_deref(), &accessed_at);
 
     let upload_id_blob = upload_uuid.as_bytes().to_vec();
     let write_pool = pools.write().clone();
@@ -65,11 +85,17 @@ pub async fn get_upload(
     });
 
     Ok(Proto::new(pb::UploadMetadata {
-        upload_id: id_uuid.to_string(),
+        upload_id: response_id,
         filename: row.filename,
         row_count: row.row_count,
         title,
         data_version: row.data_version,
+        is_collection: row.is_collection,
+        pinned: row.pinned,
+        expires_at: row.expires_at,
+        deletion_scheduled_at,
+        shared: access.is_shared(),
+        password_protected: access.is_password_protected(),
     }))
 }
 
@@ -77,9 +103,10 @@ pub async fn get_filtered_count(
     State(pools): State<DbPools>,
     Path(upload_id): Path<String>,
     Query(query): Query<CountQuery>,
+    headers: HeaderMap,
 ) -> Result<Proto<pb::CountResponse>, ApiError> {
-    let upload_uuid = Uuid::parse_str(&upload_id)
-        .map_err(|_| ApiError::bad_request("Invalid upload_id format"))?;
+    let access = resolve_upload_access(pools.read(), &upload_id, &headers).await?;
+    let upload_uuid = *access.upload_uuid();
     let data_version = get_upload_data_version(pools.read(), &upload_uuid).await?;
 
     let needs_join = if let Some(filter_json) = &query.filter {
@@ -87,7 +114,7 @@ pub async fn get_filtered_count(
         filter.needs_species_join()
     } else {
         false
-    };
+    } || access.scope_needs_species_join();
 
     let aliases = if needs_join {
         TableAliases::new(Some("s"), Some("sp"))
@@ -100,6 +127,7 @@ pub async fn get_filtered_count(
         pool: pools.read(),
         upload_id: &upload_uuid.as_bytes()[..],
         filter_json: query.filter.as_ref(),
+        scope_filter: access.scope(),
         year_tick_year: query.year_tick_year,
         country_tick_country: query.country_tick_country.as_ref(),
         aliases,
@@ -149,13 +177,18 @@ pub async fn fields_metadata() -> Proto<pb::FieldMetadataList> {
 pub async fn field_values(
     State(pools): State<DbPools>,
     Path(path): Path<FieldValuesPath>,
+    headers: HeaderMap,
 ) -> Result<Proto<pb::FieldValues>, ApiError> {
-    let upload_uuid = Uuid::parse_str(&path.upload_id)
-        .map_err(|_| ApiError::bad_request("Invalid upload_id format"))?;
-    let data_version = get_upload_data_version(pools.read(), &upload_uuid).await?;
-    let values = get_distinct_values(pools.read(), &upload_uuid.as_bytes()[..], &path.field)
-        .await
-        .map_err(|e| e.into_api_error("loading field values", "Database error"))?;
+    let access = resolve_upload_access(pools.read(), &path.upload_id, &headers).await?;
+    let data_version = get_upload_data_version(pools.read(), access.upload_uuid()).await?;
+    let values = get_distinct_values(
+        pools.read(),
+        access.upload_id_blob(),
+        &path.field,
+        access.scope(),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading field values", "Database error"))?;
 
     tracing::debug!(
         "Field values for {}: returning {} values",
@@ -0,0 +1,212 @@
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use sqlx::{FromRow, QueryBuilder, Sqlite, Transaction};
+use std::collections::HashMap;
+
+use crate::db::{self, DbPools, DbQueryError};
+use crate::error::ApiError;
+use crate::proto::{pb, Proto};
+use crate::shares::resolve_upload_access;
+use crate::sources::{SkipReason, SkippedRow};
+
+// Every skipped row is counted, but only the first MAX_STORED_ISSUES of an
+// upload are kept so a completely mismatched file can't fill the database
+// with diagnostics.
+pub const MAX_STORED_ISSUES: usize = 10_000;
+const INSERT_BATCH_SIZE: usize = 999 / 5;
+
+#[derive(Default)]
+pub struct ImportIssues {
+    skipped_rows: usize,
+    stored: Vec<SkippedRow>,
+    counts: HashMap<(&'static str, SkipReason), usize>,
+}
+
+impl ImportIssues {
+    pub fn record(&mut self, skipped: SkippedRow) {
+        self.skipped_rows += 1;
+        *self
+            .counts
+            .entry((skipped.column, skipped.reason))
+            .or_default() += 1;
+        if self.stored.len() < MAX_STORED_ISSUES {
+            self.stored.push(skipped);
+        }
+    }
+
+    pub const fn skipped_rows(&self) -> usize {
+        self.skipped_rows
+    }
+
+    /// Skip counts grouped by column and reason, most common first.
+    pub fn summary(&self) -> Vec<pb::ImportIssueCount> {
+        let mut summary: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(&(column, reason), &count)| pb::ImportIssueCount {
+                column: column.to_string(),
+                reason: reason.as_str().to_string(),
+                count: i64::try_from(count).unwrap_or(i64::MAX),
+            })
+            .collect();
+        summary.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.column.cmp(&b.column))
+                .then_with(|| a.reason.cmp(&b.reason))
+        });
+        summary
+    }
+}
+
+/// Replaces any previously stored issues for the upload.
+/// Stores an import's skipped rows under the upload's current `data_version`,
+/// which the import has already bumped. An append adds them to the report;
+/// an upload or update starts it again.
+pub(crate) async fn store_import_issues(
+    tx: &mut Transaction<'_, Sqlite>,
+    upload_id_blob: &[u8],
+    issues: &ImportIssues,
+    append: bool,
+) -> Result<(), DbQueryError> {
+    if !append {
+        db::query_with_timeout(
+            sqlx::query("DELETE FROM import_issues WHERE upload_id = ?")
+                .bind(upload_id_blob)
+                .execute(&mut **tx),
+        )
+        .await?;
+    }
+
+    let (data_version, already_stored) = db::query_with_timeout(
+        sqlx::query_as::<_, (i64, i64)>(
+            "SELECT data_version, (SELECT COUNT(*) FROM import_issues WHERE upload_id = uploads.id)
+             FROM uploads WHERE id = ?",
+        )
+        .bind(upload_id_blob)
+        .fetch_one(&mut **tx),
+    )
+    .await?;
+    let room = MAX_STORED_ISSUES.saturating_sub(usize::try_from(already_stored).unwrap_or(0));
+    let stored = &issues.stored[..issues.stored.len().min(room)];
+
+    for chunk in stored.chunks(INSERT_BATCH_SIZE) {
+        let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+            "INSERT INTO import_issues (upload_id, data_version, row_number, column_name, reason) ",
+        );
+        qb.push_values(chunk, |mut b, issue| {
+            b.push_bind(upload_id_blob)
+                .push_bind(data_version)
+                .push_bind(i64::try_from(issue.row).unwrap_or(i64::MAX))
+                .push_bind(issue.column)
+                .push_bind(issue.reason.as_str());
+        });
+        db::query_with_timeout(qb.build().execute(&mut **tx)).await?;
+    }
+
+    Ok(())
+}
+
+#[derive(FromRow)]
+struct ReportUploadRow {
+    format: Option<String>,
+    skipped_rows: i64,
+    data_version: i64,
+}
+
+#[derive(FromRow)]
+struct IssueRow {
+    data_version: i64,
+    row_number: i64,
+    column_name: String,
+    reason: String,
+}
+
+pub async fn get_import_report(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Proto<pb::ImportReport>, ApiError> {
+    let access = resolve_upload_access(pools.read(), &upload_id, &headers).await?;
+    let upload_id_blob = access.upload_id_blob();
+
+    let upload = db::query_with_timeout(
+        sqlx::query_as::<_, ReportUploadRow>(
+            "SELECT format, skipped_rows, data_version FROM uploads WHERE id = ?",
+        )
+        .bind(upload_id_blob)
+        .fetch_optional(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading upload for import report", "Database error"))?
+    .ok_or_else(|| ApiError::not_found("Upload not found"))?;
+
+    // Skipped rows and unmatched names can show what a restricted share hides
+    if access.scope().is_some() || access.hides_locations() {
+        return Ok(Proto::new(pb::ImportReport {
+            upload_id,
+            format: upload.format.unwrap_or_default(),
+            skipped_rows: upload.skipped_rows,
+            issues: Vec::new(),
+            truncated: upload.skipped_rows > 0,
+            data_version: upload.data_version,
+            unmatched_species: Vec::new(),
+        }));
+    }
+
+    let issues = db::query_with_timeout(
+        sqlx::query_as::<_, IssueRow>(
+            "SELECT data_version, row_number, column_name, reason FROM import_issues WHERE upload_id = ? ORDER BY data_version, row_number",
+        )
+        .bind(upload_id_blob)
+        .fetch_all(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading import issues", "Database error"))?;
+
+    let unmatched_species = db::query_with_timeout(
+        sqlx::query_as::<_, (String, String, i64)>(
+            "SELECT sp.common_name, sp.scientific_name, COUNT(*) AS sightings
+             FROM sightings s
+             JOIN species sp ON s.species_id = sp.id
+             WHERE s.upload_id = ? AND sp.taxon_id IS NULL
+             GROUP BY sp.id
+             ORDER BY sightings DESC, sp.common_name",
+        )
+        .bind(upload_id_blob)
+        .fetch_all(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading unmatched species", "Database error"))?;
+
+    let truncated = i64::try_from(issues.len()).unwrap_or(i64::MAX) < upload.skipped_rows;
+
+    Ok(Proto::new(pb::ImportReport {
+        upload_id: if access.is_shared() {
+            upload_id
+        } else {
+            access.upload_uuid().to_string()
+        },
+        format: upload.format.unwrap_or_default(),
+        skipped_rows: upload.skipped_rows,
+        issues: issues
+            .into_iter()
+            .map(|issue| pb::ImportIssue {
+                row: issue.row_number,
+                column: issue.column_name,
+                reason: issue.reason,
+                data_version: issue.data_version,
+            })
+            .collect(),
+        truncated,
+        data_version: upload.data_version,
+        unmatched_species: unmatched_species
+            .into_iter()
+            .map(|(common_name, scientific_name, count)| pb::SpeciesCount {
+                common_name,
+                scientific_name,
+                count,
+            })
+            .collect(),
+    }))
+}
@@ -1,17 +1,33 @@
 pub mod api_constants;
+pub mod backup;
 pub mod bitmaps;
+pub mod collections;
 pub mod config;
 pub mod db;
+pub mod edit_tokens;
 pub mod error;
+pub mod export;
 pub mod filter;
 pub mod handlers;
+pub mod import_report;
 pub mod limits;
+pub mod location_privacy;
 pub mod pipeline;
+pub mod polygons;
 pub mod proto;
+pub mod raster;
+pub mod shares;
+pub mod sighting_edits;
 pub mod sightings;
+pub mod sources;
+pub mod static_map;
 pub mod stats;
+pub mod taxonomy;
+pub mod ticks;
 pub mod tiles;
+pub mod timezone;
 pub mod upload;
+pub mod view_password;
 pub mod zip_extract;
 
 use crate::db::DbPools;
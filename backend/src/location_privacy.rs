@@ -0,0 +1,714 @@
+//! Location privacy for people viewing an upload they don't own.
+//!
+//! Sightings of sensitive species give away nest sites and roosts, so
+//! coordinates in tiles, sightings, bounding boxes and exports can be snapped
+//! to a grid, jittered or left out for anyone without the edit token. Each
+//! upload has a mode for all its sightings, per-species overrides, and can
+//! apply the bundled list in `data/sensitive_species.csv`. Share links that
+//! hide locations never show anything finer than a 10 km grid.
+
+use std::collections::HashMap;
+
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, QueryBuilder, Sqlite};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::collections::ensure_not_collection_member;
+use crate::db::{self, DbPools};
+use crate::error::ApiError;
+use crate::proto::{pb, Proto};
+use crate::shares::UploadAccess;
+use crate::tiles::invalidate_upload_cache;
+use crate::upload::{check_edit_token, parse_upload_id};
+
+const SENSITIVE_SPECIES_CSV: &str = include_str!("../data/sensitive_species.csv");
+const SENSITIVE_SPECIES_HEADER: &str = "scientific_name,common_name,mode,km";
+
+const KM_PER_DEGREE: f64 = 111.32;
+const MIN_KM: f64 = 0.1;
+const MAX_KM: f64 = 100.0;
+pub(crate) const MAX_SPECIES_RULES: usize = 500;
+/// The coarsest positions a share link that hides locations can show.
+const SHARE_GRID_KM: f64 = 10.0;
+// Keeps grid cells and jitter sane near the poles
+const MIN_COS_LATITUDE: f64 = 0.01;
+// Shown coordinates are rounded to about a metre
+const COORDINATE_SCALE: f64 = 100_000.0;
+// 4 bound columns per rule, kept under SQLite's variable limit
+const RULE_INSERT_BATCH_SIZE: usize = 200;
+
+static SENSITIVE_SPECIES: Lazy<Vec<SensitiveSpecies>> = Lazy::new(|| {
+    parse_sensitive_species(SENSITIVE_SPECIES_CSV).unwrap_or_else(|err| {
+        error!("Failed to load bundled sensitive species: {}", err);
+        panic!("Bundled sensitive species list is invalid. Application cannot start without it.");
+    })
+});
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LocationMode {
+    Exact,
+    /// Snapped to the centre of a grid cell this many km across
+    Grid(f64),
+    /// Moved between half and all of this many km, the same way each time
+    /// for the same place
+    Jitter(f64),
+    Hide,
+}
+
+impl LocationMode {
+    pub fn parse(mode: &str, km: Option<f64>) -> Result<Self, String> {
+        let distance = || match km {
+            Some(km) if (MIN_KM..=MAX_KM).contains(&km) => Ok(km),
+            Some(km) => Err(format!(
+                "km must be between {MIN_KM} and {MAX_KM}, got {km}"
+            )),
+            None => Err(format!("{mode} needs a distance in km")),
+        };
+        match mode {
+            "exact" => Ok(Self::Exact),
+            "grid" => distance().map(Self::Grid),
+            "jitter" => distance().map(Self::Jitter),
+            "hide" => Ok(Self::Hide),
+            other => Err(format!(
+                "Unknown location mode {other:?}; expected exact, grid, jitter or hide"
+            )),
+        }
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::Grid(_) => "grid",
+            Self::Jitter(_) => "jitter",
+            Self::Hide => "hide",
+        }
+    }
+
+    pub const fn km(&self) -> Option<f64> {
+        match self {
+            Self::Grid(km) | Self::Jitter(km) => Some(*km),
+            Self::Exact | Self::Hide => None,
+        }
+    }
+
+    /// Hiding beats everything; grids and jitter are compared by distance.
+    fn strictness(&self) -> f64 {
+        match self {
+            Self::Exact => 0.0,
+            Self::Grid(km) | Self::Jitter(km) => *km,
+            Self::Hide => f64::INFINITY,
+        }
+    }
+
+    fn stricter(self, other: Self) -> Self {
+        if other.strictness() > self.strictness() {
+            other
+        } else {
+            self
+        }
+    }
+
+    /// Furthest a sighting can be shown from where it was seen, in km.
+    pub fn max_shift_km(&self) -> f64 {
+        match self {
+            Self::Exact | Self::Hide => 0.0,
+            Self::Grid(km) | Self::Jitter(km) => *km,
+        }
+    }
+}
+
+struct SensitiveSpecies {
+    scientific_name: String,
+    common_name: String,
+    mode: LocationMode,
+}
+
+fn parse_sensitive_species(data: &str) -> Result<Vec<SensitiveSpecies>, String> {
+    let mut lines = data.lines().enumerate();
+    match lines.next() {
+        Some((_, first)) if first.trim() == SENSITIVE_SPECIES_HEADER => {}
+        _ => return Err(format!("expected header {SENSITIVE_SPECIES_HEADER:?}")),
+    }
+
+    let mut species = Vec::new();
+    for (idx, line) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+        let line_no = idx + 1;
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [scientific_name, common_name, mode, km] = fields[..] else {
+            return Err(format!(
+                "line {line_no}: expected 4 columns, found {}",
+                fields.len()
+            ));
+        };
+        let km = if km.is_empty() {
+            None
+        } else {
+            Some(
+                km.parse::<f64>()
+                    .map_err(|_| format!("line {line_no}: invalid distance {km:?}"))?,
+            )
+        };
+        species.push(SensitiveSpecies {
+            scientific_name: scientific_name.to_string(),
+            common_name: common_name.to_string(),
+            mode: LocationMode::parse(mode, km).map_err(|e| format!("line {line_no}: {e}"))?,
+        });
+    }
+    Ok(species)
+}
+
+fn species_key(scientific_name: &str) -> String {
+    scientific_name.trim().to_lowercase()
+}
+
+/// How to show each sighting to the current caller.
+pub struct LocationPolicy {
+    default: LocationMode,
+    species: HashMap<String, LocationMode>,
+    salt: Vec<u8>,
+}
+
+#[derive(FromRow)]
+struct UploadPrivacyRow {
+    location_mode: String,
+    location_km: Option<f64>,
+    sensitive_species: bool,
+    location_salt: Option<Vec<u8>>,
+}
+
+#[derive(FromRow)]
+struct SpeciesRuleRow {
+    scientific_name: String,
+    mode: String,
+    km: Option<f64>,
+}
+
+fn stored_mode(mode: &str, km: Option<f64>) -> Result<LocationMode, ApiError> {
+    LocationMode::parse(mode, km)
+        .map_err(|e| ApiError::internal(format!("Invalid stored location mode: {e}")))
+}
+
+impl LocationPolicy {
+    pub fn exact() -> Self {
+        Self {
+            default: LocationMode::Exact,
+            species: HashMap::new(),
+            salt: Vec::new(),
+        }
+    }
+
+    /// The owner sees everything as it is; everyone else gets the upload's
+    /// settings, coarsened further for share links that hide locations.
+    pub async fn load(pool: &sqlx::SqlitePool, access: &UploadAccess) -> Result<Self, ApiError> {
+        if access.is_owner() {
+            return Ok(Self::exact());
+        }
+
+        let row = db::query_with_timeout(
+            sqlx::query_as::<_, UploadPrivacyRow>(
+                "SELECT location_mode, location_km, sensitive_species, location_salt
+                FROM uploads WHERE id = ?",
+            )
+            .bind(access.upload_id_blob())
+            .fetch_optional(pool),
+        )
+        .await
+        .map_err(|e| e.into_api_error("loading location privacy", "Database error"))?
+        .ok_or_else(|| ApiError::not_found("Upload not found"))?;
+        let rules = load_species_rules(pool, access.upload_id_blob()).await?;
+
+        let default = stored_mode(&row.location_mode, row.location_km)?;
+        let mut species = HashMap::new();
+        if row.sensitive_species {
+            // The bundled list only ever makes a species harder to find
+            for entry in SENSITIVE_SPECIES.iter() {
+                species.insert(
+                    species_key(&entry.scientific_name),
+                    entry.mode.stricter(default),
+                );
+            }
+        }
+        for rule in rules {
+            species.insert(
+                species_key(&rule.scientific_name),
+                stored_mode(&rule.mode, rule.km)?,
+            );
+        }
+
+        let mut policy = Self {
+            default,
+            species,
+            salt: row.location_salt.unwrap_or_default(),
+        };
+        if access.hides_locations() {
+            policy.coarsen_for_share();
+        }
+        Ok(policy)
+    }
+
+    /// Keeps every position at least as coarse as a `SHARE_GRID_KM` grid.
+    fn coarsen_for_share(&mut self) {
+        let floor = LocationMode::Grid(SHARE_GRID_KM);
+        self.default = self.default.stricter(floor);
+        for mode in self.species.values_mut() {
+            *mode = mode.stricter(floor);
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.default == LocationMode::Exact
+            && self
+                .species
+                .values()
+                .all(|mode| *mode == LocationMode::Exact)
+    }
+
+    pub fn mode_for(&self, scientific_name: &str) -> LocationMode {
+        let key = species_key(scientific_name);
+        if let Some(mode) = self.species.get(&key) {
+            return *mode;
+        }
+        // Subspecies follow their species
+        let binomial = key.split_whitespace().take(2).collect::<Vec<_>>().join(" ");
+        self.species.get(&binomial).copied().unwrap_or(self.default)
+    }
+
+    /// The mode for species without one of their own.
+    pub const fn default_mode(&self) -> LocationMode {
+        self.default
+    }
+
+    /// Where a sighting is shown, or `None` if it's hidden.
+    pub fn apply(
+        &self,
+        scientific_name: &str,
+        latitude: f64,
+        longitude: f64,
+    ) -> Option<(f64, f64)> {
+        self.apply_mode(self.mode_for(scientific_name), latitude, longitude)
+    }
+
+    /// Where a location is shown under `mode`, or `None` if it's hidden.
+    pub fn apply_mode(
+        &self,
+        mode: LocationMode,
+        latitude: f64,
+        longitude: f64,
+    ) -> Option<(f64, f64)> {
+        match mode {
+            LocationMode::Exact => Some((latitude, longitude)),
+            LocationMode::Grid(km) => Some(snap_to_grid(latitude, longitude, km)),
+            LocationMode::Jitter(km) => Some(jitter(&self.salt, latitude, longitude, km)),
+            LocationMode::Hide => None,
+        }
+    }
+
+    /// Furthest any sighting can be shown from where it was seen, in km.
+    pub fn max_shift_km(&self) -> f64 {
+        self.species
+            .values()
+            .chain(std::iter::once(&self.default))
+            .map(LocationMode::max_shift_km)
+            .fold(0.0, f64::max)
+    }
+
+    /// The shown extent of a species' sightings that span the given box, or
+    /// `None` if the species is hidden. Jittered sightings could land
+    /// anywhere within their distance, so the box grows by it.
+    pub fn bounds(&self, scientific_name: &str, bbox: [f64; 4]) -> Option<[f64; 4]> {
+        let [min_lng, min_lat, max_lng, max_lat] = bbox;
+        match self.mode_for(scientific_name) {
+            LocationMode::Exact => Some(bbox),
+            LocationMode::Grid(km) => {
+                let (min_lat, min_lng) = snap_to_grid(min_lat, min_lng, km);
+                let (max_lat, max_lng) = snap_to_grid(max_lat, max_lng, km);
+                Some([min_lng, min_lat, max_lng, max_lat])
+            }
+            LocationMode::Jitter(km) => {
+                let lat_margin = km / KM_PER_DEGREE;
+                let widest = min_lat.abs().max(max_lat.abs()).min(90.0);
+                let lng_margin = lng_degrees(km, widest);
+                Some([
+                    (min_lng - lng_margin).max(-180.0),
+                    (min_lat - lat_margin).max(-90.0),
+                    (max_lng + lng_margin).min(180.0),
+                    (max_lat + lat_margin).min(90.0),
+                ])
+            }
+            LocationMode::Hide => None,
+        }
+    }
+}
+
+/// Degrees of longitude spanning `km` at the given latitude.
+pub fn lng_degrees(km: f64, latitude: f64) -> f64 {
+    (km / (KM_PER_DEGREE * latitude.to_radians().cos().max(MIN_COS_LATITUDE))).min(360.0)
+}
+
+/// Degrees of latitude spanning `km`.
+pub fn lat_degrees(km: f64) -> f64 {
+    km / KM_PER_DEGREE
+}
+
+fn round_coordinate(value: f64) -> f64 {
+    (value * COORDINATE_SCALE).round() / COORDINATE_SCALE
+}
+
+fn snap_to_grid(latitude: f64, longitude: f64, km: f64) -> (f64, f64) {
+    let lat_step = lat_degrees(km);
+    let lat = ((((latitude + 90.0) / lat_step).floor() + 0.5) * lat_step - 90.0).clamp(-90.0, 90.0);
+    // Cells keep roughly the same width in km away from the equator
+    let lng_step = lng_degrees(km, lat);
+    let lng =
+        ((((longitude + 180.0) / lng_step).floor() + 0.5) * lng_step - 180.0).clamp(-180.0, 180.0);
+    (round_coordinate(lat), round_coordinate(lng))
+}
+
+fn jitter(salt: &[u8], latitude: f64, longitude: f64, km: f64) -> (f64, f64) {
+    // Seeded by the grid cell `km` across rather than the exact place, so
+    // every sighting around a nest moves by the same offset and averaging
+    // them, or ones a few metres apart, doesn't reveal it
+    let (cell_lat, cell_lng) = snap_to_grid(latitude, longitude, km);
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(km.to_le_bytes());
+    hasher.update(cell_lat.to_le_bytes());
+    hasher.update(cell_lng.to_le_bytes());
+    let digest = hasher.finalize();
+    let unit = |bytes: &[u8]| {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(bytes);
+        u64::from_le_bytes(word) as f64 / u64::MAX as f64
+    };
+
+    let distance = km * (0.5 + 0.5 * unit(&digest[0..8]));
+    let bearing = unit(&digest[8..16]) * std::f64::consts::TAU;
+    let lat = (latitude + lat_degrees(distance * bearing.cos())).clamp(-90.0, 90.0);
+    let lng = (longitude + lng_degrees(distance * bearing.sin(), latitude) + 180.0)
+        .rem_euclid(360.0)
+        - 180.0;
+    (round_coordinate(lat), round_coordinate(lng))
+}
+
+async fn load_species_rules(
+    pool: &sqlx::SqlitePool,
+    upload_id_blob: &[u8],
+) -> Result<Vec<SpeciesRuleRow>, ApiError> {
+    db::query_with_timeout(
+        sqlx::query_as::<_, SpeciesRuleRow>(
+            "SELECT scientific_name, mode, km FROM species_location_rules
+            WHERE upload_id = ? ORDER BY scientific_name",
+        )
+        .bind(upload_id_blob)
+        .fetch_all(pool),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading species location rules", "Database error"))
+}
+
+#[derive(Deserialize)]
+pub struct SpeciesRulePayload {
+    scientific_name: String,
+    mode: String,
+    km: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct LocationPrivacyPayload {
+    mode: String,
+    km: Option<f64>,
+    #[serde(default = "default_sensitive_species")]
+    sensitive_species: bool,
+    #[serde(default)]
+    species: Vec<SpeciesRulePayload>,
+}
+
+const fn default_sensitive_species() -> bool {
+    true
+}
+
+fn rule_proto(scientific_name: String, mode: LocationMode) -> pb::LocationRule {
+    pb::LocationRule {
+        scientific_name,
+        mode: mode.as_str().to_string(),
+        km: mode.km(),
+        common_name: None,
+    }
+}
+
+async fn settings_response(
+    pool: &sqlx::SqlitePool,
+    upload_uuid: &Uuid,
+) -> Result<pb::LocationPrivacy, ApiError> {
+    let upload_id_blob = &upload_uuid.as_bytes()[..];
+    let (location_mode, location_km, sensitive_species, data_version) = db::query_with_timeout(
+        sqlx::query_as::<_, (String, Option<f64>, bool, i64)>(
+            "SELECT location_mode, location_km, sensitive_species, data_version
+            FROM uploads WHERE id = ?",
+        )
+        .bind(upload_id_blob)
+        .fetch_optional(pool),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading location privacy", "Database error"))?
+    .ok_or_else(|| ApiError::not_found("Upload not found"))?;
+
+    let species = load_species_rules(pool, upload_id_blob)
+        .await?
+        .into_iter()
+        .map(|rule| {
+            Ok(rule_proto(
+                rule.scientific_name,
+                stored_mode(&rule.mode, rule.km)?,
+            ))
+        })
+        .collect::<Result<_, ApiError>>()?;
+    let bundled_species = SENSITIVE_SPECIES
+        .iter()
+        .map(|entry| pb::LocationRule {
+            common_name: Some(entry.common_name.clone()),
+            ..rule_proto(entry.scientific_name.clone(), entry.mode)
+        })
+        .collect();
+
+    Ok(pb::LocationPrivacy {
+        mode: location_mode,
+        km: location_km,
+        sensitive_species,
+        species,
+        bundled_species,
+        data_version,
+    })
+}
+
+pub async fn get_location_privacy(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Proto<pb::LocationPrivacy>, ApiError> {
+    check_edit_token(pools.read(), &headers, &upload_id).await?;
+    let upload_uuid = parse_upload_id(&upload_id)?;
+    Ok(Proto::new(
+        settings_response(pools.read(), &upload_uuid).await?,
+    ))
+}
+
+pub async fn set_location_privacy(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<LocationPrivacyPayload>,
+) -> Result<Proto<pb::LocationPrivacy>, ApiError> {
+    check_edit_token(pools.read(), &headers, &upload_id).await?;
+    let upload_uuid = parse_upload_id(&upload_id)?;
+    let upload_id_blob = &upload_uuid.as_bytes()[..];
+
+    let mode =
+        LocationMode::parse(payload.mode.trim(), payload.km).map_err(ApiError::bad_request)?;
+    if payload.species.len() > MAX_SPECIES_RULES {
+        return Err(ApiError::bad_request(format!(
+            "At most {MAX_SPECIES_RULES} species rules are allowed"
+        )));
+    }
+    let mut rules: HashMap<String, (String, LocationMode)> = HashMap::new();
+    for rule in payload.species {
+        let name = rule.scientific_name.trim().to_string();
+        if name.is_empty() {
+            return Err(ApiError::bad_request(
+                "Species rules need a scientific_name",
+            ));
+        }
+        let rule_mode = LocationMode::parse(rule.mode.trim(), rule.km)
+            .map_err(|e| ApiError::bad_request(format!("{name}: {e}")))?;
+        rules.insert(species_key(&name), (name, rule_mode));
+    }
+    if mode != LocationMode::Exact || !rules.is_empty() {
+        ensure_not_collection_member(pools.read(), &upload_uuid).await?;
+    }
+
+    let mut tx = db::query_with_timeout(pools.write().begin())
+        .await
+        .map_err(|e| e.into_api_error("starting location privacy update", "Database error"))?;
+
+    db::query_with_timeout(
+        sqlx::query(
+            "UPDATE uploads SET location_mode = ?, location_km = ?, sensitive_species = ?,
+                data_version = data_version + 1
+            WHERE id = ?",
+        )
+        .bind(mode.as_str())
+        .bind(mode.km())
+        .bind(payload.sensitive_species)
+        .bind(upload_id_blob)
+        .execute(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("updating location privacy", "Database error"))?;
+
+    db::query_with_timeout(
+        sqlx::query("DELETE FROM species_location_rules WHERE upload_id = ?")
+            .bind(upload_id_blob)
+            .execute(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("clearing species location rules", "Database error"))?;
+
+    let rules: Vec<(String, LocationMode)> = rules.into_values().collect();
+    for chunk in rules.chunks(RULE_INSERT_BATCH_SIZE) {
+        let mut qb = QueryBuilder::<Sqlite>::new(
+            "INSERT INTO species_location_rules (upload_id, scientific_name, mode, km) ",
+        );
+        qb.push_values(chunk, |mut b, (name, rule_mode)| {
+            b.push_bind(upload_id_blob)
+                .push_bind(name)
+                .push_bind(rule_mode.as_str())
+                .push_bind(rule_mode.km());
+        });
+        db::query_with_timeout(qb.build().execute(&mut *tx))
+            .await
+            .map_err(|e| e.into_api_error("storing species location rules", "Database error"))?;
+    }
+
+    db::query_with_timeout(tx.commit())
+        .await
+        .map_err(|e| e.into_api_error("committing location privacy", "Database error"))?;
+
+    invalidate_upload_cache(&upload_id).await;
+
+    Ok(Proto::new(
+        settings_response(pools.write(), &upload_uuid).await?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Shown coordinates are rounded, so allow a couple of metres either way
+    const ROUNDING_KM: f64 = 0.002;
+
+    fn places() -> impl Iterator<Item = (f64, f64)> {
+        (0..200).map(|idx| {
+            let idx = f64::from(idx);
+            ((idx * 37.77) % 140.0 - 70.0, (idx * 91.13) % 360.0 - 180.0)
+        })
+    }
+
+    fn policy(default: LocationMode, species: &[(&str, LocationMode)]) -> LocationPolicy {
+        LocationPolicy {
+            default,
+            species: species
+                .iter()
+                .map(|(name, mode)| (species_key(name), *mode))
+                .collect(),
+            salt: b"salt".to_vec(),
+        }
+    }
+
+    #[test]
+    fn grid_snaps_within_half_a_cell() {
+        for km in [0.5, 2.0, 10.0, 50.0] {
+            for (latitude, longitude) in places() {
+                let (lat, lng) = snap_to_grid(latitude, longitude, km);
+                let lat_km = (lat - latitude).abs() * KM_PER_DEGREE;
+                let lng_km = (lng - longitude).abs() / lng_degrees(km, lat) * km;
+                assert!(lat_km <= km / 2.0 + ROUNDING_KM, "{latitude},{longitude}");
+                assert!(lng_km <= km / 2.0 + ROUNDING_KM, "{latitude},{longitude}");
+            }
+        }
+    }
+
+    #[test]
+    fn jitter_moves_between_half_and_whole_distance() {
+        for km in [0.5, 2.0, 10.0, 50.0] {
+            for (latitude, longitude) in places() {
+                let (lat, lng) = jitter(b"salt", latitude, longitude, km);
+                let north_km = (lat - latitude) * KM_PER_DEGREE;
+                // Jitter can carry a place across the antimeridian
+                let east = (lng - longitude + 540.0).rem_euclid(360.0) - 180.0;
+                let east_km = east / lng_degrees(1.0, latitude);
+                let moved = north_km.hypot(east_km);
+                assert!(
+                    (km / 2.0 - ROUNDING_KM..=km + ROUNDING_KM).contains(&moved),
+                    "{latitude},{longitude} moved {moved} km"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn jitter_shares_an_offset_within_a_cell() {
+        let km = 5.0;
+        let (latitude, longitude) = snap_to_grid(52.2, 0.12, km);
+        let nearby = (latitude + 0.001, longitude - 0.001);
+        assert_eq!(snap_to_grid(nearby.0, nearby.1, km), (latitude, longitude));
+
+        let offset = |(lat, lng): (f64, f64)| {
+            let (shown_lat, shown_lng) = jitter(b"salt", lat, lng, km);
+            (shown_lat - lat, shown_lng - lng)
+        };
+        let (lat_a, lng_a) = offset((latitude, longitude));
+        let (lat_b, lng_b) = offset(nearby);
+        assert!((lat_a - lat_b).abs() < 2.0 / COORDINATE_SCALE);
+        assert!((lng_a - lng_b).abs() < 2.0 / COORDINATE_SCALE);
+
+        assert_ne!(
+            jitter(b"salt", latitude, longitude, km),
+            jitter(b"other salt", latitude, longitude, km)
+        );
+    }
+
+    #[test]
+    fn hidden_species_have_no_position() {
+        let policy = policy(
+            LocationMode::Exact,
+            &[("Aquila chrysaetos", LocationMode::Hide)],
+        );
+        assert_eq!(policy.apply("Aquila chrysaetos", 57.1, -4.2), None);
+        assert_eq!(
+            policy.apply("Aquila chrysaetos chrysaetos", 57.1, -4.2),
+            None
+        );
+        assert_eq!(
+            policy.apply("Erithacus rubecula", 57.1, -4.2),
+            Some((57.1, -4.2))
+        );
+        assert_eq!(policy.apply_mode(LocationMode::Hide, 57.1, -4.2), None);
+    }
+
+    #[test]
+    fn share_links_are_at_least_a_ten_km_grid() {
+        let mut policy = policy(
+            LocationMode::Exact,
+            &[
+                ("Aquila chrysaetos", LocationMode::Hide),
+                ("Tetrao urogallus", LocationMode::Jitter(25.0)),
+                ("Lagopus muta", LocationMode::Grid(1.0)),
+                ("Erithacus rubecula", LocationMode::Jitter(2.0)),
+            ],
+        );
+        policy.coarsen_for_share();
+
+        assert_eq!(policy.default_mode(), LocationMode::Grid(SHARE_GRID_KM));
+        assert_eq!(policy.mode_for("Aquila chrysaetos"), LocationMode::Hide);
+        assert_eq!(
+            policy.mode_for("Tetrao urogallus"),
+            LocationMode::Jitter(25.0)
+        );
+        assert_eq!(
+            policy.mode_for("Lagopus muta"),
+            LocationMode::Grid(SHARE_GRID_KM)
+        );
+        assert_eq!(
+            policy.mode_for("Erithacus rubecula"),
+            LocationMode::Grid(SHARE_GRID_KM)
+        );
+        assert_eq!(policy.max_shift_km(), 25.0);
+    }
+}
@@ -1,10 +1,10 @@
 use axum::body::Body;
 use axum::error_handling::HandleErrorLayer;
 use axum::extract::{ConnectInfo, Extension, Path, Query, State};
-use axum::http::{header, HeaderValue, Request, StatusCode};
+use axum::http::{header, HeaderMap, HeaderValue, Request, StatusCode};
 use axum::middleware::{from_fn, Next};
 use axum::response::{IntoResponse, Response};
-use axum::routing::{get, post, put};
+use axum::routing::{delete, get, patch, post, put};
 use axum::{BoxError, Router};
 use dashmap::DashMap;
 use ipnet::IpNet;
@@ -32,7 +32,10 @@ use redgrouse::filter::{build_filter_clause, CountQuery, FilterRequest, TableAli
 use redgrouse::handlers;
 use redgrouse::limits::{UploadLimitError, UploadLimiter, UploadUsageTracker};
 use redgrouse::proto::{pb, Proto};
-use redgrouse::{db, sightings, stats, tiles, upload};
+use redgrouse::{
+    backup, collections, db, edit_tokens, export, import_report, location_privacy, shares,
+    sighting_edits, sightings, static_map, stats, taxonomy, tiles, timezone, upload, view_password,
+};
 
 const BUILD_VERSION: &str = env!("BUILD_VERSION");
 const BUILD_DATE: &str = env!("BUILD_DATE");
@@ -102,14 +105,28 @@ async fn main() -> anyhow::Result<()> {
     let database_url =
         env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:redgrouse.db".to_string());
 
+    timezone::load_boundaries()?;
+    static_map::load_land_polygons()?;
+
     let pools = db::init_pool(&database_url).await?;
     db::run_migrations(&pools).await?;
+    let moved_years = timezone::backfill_local_dates(pools.write()).await?;
     db::vacuum_database(&pools).await;
 
-    let retention_days: i64 = env::var("REDGROUSE_DATA_RETENTION_DAYS")
-        .unwrap_or_else(|_| "365".to_string())
-        .parse()
-        .unwrap_or(365);
+    let reconcile_pools = pools.clone();
+    tokio::spawn(async move {
+        if let Err(e) = taxonomy::reconcile_species(&reconcile_pools).await {
+            e.log("reconciling species with the taxonomy");
+        }
+        if let Err(e) = db::recompute_deduplicated_uploads(&reconcile_pools).await {
+            e.log("recomputing ticks for uploads with removed duplicates");
+        }
+        if let Err(e) = timezone::refresh_moved_years(&reconcile_pools, moved_years).await {
+            e.log("recomputing ticks for sightings moved to another year");
+        }
+    });
+
+    let retention_days = config::retention_days();
 
     let write_pool = pools.write().clone();
     tokio::spawn(async move {
@@ -159,7 +176,21 @@ async fn main() -> anyhow::Result<()> {
     let ingest_routes = Router::new()
         .route(api_constants::UPLOAD_ROUTE, post(upload::upload_csv))
         .route(api_constants::UPLOAD_DETAILS_ROUTE, put(upload::update_csv))
-        .route_layer(ingest_layer);
+        .route(api_constants::UPLOAD_APPEND_ROUTE, post(upload::append_csv))
+        .route(
+            api_constants::UPLOAD_RESTORE_ROUTE,
+            post(upload::restore_upload),
+        )
+        .route(
+            api_constants::COLLECTIONS_ROUTE,
+            post(collections::create_collection),
+        )
+        .route(
+            api_constants::COLLECTION_MEMBER_ROUTE,
+            put(collections::add_collection_member).delete(collections::remove_collection_member),
+        )
+        .route_layer(ingest_layer)
+        .route_layer(from_fn(enforce_upload_limit));
 
     let rate_limiter = RequestRateLimiter::new(GLOBAL_RATE_LIMIT_PER_MINUTE, RATE_LIMIT_WINDOW);
     let (cloudfront_result, cloudflare_result) =
@@ -215,17 +246,96 @@ async fn main() -> anyhow::Result<()> {
         .route(api_constants::UPLOAD_BBOX_ROUTE, get(get_bbox))
         .route(
             api_constants::UPLOAD_SIGHTINGS_ROUTE,
-            get(sightings::get_sightings),
+            get(sightings::get_sightings).post(sighting_edits::create_sighting),
+        )
+        .route(
+            api_constants::UPLOAD_SIGHTING_ROUTE,
+            patch(sighting_edits::update_sighting).delete(sighting_edits::delete_sighting),
         )
         .route(api_constants::UPLOAD_STATS_ROUTE, get(stats::get_stats))
+        .route(
+            api_constants::UPLOAD_IMPORT_REPORT_ROUTE,
+            get(import_report::get_import_report),
+        )
+        .route(
+            api_constants::UPLOAD_EXPORT_GEOJSON_ROUTE,
+            get(export::export_geojson),
+        )
+        .route(
+            api_constants::UPLOAD_EXPORT_KML_ROUTE,
+            get(export::export_kml),
+        )
+        .route(
+            api_constants::UPLOAD_EXPORT_CSV_ROUTE,
+            get(export::export_csv),
+        )
+        .route(
+            api_constants::UPLOAD_MAP_PNG_ROUTE,
+            get(static_map::static_map_png),
+        )
+        .route(
+            api_constants::UPLOAD_MAP_SVG_ROUTE,
+            get(static_map::static_map_svg),
+        )
+        .route(
+            api_constants::UPLOAD_BACKUP_ROUTE,
+            get(backup::download_backup),
+        )
+        .route(
+            api_constants::UPLOAD_RETENTION_ROUTE,
+            put(upload::set_retention),
+        )
+        .route(
+            api_constants::UPLOAD_SHARES_ROUTE,
+            get(shares::list_shares).post(shares::create_share),
+        )
+        .route(
+            api_constants::UPLOAD_SHARE_ROUTE,
+            delete(shares::revoke_share),
+        )
+        .route(
+            api_constants::UPLOAD_LOCATION_PRIVACY_ROUTE,
+            get(location_privacy::get_location_privacy).put(location_privacy::set_location_privacy),
+        )
+        .route(
+            api_constants::UPLOAD_EDIT_TOKENS_ROUTE,
+            get(edit_tokens::list_edit_tokens).post(edit_tokens::create_edit_token),
+        )
+        .route(
+            api_constants::UPLOAD_EDIT_TOKEN_ROUTE,
+            delete(edit_tokens::revoke_edit_token),
+        )
+        .route(
+            api_constants::UPLOAD_EDIT_TOKEN_ROTATE_ROUTE,
+            post(edit_tokens::rotate_edit_token),
+        )
+        .route(
+            api_constants::UPLOAD_PASSWORD_ROUTE,
+            put(view_password::set_view_password),
+        )
+        .route(
+            api_constants::UPLOAD_UNLOCK_ROUTE,
+            post(view_password::unlock_upload),
+        )
+        .route(
+            api_constants::COLLECTION_DETAILS_ROUTE,
+            get(collections::get_collection),
+        )
         .route(api_constants::TILE_ROUTE, get(tiles::get_tile))
+        .route(
+            api_constants::DENSITY_TILE_ROUTE,
+            get(tiles::get_density_tile),
+        )
+        .route(
+            api_constants::CLUSTER_TILE_ROUTE,
+            get(tiles::get_cluster_tile),
+        )
         .route(api_constants::FIELDS_ROUTE, get(handlers::fields_metadata))
         .route(
             api_constants::FIELD_VALUES_ROUTE,
             get(handlers::field_values),
         )
         .merge(ingest_routes)
-        .layer(from_fn(enforce_upload_limit))
         .layer(from_fn(enforce_rate_limit))
         .layer(build_version_header)
         .layer(cors)
@@ -652,10 +762,12 @@ async fn get_bbox(
     State(pools): State<DbPools>,
     Path(upload_id): Path<String>,
     Query(query): Query<CountQuery>,
+    headers: HeaderMap,
 ) -> Result<impl axum::response::IntoResponse, ApiError> {
-    let upload_uuid = uuid::Uuid::parse_str(&upload_id)
-        .map_err(|_| ApiError::bad_request("Invalid upload_id format"))?;
+    let access = shares::resolve_upload_access(pools.read(), &upload_id, &headers).await?;
+    let upload_uuid = *access.upload_uuid();
     let data_version = upload::get_upload_data_version(pools.read(), &upload_uuid).await?;
+    let policy = location_privacy::LocationPolicy::load(pools.read(), &access).await?;
 
     let tick_visibility = query.tick_visibility()?;
 
@@ -663,15 +775,22 @@ async fn get_bbox(
         pool: pools.read(),
         upload_id: &upload_uuid.as_bytes()[..],
         filter_json: query.filter.as_ref(),
+        scope_filter: access.scope(),
         year_tick_year: query.year_tick_year,
         country_tick_country: query.country_tick_country.as_ref(),
-        aliases: TableAliases::new(None, None),
+        aliases: TableAliases::new(Some("s"), Some("sp")),
         tick_visibility: &tick_visibility,
     })
     .await?;
 
+    // Location privacy can differ by species, so bound each one separately
+    let (species_column, group_by) = if policy.is_exact() {
+        ("", "")
+    } else {
+        ("sp.scientific_name, ", " GROUP BY s.species_id")
+    };
     let sql = format!(
-        "SELECT MIN(longitude) as min_lng, MIN(latitude) as min_lat, MAX(longitude) as max_lng, MAX(latitude) as max_lat FROM sightings WHERE upload_id = ?{}",
+        "SELECT {species_column}MIN(s.longitude) as min_lng, MIN(s.latitude) as min_lat, MAX(s.longitude) as max_lng, MAX(s.latitude) as max_lat FROM sightings s JOIN species sp ON s.species_id = sp.id WHERE s.upload_id = ?{}{group_by}",
         filter_sql.clause()
     );
 
@@ -681,36 +800,55 @@ async fn get_bbox(
         db_query = db_query.bind(param);
     }
 
-    let row = db::query_with_timeout(db_query.fetch_optional(pools.read()))
+    let rows = db::query_with_timeout(db_query.fetch_all(pools.read()))
         .await
         .map_err(|e| e.into_api_error("getting bounding box", "Database error"))?;
 
-    let Some(row) = row else {
-        return Ok(Response::builder()
-            .status(StatusCode::NO_CONTENT)
-            .header("x-upload-version", data_version.to_string())
-            .body(Body::empty())
-            .unwrap());
-    };
-
-    let min_lng: Option<f64> = row.get("min_lng");
-    let min_lat: Option<f64> = row.get("min_lat");
-    let max_lng: Option<f64> = row.get("max_lng");
-    let max_lat: Option<f64> = row.get("max_lat");
+    let mut bounds: Option<[f64; 4]> = None;
+    for row in rows {
+        let min_lng: Option<f64> = row.get("min_lng");
+        let min_lat: Option<f64> = row.get("min_lat");
+        let max_lng: Option<f64> = row.get("max_lng");
+        let max_lat: Option<f64> = row.get("max_lat");
+        let (Some(min_lng), Some(min_lat), Some(max_lng), Some(max_lat)) =
+            (min_lng, min_lat, max_lng, max_lat)
+        else {
+            continue;
+        };
+        let scientific_name: &str = if policy.is_exact() {
+            ""
+        } else {
+            row.get("scientific_name")
+        };
+        let Some(species_bounds) =
+            policy.bounds(scientific_name, [min_lng, min_lat, max_lng, max_lat])
+        else {
+            continue;
+        };
+        bounds = Some(match bounds {
+            Some([a, b, c, d]) => [
+                a.min(species_bounds[0]),
+                b.min(species_bounds[1]),
+                c.max(species_bounds[2]),
+                d.max(species_bounds[3]),
+            ],
+            None => species_bounds,
+        });
+    }
 
-    if min_lng.is_none() || min_lat.is_none() || max_lng.is_none() || max_lat.is_none() {
+    let Some([min_lng, min_lat, max_lng, max_lat]) = bounds else {
         return Ok(Response::builder()
             .status(StatusCode::NO_CONTENT)
             .header("x-upload-version", data_version.to_string())
             .body(Body::empty())
             .unwrap());
-    }
+    };
 
     Ok(Proto::new(pb::BboxResponse {
-        min_lng: min_lng.unwrap(),
-        min_lat: min_lat.unwrap(),
-        max_lng: max_lng.unwrap(),
-        max_lat: max_lat.unwrap(),
+        min_lng,
+        min_lat,
+        max_lng,
+        max_lat,
         data_version,
     })
     .into_response())
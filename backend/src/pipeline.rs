@@ -1,6 +1,9 @@
 use crate::db::{self, DbQueryError};
 use crate::error::ApiError;
+use crate::taxonomy::{SpeciesClass, Taxon, Taxonomy};
 use crate::tiles::LatLng;
+use crate::timezone;
+use chrono::{Datelike, NaiveDate};
 use country_boundaries::{CountryBoundaries, LatLon, BOUNDARIES_ODBL_360X180};
 use csv_async::{ByteRecord, StringRecord};
 use once_cell::sync::Lazy;
@@ -8,6 +11,8 @@ use smartstring::{LazyCompact, SmartString};
 use sqlx::{Acquire, QueryBuilder, Sqlite, Transaction};
 use std::collections::{hash_map::DefaultHasher, HashMap, HashSet};
 use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use tokio::sync::Mutex;
 use tracing::error;
 use uuid::Uuid;
 
@@ -25,21 +30,14 @@ pub const BATCH_SIZE: usize = 1000;
 pub const MAX_UPLOAD_ROWS: usize = 250_000;
 const MAX_CSV_COLUMNS: usize = 256;
 const MAX_RECORD_BYTES: usize = 8 * 1024; // 8 KiB per record to prevent line bombs
-const SQLITE_MAX_VARIABLES: usize = 999;
+pub(crate) const SQLITE_MAX_VARIABLES: usize = 999;
 const SPECIES_LOOKUP_BATCH_SIZE: usize = SQLITE_MAX_VARIABLES / 2;
-
-const COL_SIGHTING_ID: &str = "sightingId";
-const COL_DATE: &str = "date";
-const COL_LONGITUDE: &str = "longitude";
-const COL_LATITUDE: &str = "latitude";
-const COL_SCIENTIFIC_NAME: &str = "scientificName";
-const COL_COMMON_NAME: &str = "commonName";
-const COL_COUNT: &str = "count";
+const SPECIES_INSERT_BATCH_SIZE: usize = SQLITE_MAX_VARIABLES / 5;
 
 /// Raw sighting data parsed from CSV (before geocoding)
 #[derive(Debug, Clone)]
 pub struct ParsedSighting {
-    pub sighting_uuid: String,
+    pub sighting_uuid: Uuid,
     pub common_name: String,
     pub scientific_name: Option<String>,
     pub count: i32,
@@ -48,9 +46,58 @@ pub struct ParsedSighting {
     pub observed_at: String,
 }
 
+impl ParsedSighting {
+    /// Hash of every stored field an import can set, so append can tell rows
+    /// that are already stored unchanged from corrected ones.
+    pub fn fingerprint(&self) -> u64 {
+        let (common_name, scientific_name) = canonical_species_key(
+            &self.common_name,
+            self.scientific_name.as_deref().unwrap_or_default(),
+        );
+        sighting_fingerprint(
+            &common_name,
+            &scientific_name,
+            i64::from(self.count),
+            self.latitude,
+            self.longitude,
+            &self.observed_at,
+        )
+    }
+}
+
+pub(crate) fn sighting_fingerprint(
+    common_name: &str,
+    scientific_name: &str,
+    count: i64,
+    latitude: f64,
+    longitude: f64,
+    observed_at: &str,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    common_name.hash(&mut hasher);
+    scientific_name.hash(&mut hasher);
+    count.hash(&mut hasher);
+    latitude.to_bits().hash(&mut hasher);
+    longitude.to_bits().hash(&mut hasher);
+    observed_at.hash(&mut hasher);
+    hasher.finish()
+}
+
 type SString = SmartString<LazyCompact>;
 type SpeciesKey = (SString, SString);
 
+/// Names a species is stored under: the taxonomy's canonical names when it
+/// recognises them, otherwise the names as given.
+fn canonical_species_key(common_name: &str, scientific_name: &str) -> SpeciesKey {
+    match Taxonomy::global().lookup(common_name, scientific_name) {
+        Some(taxon) => (
+            taxon.common_name.as_str().into(),
+            taxon.scientific_name.as_str().into(),
+        ),
+        None => (common_name.into(), scientific_name.into()),
+    }
+}
+
 /// Fully processed sighting ready for database insertion
 #[derive(Debug, Clone)]
 pub struct ProcessedSighting {
@@ -65,114 +112,15 @@ pub struct ProcessedSighting {
     pub region_code: Option<SString>,
     // ISO dates "YYYY-MM-DD" are 10 bytes -> fit inline perfectly
     pub observed_at: SString,
+    // Date where the sighting was made, which ticks and streaks go by
+    pub local_date: SString,
     pub count: i32,
     pub latitude: f64,
     pub longitude: f64,
     pub year: i32,
-    // Tick flags (computed during upload)
-    pub lifer: bool,
-    pub year_tick: bool,
-    pub country_tick: bool,
     pub vis_rank: i32,
 }
 
-pub struct CsvParser {
-    col_map: ColumnMap,
-    row_number: usize,
-}
-
-impl CsvParser {
-    pub fn new(headers: &StringRecord) -> Result<Self, ApiError> {
-        validate_header_limits(headers)?;
-        let col_map = ColumnMap::from_headers(headers);
-        if !col_map.is_valid() {
-            error!("CSV missing required columns");
-            return Err(ApiError::bad_request(
-                "CSV missing required columns (sightingId, date, longitude, latitude, commonName)",
-            ));
-        }
-        Ok(Self {
-            col_map,
-            row_number: 1,
-        })
-    }
-
-    pub fn parse_row(&mut self, record: &ByteRecord) -> Result<Option<ParsedSighting>, ApiError> {
-        enforce_record_limits(record, self.row_number)?;
-        self.row_number += 1;
-
-        let Some(sighting_uuid) = get_field(
-            record,
-            self.col_map.sighting_id,
-            COL_SIGHTING_ID,
-            self.row_number - 1,
-        )?
-        else {
-            return Ok(None);
-        };
-        let Some(common_name) = get_field(
-            record,
-            self.col_map.common_name,
-            COL_COMMON_NAME,
-            self.row_number - 1,
-        )?
-        else {
-            return Ok(None);
-        };
-        let Some(observed_at) =
-            get_field(record, self.col_map.date, COL_DATE, self.row_number - 1)?
-        else {
-            return Ok(None);
-        };
-
-        let latitude = match get_field(
-            record,
-            self.col_map.latitude,
-            COL_LATITUDE,
-            self.row_number - 1,
-        )? {
-            Some(value) => match value.parse::<f64>() {
-                Ok(parsed) => parsed,
-                Err(_) => return Ok(None),
-            },
-            None => return Ok(None),
-        };
-        let longitude = match get_field(
-            record,
-            self.col_map.longitude,
-            COL_LONGITUDE,
-            self.row_number - 1,
-        )? {
-            Some(value) => match value.parse::<f64>() {
-                Ok(parsed) => parsed,
-                Err(_) => return Ok(None),
-            },
-            None => return Ok(None),
-        };
-
-        let count: i32 = get_field(record, self.col_map.count, COL_COUNT, self.row_number - 1)?
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(1);
-
-        let scientific_name = get_field(
-            record,
-            self.col_map.scientific_name,
-            COL_SCIENTIFIC_NAME,
-            self.row_number - 1,
-        )?;
-
-        Ok(Some(ParsedSighting {
-            sighting_uuid,
-            common_name,
-            scientific_name,
-            count,
-            latitude,
-            longitude,
-            observed_at,
-        }))
-    }
-}
-
 pub struct Geocoder;
 
 impl Geocoder {
@@ -212,14 +160,15 @@ impl Geocoder {
             .into_iter()
             .zip(geocode_results)
             .map(|(sighting, (country_code, region_code))| {
-                let year = extract_year(&sighting.observed_at);
-                let sighting_uuid = Uuid::parse_str(&sighting.sighting_uuid).unwrap_or_else(|err| {
-                    error!("Invalid UUID format in processed sighting (should be caught during CSV parsing): {} - {}", sighting.sighting_uuid, err);
-                    // Generate a new UUID as fallback - this should never happen in practice
-                    Uuid::new_v4()
-                });
+                let (local_date, year) = local_day(
+                    &sighting.observed_at,
+                    &country_code,
+                    region_code.as_deref(),
+                    sighting.latitude,
+                    sighting.longitude,
+                );
                 ProcessedSighting {
-                    sighting_uuid,
+                    sighting_uuid: sighting.sighting_uuid,
                     common_name: sighting.common_name.into(),
                     scientific_name: sighting.scientific_name.unwrap_or_default().into(),
                     species_id: None, // Will be looked up before insertion
@@ -229,10 +178,8 @@ impl Geocoder {
                     country_code,
                     region_code,
                     observed_at: sighting.observed_at.into(),
+                    local_date,
                     year,
-                    lifer: false, // Will be set during flush
-                    year_tick: false, // Will be set during flush
-                    country_tick: false, // Will be set during flush
                     vis_rank: 0, // Will be set during flush
                 }
             })
@@ -250,11 +197,12 @@ pub struct DbSink {
     upload_id: String,
     batch: Vec<ProcessedSighting>,
     total_rows: usize,
-    // Track seen species/years/countries for tick calculation
-    seen_species: HashSet<i64>,
-    seen_year_ticks: HashSet<(i64, i32)>,
-    seen_country_ticks: HashSet<(i64, String)>,
+    // Rows already in the upload when appending, counted towards the limit
+    existing_rows: usize,
     species_cache: HashMap<(SString, SString), i64>,
+    // IDs of every sighting written so far, kept outside the sink so an
+    // append that fails part way can delete exactly those rows
+    written_ids: Option<Arc<Mutex<Vec<i64>>>>,
 }
 
 impl DbSink {
@@ -263,21 +211,35 @@ impl DbSink {
             upload_id,
             batch: Vec::with_capacity(BATCH_SIZE),
             total_rows: 0,
-            seen_species: HashSet::new(),
-            seen_year_ticks: HashSet::new(),
-            seen_country_ticks: HashSet::new(),
+            existing_rows: 0,
             species_cache: HashMap::new(),
+            written_ids: None,
         }
     }
 
+    pub const fn with_existing_rows(mut self, existing_rows: usize) -> Self {
+        self.existing_rows = existing_rows;
+        self
+    }
+
+    pub fn with_written_ids(mut self, written_ids: Arc<Mutex<Vec<i64>>>) -> Self {
+        self.written_ids = Some(written_ids);
+        self
+    }
+
     pub fn needs_flush(&self) -> bool {
         self.batch.len() >= BATCH_SIZE
     }
 
     pub fn add(&mut self, sighting: ProcessedSighting) -> Result<(), ApiError> {
-        if self.total_rows + self.batch.len() + 1 > MAX_UPLOAD_ROWS {
+        if self.existing_rows + self.total_rows + self.batch.len() + 1 > MAX_UPLOAD_ROWS {
+            let subject = if self.existing_rows > 0 {
+                "Upload"
+            } else {
+                "CSV"
+            };
             return Err(ApiError::bad_request(format!(
-                "CSV exceeds {MAX_UPLOAD_ROWS} row limit"
+                "{subject} exceeds {MAX_UPLOAD_ROWS} row limit"
             )));
         }
 
@@ -317,50 +279,22 @@ impl DbSink {
 
         self.resolve_species_ids(&mut *conn).await?;
 
-        // Compute tick flags
+        // Ticks depend on when each sighting was made rather than where it
+        // sits in the file, so they're worked out over the whole upload once
+        // ingest finishes (see `recompute_tick_flags`), which also moves
+        // ticks to vis_rank 0
         for sighting in &mut self.batch {
-            let species_id = sighting.species_id.expect("species_id should be set");
-
-            // Check for lifer (first sighting of this species in this upload)
-            if !self.seen_species.contains(&species_id) {
-                sighting.lifer = true;
-                self.seen_species.insert(species_id);
-            }
-
-            // Check for year tick (first sighting of this species in this year)
-            let year_tick_key = (species_id, sighting.year);
-            if !self.seen_year_ticks.contains(&year_tick_key) {
-                sighting.year_tick = true;
-                self.seen_year_ticks.insert(year_tick_key);
-            }
-
-            // Check for country tick (first sighting of this species in this country)
-            if !sighting.country_code.is_empty()
-                && !sighting.country_code.eq_ignore_ascii_case("XX")
-            {
-                let country_tick_key = (species_id, sighting.country_code.to_string());
-                if !self.seen_country_ticks.contains(&country_tick_key) {
-                    sighting.country_tick = true;
-                    self.seen_country_ticks.insert(country_tick_key);
-                }
-            }
-
-            // Set vis_rank: 0 for lifers/year_ticks/country_ticks, pseudo-random otherwise
-            if sighting.lifer || sighting.year_tick || sighting.country_tick {
-                sighting.vis_rank = 0;
-            } else {
-                // Use hash of UUID for pseudo-random vis_rank (0-10000)
-                let mut hasher = DefaultHasher::new();
-                sighting.sighting_uuid.hash(&mut hasher);
-                sighting.vis_rank = (hasher.finish() % 10001) as i32;
-            }
+            sighting.vis_rank = vis_rank_for(&sighting.sighting_uuid);
         }
 
-        insert_batch(conn, &self.upload_id, &self.batch)
+        let ids = insert_batch(conn, &self.upload_id, &self.batch)
             .await
             .map_err(|e| {
                 e.into_api_error("inserting sightings batch", "Failed to insert sightings")
             })?;
+        if let Some(written_ids) = &self.written_ids {
+            written_ids.lock().await.extend(ids);
+        }
 
         Ok(())
     }
@@ -376,10 +310,7 @@ impl DbSink {
                 continue;
             }
 
-            let key = (
-                sighting.common_name.clone(),
-                sighting.scientific_name.clone(),
-            );
+            let key = canonical_species_key(&sighting.common_name, &sighting.scientific_name);
 
             if let Some(&cached_id) = self.species_cache.get(&key) {
                 sighting.species_id = Some(cached_id);
@@ -444,6 +375,11 @@ impl DbSink {
     pub fn total_rows(&self) -> usize {
         self.total_rows + self.batch.len()
     }
+
+    /// Species of every row written so far.
+    pub fn species_ids(&self) -> impl Iterator<Item = i64> + '_ {
+        self.species_cache.values().copied()
+    }
 }
 
 async fn fetch_species_ids(
@@ -488,6 +424,38 @@ async fn fetch_species_ids(
     Ok(resolved)
 }
 
+/// Looks up or creates a single species, for edits made outside a CSV import.
+pub(crate) async fn resolve_species_id(
+    conn: &mut sqlx::SqliteConnection,
+    common_name: &str,
+    scientific_name: &str,
+) -> Result<i64, DbQueryError> {
+    let keys = [canonical_species_key(common_name, scientific_name)];
+
+    if let Some((_, id)) = fetch_species_ids(conn, &keys).await?.into_iter().next() {
+        return Ok(id);
+    }
+    if let Some((_, id)) = insert_species_batch(conn, &keys).await?.into_iter().next() {
+        return Ok(id);
+    }
+    // Lost an insert race; the row exists now.
+    fetch_species_ids(conn, &keys)
+        .await?
+        .into_iter()
+        .next()
+        .map(|(_, id)| id)
+        .ok_or(DbQueryError::Sqlx(sqlx::Error::RowNotFound))
+}
+
+struct NewSpecies<'a> {
+    key: &'a SpeciesKey,
+    taxon_id: Option<i64>,
+    countable: bool,
+    parent_species_id: Option<i64>,
+}
+
+/// Inserts species classified against the taxonomy. Subspecies groups need
+/// their parent species to exist first, so those are resolved beforehand.
 async fn insert_species_batch(
     conn: &mut sqlx::SqliteConnection,
     keys: &[SpeciesKey],
@@ -496,24 +464,95 @@ async fn insert_species_batch(
         return Ok(Vec::new());
     }
 
-    let mut inserted = Vec::new();
+    let taxonomy = Taxonomy::global();
+    let classes: Vec<SpeciesClass<'_>> = keys
+        .iter()
+        .map(|key| taxonomy.classify(&key.0, &key.1))
+        .collect();
+
+    let parent_keys: HashSet<SpeciesKey> = classes
+        .iter()
+        .filter_map(|class| class.parent)
+        .map(taxon_key)
+        .collect();
+    let parent_keys: Vec<SpeciesKey> = parent_keys.into_iter().collect();
+    let parent_ids = resolve_parent_species(conn, &parent_keys).await?;
+
+    let rows: Vec<NewSpecies<'_>> = keys
+        .iter()
+        .zip(&classes)
+        .map(|(key, class)| NewSpecies {
+            key,
+            taxon_id: class.taxon.map(|taxon| taxon.id),
+            countable: class.countable,
+            parent_species_id: class
+                .parent
+                .and_then(|parent| parent_ids.get(&taxon_key(parent)).copied()),
+        })
+        .collect();
 
-    for chunk in keys.chunks(SPECIES_LOOKUP_BATCH_SIZE.max(1)) {
-        let mut qb =
-            QueryBuilder::new("INSERT INTO species (common_name, scientific_name) VALUES ");
+    insert_species_rows(conn, &rows).await
+}
 
-        let mut first = true;
-        for key in chunk {
-            if !first {
-                qb.push(", ");
-            }
-            first = false;
-            qb.push("(")
-                .push_bind(key.0.as_str())
-                .push(", ")
-                .push_bind(key.1.as_str())
-                .push(")");
-        }
+fn taxon_key(taxon: &Taxon) -> SpeciesKey {
+    (
+        taxon.common_name.as_str().into(),
+        taxon.scientific_name.as_str().into(),
+    )
+}
+
+/// Parents are always full species, so they never need a parent of their own.
+async fn resolve_parent_species(
+    conn: &mut sqlx::SqliteConnection,
+    keys: &[SpeciesKey],
+) -> Result<HashMap<SpeciesKey, i64>, DbQueryError> {
+    let mut resolved: HashMap<SpeciesKey, i64> =
+        fetch_species_ids(conn, keys).await?.into_iter().collect();
+
+    let taxonomy = Taxonomy::global();
+    let missing: Vec<NewSpecies<'_>> = keys
+        .iter()
+        .filter(|key| !resolved.contains_key(*key))
+        .map(|key| NewSpecies {
+            key,
+            taxon_id: taxonomy.lookup(&key.0, &key.1).map(|taxon| taxon.id),
+            countable: true,
+            parent_species_id: None,
+        })
+        .collect();
+    if missing.is_empty() {
+        return Ok(resolved);
+    }
+    resolved.extend(insert_species_rows(conn, &missing).await?);
+
+    // Lost an insert race; the rows exist now.
+    let retry_keys: Vec<SpeciesKey> = keys
+        .iter()
+        .filter(|key| !resolved.contains_key(*key))
+        .cloned()
+        .collect();
+    resolved.extend(fetch_species_ids(conn, &retry_keys).await?);
+
+    Ok(resolved)
+}
+
+async fn insert_species_rows(
+    conn: &mut sqlx::SqliteConnection,
+    rows: &[NewSpecies<'_>],
+) -> Result<Vec<(SpeciesKey, i64)>, DbQueryError> {
+    let mut inserted = Vec::new();
+
+    for chunk in rows.chunks(SPECIES_INSERT_BATCH_SIZE) {
+        let mut qb = QueryBuilder::new(
+            "INSERT INTO species (common_name, scientific_name, taxon_id, countable, parent_species_id) ",
+        );
+        qb.push_values(chunk, |mut b, row| {
+            b.push_bind(row.key.0.as_str())
+                .push_bind(row.key.1.as_str())
+                .push_bind(row.taxon_id)
+                .push_bind(row.countable)
+                .push_bind(row.parent_species_id);
+        });
         qb.push(" ON CONFLICT DO NOTHING RETURNING common_name, scientific_name, id");
 
         let rows = db::query_with_timeout(
@@ -551,20 +590,21 @@ async fn insert_batch(
     conn: &mut sqlx::SqliteConnection,
     upload_id: &str,
     rows: &[ProcessedSighting],
-) -> Result<(), DbQueryError> {
+) -> Result<Vec<i64>, DbQueryError> {
+    let mut ids = Vec::with_capacity(rows.len());
     if rows.is_empty() {
-        return Ok(());
+        return Ok(ids);
     }
 
     let upload_uuid = Uuid::parse_str(upload_id)
         .map_err(|_| DbQueryError::Sqlx(sqlx::Error::Decode("Invalid UUID format".into())))?;
     let upload_blob = upload_uuid.as_bytes();
-    const COLUMNS_PER_ROW: usize = 14;
+    const COLUMNS_PER_ROW: usize = 12;
     let max_rows_per_chunk = (SQLITE_MAX_VARIABLES / COLUMNS_PER_ROW).max(1);
 
     for chunk in rows.chunks(max_rows_per_chunk) {
         let mut qb = QueryBuilder::<Sqlite>::new(
-            "INSERT INTO sightings (upload_id, sighting_uuid, species_id, count, latitude, longitude, country_code, region_code, observed_at, year, lifer, year_tick, country_tick, vis_rank) VALUES ",
+            "INSERT INTO sightings (upload_id, sighting_uuid, species_id, count, latitude, longitude, country_code, region_code, observed_at, local_date, year, vis_rank) VALUES ",
         );
 
         for (idx, sighting) in chunk.iter().enumerate() {
@@ -595,26 +635,23 @@ async fn insert_batch(
             qb.push(", ");
             qb.push_bind(sighting.observed_at.as_str());
             qb.push(", ");
-            qb.push_bind(sighting.year);
-            qb.push(", ");
-            qb.push_bind(i32::from(sighting.lifer));
-            qb.push(", ");
-            qb.push_bind(i32::from(sighting.year_tick));
+            qb.push_bind(sighting.local_date.as_str());
             qb.push(", ");
-            qb.push_bind(i32::from(sighting.country_tick));
+            qb.push_bind(sighting.year);
             qb.push(", ");
             qb.push_bind(sighting.vis_rank);
             qb.push(")");
         }
+        qb.push(" RETURNING id");
 
-        let query = qb.build();
-        db::query_with_timeout(query.execute(&mut *conn)).await?;
+        let query = qb.build_query_scalar::<i64>();
+        ids.extend(db::query_with_timeout(query.fetch_all(&mut *conn)).await?);
     }
 
-    Ok(())
+    Ok(ids)
 }
 
-fn validate_header_limits(headers: &StringRecord) -> Result<(), ApiError> {
+pub(crate) fn validate_header_limits(headers: &StringRecord) -> Result<(), ApiError> {
     let column_count = headers.len();
     if column_count > MAX_CSV_COLUMNS {
         return Err(ApiError::bad_request(format!(
@@ -624,7 +661,10 @@ fn validate_header_limits(headers: &StringRecord) -> Result<(), ApiError> {
     Ok(())
 }
 
-fn enforce_record_limits(record: &ByteRecord, row_number: usize) -> Result<(), ApiError> {
+pub(crate) fn enforce_record_limits(
+    record: &ByteRecord,
+    row_number: usize,
+) -> Result<(), ApiError> {
     if record.len() > MAX_CSV_COLUMNS {
         return Err(ApiError::bad_request(format!(
             "Row {} has {} columns; maximum supported is {}",
@@ -644,45 +684,7 @@ fn enforce_record_limits(record: &ByteRecord, row_number: usize) -> Result<(), A
     Ok(())
 }
 
-#[derive(Default)]
-struct ColumnMap {
-    sighting_id: Option<usize>,
-    date: Option<usize>,
-    longitude: Option<usize>,
-    latitude: Option<usize>,
-    scientific_name: Option<usize>,
-    common_name: Option<usize>,
-    count: Option<usize>,
-}
-
-impl ColumnMap {
-    fn from_headers(headers: &StringRecord) -> Self {
-        let mut map = Self::default();
-        for (idx, header) in headers.iter().enumerate() {
-            match header {
-                COL_SIGHTING_ID => map.sighting_id = Some(idx),
-                COL_DATE => map.date = Some(idx),
-                COL_LONGITUDE => map.longitude = Some(idx),
-                COL_LATITUDE => map.latitude = Some(idx),
-                COL_SCIENTIFIC_NAME => map.scientific_name = Some(idx),
-                COL_COMMON_NAME => map.common_name = Some(idx),
-                COL_COUNT => map.count = Some(idx),
-                _ => {}
-            }
-        }
-        map
-    }
-
-    const fn is_valid(&self) -> bool {
-        self.sighting_id.is_some()
-            && self.date.is_some()
-            && self.longitude.is_some()
-            && self.latitude.is_some()
-            && self.common_name.is_some()
-    }
-}
-
-fn get_field(
+pub(crate) fn get_field(
     record: &ByteRecord,
     idx: Option<usize>,
     field_name: &str,
@@ -716,6 +718,57 @@ fn get_field(
     Ok(Some(trimmed.to_string()))
 }
 
+/// Pseudo-random vis_rank (0-10000) for sightings that aren't ticks, taken
+/// from the last four bytes of the UUID so it stays stable across re-imports
+/// and can be recomputed in SQL by [`vis_rank_sql`].
+pub(crate) fn vis_rank_for(sighting_uuid: &Uuid) -> i32 {
+    let bytes = sighting_uuid.as_bytes();
+    let tail = u32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]);
+    (tail % 10001) as i32
+}
+
+/// SQL for [`vis_rank_for`] over a `sighting_uuid` blob column. SQLite has no
+/// way to read a blob as a number, so the last four bytes are summed up from
+/// their hex digits.
+pub(crate) fn vis_rank_sql(column: &str) -> String {
+    let digits: Vec<String> = (0..8)
+        .map(|i| {
+            format!(
+                "(instr('0123456789ABCDEF', substr(hex({column}), {}, 1)) - 1) * {}",
+                25 + i,
+                1_u64 << (4 * (7 - i))
+            )
+        })
+        .collect();
+    format!("(({}) % 10001)", digits.join(" + "))
+}
+
+/// The calendar date at the start of an ISO 8601 date or timestamp.
+pub(crate) fn parse_observed_date(value: &str) -> Option<NaiveDate> {
+    value
+        .get(0..10)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+}
+
+/// Calendar date and year a sighting was made on, in the local time of where
+/// it was made. Falls back to the date as written if the timestamp can't be
+/// parsed.
+pub(crate) fn local_day(
+    observed_at: &str,
+    country_code: &str,
+    region_code: Option<&str>,
+    latitude: f64,
+    longitude: f64,
+) -> (SString, i32) {
+    match timezone::local_date(observed_at, country_code, region_code, latitude, longitude) {
+        Some(date) => (date.format("%Y-%m-%d").to_string().into(), date.year()),
+        None => (
+            observed_at.get(0..10).unwrap_or(observed_at).into(),
+            extract_year(observed_at),
+        ),
+    }
+}
+
 fn extract_year(date_str: &str) -> i32 {
     // ISO 8601 format: 2020-02-14T09:34:18.584Z
     date_str
@@ -727,6 +780,16 @@ fn extract_year(date_str: &str) -> i32 {
         })
 }
 
+pub(crate) fn geocode(latlng: LatLng) -> (SString, Option<SString>) {
+    (get_country_code(latlng), get_region_code(latlng))
+}
+
+/// Whether a location is inside any country's land boundary. Used to draw
+/// land on static maps.
+pub(crate) fn is_land(latitude: f64, longitude: f64) -> bool {
+    LatLon::new(latitude, longitude).is_ok_and(|latlon| !BOUNDARIES.ids(latlon).is_empty())
+}
+
 fn get_country_code(latlng: LatLng) -> SString {
     let Ok(latlon) = LatLon::new(latlng.lat, latlng.lng) else {
         return "XX".into();
@@ -0,0 +1,202 @@
+//! Point-in-polygon lookups over GeoJSON polygons, shared by the time zone
+//! boundaries used for local dates and the land drawn on static maps.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+// Height in degrees of the latitude bands ring edges are bucketed into, so a
+// point is only tested against the edges near its own latitude
+const BAND_DEGREES: f64 = 0.1;
+
+#[derive(Deserialize)]
+pub(crate) struct FeatureCollection<P> {
+    pub features: Vec<Feature<P>>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Feature<P> {
+    pub properties: P,
+    pub geometry: Geometry,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", content = "coordinates")]
+pub(crate) enum Geometry {
+    Polygon(Vec<Vec<[f64; 2]>>),
+    MultiPolygon(Vec<Vec<Vec<[f64; 2]>>>),
+}
+
+impl<P: DeserializeOwned> FeatureCollection<P> {
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|err| format!("opening: {err}"))?;
+        serde_json::from_reader(BufReader::new(file))
+            .map_err(|err| format!("reading GeoJSON: {err}"))
+    }
+}
+
+/// One ring of a polygon, as `[longitude, latitude]` points with the first
+/// repeated at the end.
+struct Ring {
+    points: Vec<[f64; 2]>,
+    min_lat: f64,
+    /// Edges (by index of their first point) crossing each latitude band
+    bands: Vec<Vec<u32>>,
+}
+
+impl Ring {
+    fn new(points: Vec<[f64; 2]>) -> Self {
+        let min_lat = points.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min);
+        let max_lat = points
+            .iter()
+            .map(|p| p[1])
+            .fold(f64::NEG_INFINITY, f64::max);
+        let band_count = if points.is_empty() {
+            0
+        } else {
+            ((max_lat - min_lat) / BAND_DEGREES) as usize + 1
+        };
+        let mut bands = vec![Vec::new(); band_count];
+        for (idx, edge) in points.windows(2).enumerate() {
+            let low = edge[0][1].min(edge[1][1]);
+            let high = edge[0][1].max(edge[1][1]);
+            let first = ((low - min_lat) / BAND_DEGREES) as usize;
+            let last = (((high - min_lat) / BAND_DEGREES) as usize).min(band_count - 1);
+            for band in &mut bands[first..=last] {
+                band.push(idx as u32);
+            }
+        }
+        Self {
+            points,
+            min_lat,
+            bands,
+        }
+    }
+
+    /// Even-odd test with a ray cast east from the point.
+    fn contains(&self, lat: f64, lon: f64) -> bool {
+        if lat < self.min_lat {
+            return false;
+        }
+        let Some(edges) = self
+            .bands
+            .get(((lat - self.min_lat) / BAND_DEGREES) as usize)
+        else {
+            return false;
+        };
+
+        let mut inside = false;
+        for &idx in edges {
+            let [x1, y1] = self.points[idx as usize];
+            let [x2, y2] = self.points[idx as usize + 1];
+            if (y1 > lat) != (y2 > lat) && lon < (x2 - x1) * (lat - y1) / (y2 - y1) + x1 {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+}
+
+struct Polygon<T> {
+    value: T,
+    /// `[min_lon, min_lat, max_lon, max_lat]`
+    bbox: [f64; 4],
+    outer: Ring,
+    holes: Vec<Ring>,
+}
+
+impl<T> Polygon<T> {
+    fn contains(&self, lat: f64, lon: f64) -> bool {
+        let [min_lon, min_lat, max_lon, max_lat] = self.bbox;
+        (min_lon..=max_lon).contains(&lon)
+            && (min_lat..=max_lat).contains(&lat)
+            && self.outer.contains(lat, lon)
+            && !self.holes.iter().any(|hole| hole.contains(lat, lon))
+    }
+}
+
+/// Polygons tagged with a value, indexed by the whole-degree cells their
+/// bounding boxes cover.
+pub(crate) struct PolygonIndex<T> {
+    polygons: Vec<Polygon<T>>,
+    cells: Vec<Vec<u32>>,
+}
+
+impl<T: Clone> PolygonIndex<T> {
+    pub(crate) fn new(features: impl IntoIterator<Item = (T, Geometry)>) -> Self {
+        let mut polygons = Vec::new();
+        for (value, geometry) in features {
+            let parts = match geometry {
+                Geometry::Polygon(rings) => vec![rings],
+                Geometry::MultiPolygon(parts) => parts,
+            };
+            for rings in parts {
+                let mut rings = rings.into_iter();
+                let Some(outer) = rings.next().filter(|ring| ring.len() >= 4) else {
+                    continue;
+                };
+                let bbox = outer.iter().fold(
+                    [
+                        f64::INFINITY,
+                        f64::INFINITY,
+                        f64::NEG_INFINITY,
+                        f64::NEG_INFINITY,
+                    ],
+                    |[min_lon, min_lat, max_lon, max_lat], &[lon, lat]| {
+                        [
+                            min_lon.min(lon),
+                            min_lat.min(lat),
+                            max_lon.max(lon),
+                            max_lat.max(lat),
+                        ]
+                    },
+                );
+                polygons.push(Polygon {
+                    value: value.clone(),
+                    bbox,
+                    outer: Ring::new(outer),
+                    holes: rings.map(Ring::new).collect(),
+                });
+            }
+        }
+
+        let mut cells = vec![Vec::new(); 360 * 180];
+        for (idx, polygon) in polygons.iter().enumerate() {
+            let [min_lon, min_lat, max_lon, max_lat] = polygon.bbox;
+            let (first_row, first_col) = cell_coords(min_lat, min_lon);
+            let (last_row, last_col) = cell_coords(max_lat, max_lon);
+            for row in first_row..=last_row {
+                for col in first_col..=last_col {
+                    cells[row * 360 + col].push(idx as u32);
+                }
+            }
+        }
+
+        Self { polygons, cells }
+    }
+}
+
+impl<T> PolygonIndex<T> {
+    pub(crate) fn len(&self) -> usize {
+        self.polygons.len()
+    }
+
+    /// The value of the first polygon the point is in.
+    pub(crate) fn find(&self, lat: f64, lon: f64) -> Option<&T> {
+        let (row, col) = cell_coords(lat, lon);
+        self.cells[row * 360 + col]
+            .iter()
+            .map(|&idx| &self.polygons[idx as usize])
+            .find(|polygon| polygon.contains(lat, lon))
+            .map(|polygon| &polygon.value)
+    }
+}
+
+fn cell_coords(lat: f64, lon: f64) -> (usize, usize) {
+    let row = ((lat + 90.0).floor() as usize).min(179);
+    let col = ((lon + 180.0).floor() as usize).min(359);
+    (row, col)
+}
@@ -0,0 +1,200 @@
+//! A small software rasteriser for map images, for clients that can't render
+//! vector tiles. It only knows how to draw what the maps need, antialiased,
+//! onto an RGBA canvas that it can encode as a PNG.
+
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+pub type Colour = [u8; 3];
+
+// The same colours as the map (see `frontend/src/lib/colours.ts`)
+pub const COLOUR_LIFER: Colour = [0x93, 0x33, 0xEA];
+pub const COLOUR_YEAR_TICK: Colour = [0x3B, 0x82, 0xF6];
+pub const COLOUR_COUNTRY_TICK: Colour = [0xF9, 0x73, 0x16];
+pub const COLOUR_NORMAL_SIGHTING: Colour = [0xE6, 0x39, 0x46];
+pub const COLOUR_WHITE: Colour = [0xFF, 0xFF, 0xFF];
+
+// Sighting markers, in pixels, as drawn on the map
+pub const SIGHTING_RADIUS: f64 = 6.0;
+pub const SIGHTING_STROKE_WIDTH: f64 = 1.5;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// How a sighting is marked, by the most notable tick it is. Lifers come
+/// first, then country ticks, then year ticks, as on the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SightingKind {
+    Normal,
+    YearTick,
+    CountryTick,
+    Lifer,
+}
+
+impl SightingKind {
+    pub fn new(lifer: bool, year_tick: bool, country_tick: bool) -> Self {
+        if lifer {
+            Self::Lifer
+        } else if country_tick {
+            Self::CountryTick
+        } else if year_tick {
+            Self::YearTick
+        } else {
+            Self::Normal
+        }
+    }
+
+    pub const fn colour(self) -> Colour {
+        match self {
+            Self::Normal => COLOUR_NORMAL_SIGHTING,
+            Self::YearTick => COLOUR_YEAR_TICK,
+            Self::CountryTick => COLOUR_COUNTRY_TICK,
+            Self::Lifer => COLOUR_LIFER,
+        }
+    }
+}
+
+/// RGBA pixels with straight (not premultiplied) alpha.
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    /// A fully transparent canvas.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// An opaque canvas in a single colour.
+    pub fn filled(width: u32, height: u32, colour: Colour) -> Self {
+        let [r, g, b] = colour;
+        Self {
+            width,
+            height,
+            pixels: [r, g, b, 0xFF].repeat(width as usize * height as usize),
+        }
+    }
+
+    /// Blends `colour` over the pixel at `x`, `y` with the given opacity.
+    fn blend(&mut self, x: u32, y: u32, colour: Colour, alpha: f64) {
+        if alpha <= 0.0 || x >= self.width || y >= self.height {
+            return;
+        }
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        let pixel = &mut self.pixels[i..i + 4];
+        let src_a = alpha.min(1.0);
+        let dst_a = f64::from(pixel[3]) / 255.0;
+        let out_a = src_a + dst_a * (1.0 - src_a);
+        for (channel, src) in pixel.iter_mut().zip(colour) {
+            let value =
+                (f64::from(src) * src_a + f64::from(*channel) * dst_a * (1.0 - src_a)) / out_a;
+            *channel = value.round().clamp(0.0, 255.0) as u8;
+        }
+        pixel[3] = (out_a * 255.0).round() as u8;
+    }
+
+    /// Fills a rectangle of whole pixels, clipped to the canvas.
+    pub fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, colour: Colour) {
+        let x_max = x.saturating_add(width).min(self.width);
+        let y_max = y.saturating_add(height).min(self.height);
+        for row in y..y_max {
+            for column in x..x_max {
+                self.blend(column, row, colour, 1.0);
+            }
+        }
+    }
+
+    /// Draws a filled circle centred on `cx`, `cy`, with edges smoothed over
+    /// one pixel. Parts off the canvas are clipped.
+    pub fn fill_circle(&mut self, cx: f64, cy: f64, radius: f64, colour: Colour) {
+        let x_min = (cx - radius - 1.0).floor().max(0.0) as u32;
+        let y_min = (cy - radius - 1.0).floor().max(0.0) as u32;
+        let x_max = (cx + radius + 1.0).ceil().min(f64::from(self.width)) as u32;
+        let y_max = (cy + radius + 1.0).ceil().min(f64::from(self.height)) as u32;
+        for y in y_min..y_max {
+            for x in x_min..x_max {
+                let dx = f64::from(x) + 0.5 - cx;
+                let dy = f64::from(y) + 0.5 - cy;
+                let coverage = (radius + 0.5 - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0);
+                self.blend(x, y, colour, coverage);
+            }
+        }
+    }
+
+    /// Draws a sighting marker: a circle in the colour of its kind with a
+    /// white outline. `scale` is the ratio of canvas pixels to screen pixels.
+    pub fn draw_sighting(&mut self, x: f64, y: f64, kind: SightingKind, scale: f64) {
+        let radius = SIGHTING_RADIUS * scale;
+        self.fill_circle(x, y, radius + SIGHTING_STROKE_WIDTH * scale, COLOUR_WHITE);
+        self.fill_circle(x, y, radius, kind.colour());
+    }
+
+    /// Encodes the canvas as an 8-bit RGBA PNG.
+    pub fn to_png(&self) -> std::io::Result<Vec<u8>> {
+        self.to_png_with_text(&[])
+    }
+
+    /// Encodes the canvas with `tEXt` chunks of `(keyword, text)`, such as
+    /// `Copyright`. Characters outside Latin-1 are left out, since that's
+    /// all the chunk holds.
+    pub fn to_png_with_text(&self, text: &[(&str, &str)]) -> std::io::Result<Vec<u8>> {
+        let row_len = self.width as usize * 4;
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for row in self.pixels.chunks(row_len.max(1)) {
+            // Each scanline starts with its filter type, and 0 is none
+            encoder.write_all(&[0])?;
+            encoder.write_all(row)?;
+        }
+        let image_data = encoder.finish()?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        // Bit depth 8, colour type 6 (RGBA), default compression, filtering
+        // and no interlacing
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut png = Vec::with_capacity(image_data.len() + 64);
+        png.extend_from_slice(&PNG_SIGNATURE);
+        write_chunk(&mut png, b"IHDR", &header);
+        for (keyword, value) in text {
+            let mut data: Vec<u8> = latin1(keyword);
+            data.push(0);
+            data.extend(latin1(value));
+            write_chunk(&mut png, b"tEXt", &data);
+        }
+        write_chunk(&mut png, b"IDAT", &image_data);
+        write_chunk(&mut png, b"IEND", &[]);
+        Ok(png)
+    }
+}
+
+/// The colour as used in CSS and SVG, like `#e63946`.
+pub fn hex(colour: Colour) -> String {
+    format!("#{}", hex::encode(colour))
+}
+
+fn latin1(value: &str) -> Vec<u8> {
+    value
+        .chars()
+        .filter_map(|c| u8::try_from(u32::from(c)).ok())
+        .collect()
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    let len = u32::try_from(data.len()).unwrap_or(u32::MAX);
+    png.extend_from_slice(&len.to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+    png.extend_from_slice(&crc.finalize().to_be_bytes());
+}
@@ -0,0 +1,340 @@
+//! Read-only share links. A share token works in place of the upload ID on
+//! every read endpoint, so an upload can be shown to someone without handing
+//! out its ID. A share can be limited to the sightings matching a filter, can
+//! snap coordinates to a coarse grid, and can expire. Owners create, list and
+//! revoke shares with the upload's edit token.
+
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use serde::Deserialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::db::{self, DbPools};
+use crate::error::ApiError;
+use crate::filter::FilterGroup;
+use crate::proto::{pb, Proto};
+use crate::upload::{
+    check_edit_token, extract_edit_token, hash_token, parse_expiry, parse_upload_id,
+    verify_upload_access,
+};
+use crate::view_password::has_view_session;
+
+// The prefix keeps share tokens from ever parsing as an upload ID
+const SHARE_TOKEN_PREFIX: &str = "share_";
+const MAX_SHARES_PER_UPLOAD: i64 = 50;
+const MAX_LABEL_LENGTH: usize = 128;
+
+/// What a read request may see: the upload behind the ID or share token in
+/// the path, whether the caller owns it, and any restrictions the share
+/// carries.
+pub struct UploadAccess {
+    upload_uuid: Uuid,
+    scope: Option<FilterGroup>,
+    hide_locations: bool,
+    shared: bool,
+    owner: bool,
+    password_protected: bool,
+}
+
+impl UploadAccess {
+    pub fn upload_uuid(&self) -> &Uuid {
+        &self.upload_uuid
+    }
+
+    pub fn upload_id_blob(&self) -> &[u8] {
+        &self.upload_uuid.as_bytes()[..]
+    }
+
+    /// The share's filter, which applies on top of the request's own.
+    pub fn scope(&self) -> Option<&FilterGroup> {
+        self.scope.as_ref()
+    }
+
+    pub fn is_shared(&self) -> bool {
+        self.shared
+    }
+
+    pub fn hides_locations(&self) -> bool {
+        self.hide_locations
+    }
+
+    /// Set when the request came with the upload's edit token, which lifts
+    /// [location privacy](crate::location_privacy).
+    pub fn is_owner(&self) -> bool {
+        self.owner
+    }
+
+    pub fn is_password_protected(&self) -> bool {
+        self.password_protected
+    }
+
+    pub fn scope_needs_species_join(&self) -> bool {
+        self.scope
+            .as_ref()
+            .is_some_and(FilterGroup::needs_species_join)
+    }
+}
+
+pub fn is_share_token(id: &str) -> bool {
+    id.starts_with(SHARE_TOKEN_PREFIX)
+}
+
+#[derive(FromRow)]
+struct ShareAccessRow {
+    upload_id: Vec<u8>,
+    filter: Option<String>,
+    hide_locations: bool,
+}
+
+/// Resolves the `{upload_id}` of a read route, which may be an upload ID or a
+/// share token. Revoked and expired shares are reported as not found. An
+/// edit token that doesn't match is ignored, since reads don't need one.
+/// Uploads with a [view password](crate::view_password) also need a session
+/// from unlocking it, unless the edit token was sent.
+pub async fn resolve_upload_access(
+    pool: &sqlx::SqlitePool,
+    id: &str,
+    headers: &HeaderMap,
+) -> Result<UploadAccess, ApiError> {
+    let mut access = lookup_upload_access(pool, id).await?;
+
+    let password_hash = db::query_with_timeout(
+        sqlx::query_scalar::<_, Option<String>>(
+            "SELECT view_password_hash FROM uploads WHERE id = ?",
+        )
+        .bind(access.upload_id_blob())
+        .fetch_optional(pool),
+    )
+    .await
+    .map_err(|e| e.into_api_error("checking upload access", "Database error"))?;
+    // Missing uploads are left for the handler to report
+    let Some(password_hash) = password_hash else {
+        return Ok(access);
+    };
+
+    if let (false, Some(token)) = (access.shared, extract_edit_token(headers)) {
+        access.owner = verify_upload_access(pool, &access.upload_uuid.to_string(), &token)
+            .await
+            .map_err(|e| e.into_api_error("verifying edit token", "Database error"))?;
+    }
+    if let Some(password_hash) = &password_hash {
+        access.password_protected = true;
+        if !access.owner && !has_view_session(headers, access.upload_uuid(), password_hash) {
+            return Err(ApiError::password_required(
+                "This upload needs a password to view",
+            ));
+        }
+    }
+    Ok(access)
+}
+
+/// The upload behind an upload ID or share token, without checking who's
+/// asking.
+pub(crate) async fn resolve_upload_uuid(
+    pool: &sqlx::SqlitePool,
+    id: &str,
+) -> Result<Uuid, ApiError> {
+    Ok(lookup_upload_access(pool, id).await?.upload_uuid)
+}
+
+async fn lookup_upload_access(pool: &sqlx::SqlitePool, id: &str) -> Result<UploadAccess, ApiError> {
+    if let Ok(upload_uuid) = Uuid::parse_str(id) {
+        return Ok(UploadAccess {
+            upload_uuid,
+            scope: None,
+            hide_locations: false,
+            shared: false,
+            owner: false,
+            password_protected: false,
+        });
+    }
+    if !is_share_token(id) {
+        return Err(ApiError::bad_request("Invalid upload_id format"));
+    }
+
+    let row = db::query_with_timeout(
+        sqlx::query_as::<_, ShareAccessRow>(
+            "SELECT upload_id, filter, hide_locations FROM share_tokens
+             WHERE token_hash = ?
+               AND (expires_at IS NULL OR expires_at > strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))",
+        )
+        .bind(hash_token(id))
+        .fetch_optional(pool),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading share link", "Database error"))?
+    .ok_or_else(|| ApiError::not_found("Share link not found"))?;
+
+    let upload_uuid = Uuid::from_slice(&row.upload_id)
+        .map_err(|_| ApiError::internal("Invalid UUID format in database"))?;
+    let scope = row.filter.as_ref().map(FilterGroup::try_from).transpose()?;
+
+    Ok(UploadAccess {
+        upload_uuid,
+        scope,
+        hide_locations: row.hide_locations,
+        shared: true,
+        owner: false,
+        password_protected: false,
+    })
+}
+
+#[derive(Deserialize)]
+pub struct SharePayload {
+    label: Option<String>,
+    filter: Option<String>,
+    #[serde(default)]
+    hide_locations: bool,
+    expires_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SharePath {
+    upload_id: String,
+    share_id: i64,
+}
+
+#[derive(FromRow)]
+struct ShareRow {
+    id: i64,
+    label: Option<String>,
+    filter: Option<String>,
+    hide_locations: bool,
+    created_at: String,
+    expires_at: Option<String>,
+}
+
+impl ShareRow {
+    fn into_proto(self, token: Option<String>) -> pb::ShareLink {
+        pb::ShareLink {
+            share_id: self.id,
+            token,
+            label: self.label,
+            filter: self.filter,
+            hide_locations: self.hide_locations,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+        }
+    }
+}
+
+fn normalise_label(label: Option<String>) -> Result<Option<String>, ApiError> {
+    let Some(label) = label
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+    else {
+        return Ok(None);
+    };
+    if label.chars().count() > MAX_LABEL_LENGTH {
+        return Err(ApiError::bad_request(format!(
+            "Share label must be at most {MAX_LABEL_LENGTH} characters"
+        )));
+    }
+    Ok(Some(label))
+}
+
+pub async fn create_share(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<SharePayload>,
+) -> Result<Proto<pb::ShareLink>, ApiError> {
+    check_edit_token(pools.read(), &headers, &upload_id).await?;
+    let upload_uuid = parse_upload_id(&upload_id)?;
+
+    let label = normalise_label(payload.label)?;
+    let filter = payload
+        .filter
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty());
+    if let Some(filter) = &filter {
+        FilterGroup::try_from(filter)?;
+    }
+    let expires_at = payload
+        .expires_at
+        .as_deref()
+        .map(parse_expiry)
+        .transpose()?;
+
+    let existing = db::query_with_timeout(
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM share_tokens WHERE upload_id = ?")
+            .bind(&upload_uuid.as_bytes()[..])
+            .fetch_one(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("counting share links", "Database error"))?;
+    if existing >= MAX_SHARES_PER_UPLOAD {
+        return Err(ApiError::bad_request(format!(
+            "An upload can have at most {MAX_SHARES_PER_UPLOAD} share links"
+        )));
+    }
+
+    let token = format!("{SHARE_TOKEN_PREFIX}{}", Uuid::new_v4().simple());
+    let row = db::query_with_timeout(
+        sqlx::query_as::<_, ShareRow>(
+            "INSERT INTO share_tokens (upload_id, token_hash, label, filter, hide_locations, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            RETURNING id, label, filter, hide_locations, created_at, expires_at",
+        )
+        .bind(&upload_uuid.as_bytes()[..])
+        .bind(hash_token(&token))
+        .bind(&label)
+        .bind(&filter)
+        .bind(payload.hide_locations)
+        .bind(&expires_at)
+        .fetch_one(pools.write()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("creating share link", "Database error"))?;
+
+    Ok(Proto::new(row.into_proto(Some(token))))
+}
+
+pub async fn list_shares(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Proto<pb::ShareLinkList>, ApiError> {
+    check_edit_token(pools.read(), &headers, &upload_id).await?;
+    let upload_uuid = parse_upload_id(&upload_id)?;
+
+    let rows = db::query_with_timeout(
+        sqlx::query_as::<_, ShareRow>(
+            "SELECT id, label, filter, hide_locations, created_at, expires_at
+            FROM share_tokens WHERE upload_id = ? ORDER BY id",
+        )
+        .bind(&upload_uuid.as_bytes()[..])
+        .fetch_all(pools.read()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("listing share links", "Database error"))?;
+
+    Ok(Proto::new(pb::ShareLinkList {
+        shares: rows.into_iter().map(|row| row.into_proto(None)).collect(),
+    }))
+}
+
+pub async fn revoke_share(
+    State(pools): State<DbPools>,
+    Path(path): Path<SharePath>,
+    headers: HeaderMap,
+) -> Result<Proto<pb::DeleteResponse>, ApiError> {
+    check_edit_token(pools.read(), &headers, &path.upload_id).await?;
+    let upload_uuid = parse_upload_id(&path.upload_id)?;
+
+    let result = db::query_with_timeout(
+        sqlx::query("DELETE FROM share_tokens WHERE id = ? AND upload_id = ?")
+            .bind(path.share_id)
+            .bind(&upload_uuid.as_bytes()[..])
+            .execute(pools.write()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("revoking share link", "Database error"))?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("Share link not found"));
+    }
+
+    Ok(Proto::new(pb::DeleteResponse { deleted: true }))
+}
@@ -0,0 +1,442 @@
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use serde::Deserialize;
+use sqlx::{FromRow, Sqlite, Transaction};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::bitmaps::compute_and_store_bitmaps;
+use crate::collections::{collections_containing, spawn_collection_rebuilds};
+use crate::db::{self, DbPools};
+use crate::error::ApiError;
+use crate::pipeline::{geocode, local_day, resolve_species_id, vis_rank_for, MAX_UPLOAD_ROWS};
+use crate::proto::{pb, Proto};
+use crate::sightings::invalidate_name_index_cache;
+use crate::sources::dates::normalise_observed_at;
+use crate::sources::DateFormat;
+use crate::ticks::recompute_tick_flags;
+use crate::tiles::{invalidate_upload_cache, LatLng};
+use crate::upload::{
+    check_edit_token, compute_grid_cell_visibility_tx, is_collection, parse_upload_id,
+};
+
+const MAX_NAME_CHARS: usize = 256;
+
+#[derive(Deserialize)]
+pub struct SightingPath {
+    upload_id: String,
+    sighting_id: i64,
+}
+
+#[derive(Deserialize)]
+pub struct NewSighting {
+    common_name: String,
+    scientific_name: Option<String>,
+    count: Option<i64>,
+    latitude: f64,
+    longitude: f64,
+    observed_at: String,
+    sighting_uuid: Option<String>,
+}
+
+/// Fields left out of the payload are kept as they are.
+#[derive(Deserialize)]
+pub struct SightingPatch {
+    common_name: Option<String>,
+    scientific_name: Option<String>,
+    count: Option<i64>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    observed_at: Option<String>,
+}
+
+#[derive(FromRow)]
+struct StoredSighting {
+    species_id: i64,
+    latitude: f64,
+    longitude: f64,
+    country_code: String,
+    region_code: Option<String>,
+    observed_at: String,
+}
+
+pub async fn create_sighting(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<NewSighting>,
+) -> Result<Proto<pb::SightingEditResponse>, ApiError> {
+    let upload_uuid = authorise_edit(&pools, &headers, &upload_id).await?;
+    let upload_id_blob = &upload_uuid.as_bytes()[..];
+
+    let common_name = validate_name(&payload.common_name, "common_name")?;
+    let scientific_name = validate_scientific_name(payload.scientific_name.as_deref())?;
+    let count = validate_count(payload.count.unwrap_or(1))?;
+    let (latitude, longitude) = validate_coordinates(payload.latitude, payload.longitude)?;
+    let observed_at = validate_observed_at(&payload.observed_at)?;
+    let sighting_uuid = match payload.sighting_uuid.as_deref() {
+        Some(value) => Uuid::parse_str(value.trim())
+            .map_err(|_| ApiError::bad_request("Invalid sighting_uuid format"))?,
+        None => Uuid::new_v4(),
+    };
+
+    let mut tx = begin(&pools).await?;
+
+    let row_count = db::query_with_timeout(
+        sqlx::query_scalar::<_, Option<i64>>("SELECT row_count FROM uploads WHERE id = ?")
+            .bind(upload_id_blob)
+            .fetch_one(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading upload row count", "Database error"))?
+    .unwrap_or(0);
+    if usize::try_from(row_count).unwrap_or(usize::MAX) >= MAX_UPLOAD_ROWS {
+        return Err(ApiError::bad_request(format!(
+            "Uploads are limited to {MAX_UPLOAD_ROWS} sightings"
+        )));
+    }
+
+    let duplicate = db::query_with_timeout(
+        sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM sightings WHERE upload_id = ? AND sighting_uuid = ? LIMIT 1",
+        )
+        .bind(upload_id_blob)
+        .bind(&sighting_uuid.as_bytes()[..])
+        .fetch_optional(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("checking sighting uuid", "Database error"))?;
+    if duplicate.is_some() {
+        return Err(ApiError::bad_request(
+            "A sighting with this sighting_uuid already exists in the upload",
+        ));
+    }
+
+    let species_id = resolve_species_id(&mut tx, &common_name, &scientific_name)
+        .await
+        .map_err(|e| e.into_api_error("resolving species", "Database error"))?;
+    let (country_code, region_code) = geocode(LatLng {
+        lat: latitude,
+        lng: longitude,
+    });
+    let (local_date, year) = local_day(
+        &observed_at,
+        &country_code,
+        region_code.as_deref(),
+        latitude,
+        longitude,
+    );
+
+    let sighting_id = db::query_with_timeout(
+        sqlx::query_scalar::<_, i64>(
+            "INSERT INTO sightings (upload_id, sighting_uuid, species_id, count, latitude, longitude, country_code, region_code, observed_at, local_date, year, vis_rank)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING id",
+        )
+        .bind(upload_id_blob)
+        .bind(&sighting_uuid.as_bytes()[..])
+        .bind(species_id)
+        .bind(count)
+        .bind(latitude)
+        .bind(longitude)
+        .bind(country_code.as_str())
+        .bind(region_code.as_deref())
+        .bind(&observed_at)
+        .bind(local_date.as_str())
+        .bind(year)
+        .bind(vis_rank_for(&sighting_uuid))
+        .fetch_one(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("inserting sighting", "Database error"))?;
+
+    let data_version = finish_edit(tx, upload_id_blob, &[species_id], 1).await?;
+    after_commit(&pools, &upload_uuid).await;
+
+    Ok(Proto::new(pb::SightingEditResponse {
+        sighting_id,
+        data_version,
+        deleted: false,
+    }))
+}
+
+pub async fn update_sighting(
+    State(pools): State<DbPools>,
+    Path(path): Path<SightingPath>,
+    headers: HeaderMap,
+    Json(payload): Json<SightingPatch>,
+) -> Result<Proto<pb::SightingEditResponse>, ApiError> {
+    let upload_uuid = authorise_edit(&pools, &headers, &path.upload_id).await?;
+    let upload_id_blob = &upload_uuid.as_bytes()[..];
+
+    if payload.scientific_name.is_some() && payload.common_name.is_none() {
+        return Err(ApiError::bad_request(
+            "common_name is required when changing the species",
+        ));
+    }
+    let species = match payload.common_name.as_deref() {
+        Some(common_name) => Some((
+            validate_name(common_name, "common_name")?,
+            validate_scientific_name(payload.scientific_name.as_deref())?,
+        )),
+        None => None,
+    };
+    let count = payload.count.map(validate_count).transpose()?;
+    let observed_at = payload
+        .observed_at
+        .as_deref()
+        .map(validate_observed_at)
+        .transpose()?;
+
+    let mut tx = begin(&pools).await?;
+    let existing = load_sighting(&mut tx, upload_id_blob, path.sighting_id).await?;
+
+    let (latitude, longitude) = validate_coordinates(
+        payload.latitude.unwrap_or(existing.latitude),
+        payload.longitude.unwrap_or(existing.longitude),
+    )?;
+    let moved = payload.latitude.is_some() || payload.longitude.is_some();
+    let (country_code, region_code) = if moved {
+        let (country_code, region_code) = geocode(LatLng {
+            lat: latitude,
+            lng: longitude,
+        });
+        (
+            country_code.to_string(),
+            region_code.map(|code| code.to_string()),
+        )
+    } else {
+        (existing.country_code, existing.region_code)
+    };
+
+    let species_id = match &species {
+        Some((common_name, scientific_name)) => {
+            resolve_species_id(&mut tx, common_name, scientific_name)
+                .await
+                .map_err(|e| e.into_api_error("resolving species", "Database error"))?
+        }
+        None => existing.species_id,
+    };
+    let observed_at = observed_at.unwrap_or(existing.observed_at);
+    let (local_date, year) = local_day(
+        &observed_at,
+        &country_code,
+        region_code.as_deref(),
+        latitude,
+        longitude,
+    );
+
+    db::query_with_timeout(
+        sqlx::query(
+            "UPDATE sightings SET
+                species_id = ?,
+                count = COALESCE(?, count),
+                latitude = ?,
+                longitude = ?,
+                country_code = ?,
+                region_code = ?,
+                observed_at = ?,
+                local_date = ?,
+                year = ?
+            WHERE id = ?",
+        )
+        .bind(species_id)
+        .bind(count)
+        .bind(latitude)
+        .bind(longitude)
+        .bind(&country_code)
+        .bind(region_code.as_deref())
+        .bind(&observed_at)
+        .bind(local_date.as_str())
+        .bind(year)
+        .bind(path.sighting_id)
+        .execute(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("updating sighting", "Database error"))?;
+
+    let data_version =
+        finish_edit(tx, upload_id_blob, &[existing.species_id, species_id], 0).await?;
+    after_commit(&pools, &upload_uuid).await;
+
+    Ok(Proto::new(pb::SightingEditResponse {
+        sighting_id: path.sighting_id,
+        data_version,
+        deleted: false,
+    }))
+}
+
+pub async fn delete_sighting(
+    State(pools): State<DbPools>,
+    Path(path): Path<SightingPath>,
+    headers: HeaderMap,
+) -> Result<Proto<pb::SightingEditResponse>, ApiError> {
+    let upload_uuid = authorise_edit(&pools, &headers, &path.upload_id).await?;
+    let upload_id_blob = &upload_uuid.as_bytes()[..];
+
+    let mut tx = begin(&pools).await?;
+    let existing = load_sighting(&mut tx, upload_id_blob, path.sighting_id).await?;
+
+    db::query_with_timeout(
+        sqlx::query("DELETE FROM sightings WHERE id = ?")
+            .bind(path.sighting_id)
+            .execute(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("deleting sighting", "Database error"))?;
+
+    let data_version = finish_edit(tx, upload_id_blob, &[existing.species_id], -1).await?;
+    after_commit(&pools, &upload_uuid).await;
+
+    Ok(Proto::new(pb::SightingEditResponse {
+        sighting_id: path.sighting_id,
+        data_version,
+        deleted: true,
+    }))
+}
+
+async fn authorise_edit(
+    pools: &DbPools,
+    headers: &HeaderMap,
+    upload_id: &str,
+) -> Result<Uuid, ApiError> {
+    let upload_uuid = parse_upload_id(upload_id)?;
+    check_edit_token(pools.read(), headers, upload_id).await?;
+
+    if is_collection(pools.read(), &upload_uuid).await? {
+        return Err(ApiError::bad_request(
+            "Collections are built from their member uploads; edit the member upload instead",
+        ));
+    }
+
+    Ok(upload_uuid)
+}
+
+async fn begin(pools: &DbPools) -> Result<Transaction<'static, Sqlite>, ApiError> {
+    db::query_with_timeout(pools.write().begin())
+        .await
+        .map_err(|e| e.into_api_error("starting sighting edit", "Database error"))
+}
+
+async fn load_sighting(
+    tx: &mut Transaction<'_, Sqlite>,
+    upload_id_blob: &[u8],
+    sighting_id: i64,
+) -> Result<StoredSighting, ApiError> {
+    db::query_with_timeout(
+        sqlx::query_as::<_, StoredSighting>(
+            "SELECT species_id, latitude, longitude, country_code, region_code, observed_at
+             FROM sightings WHERE id = ? AND upload_id = ?",
+        )
+        .bind(sighting_id)
+        .bind(upload_id_blob)
+        .fetch_optional(&mut **tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading sighting", "Database error"))?
+    .ok_or_else(|| ApiError::not_found("Sighting not found"))
+}
+
+/// Recomputes the ticks of the species touched by the edit, re-runs the grid
+/// pass and bumps the upload's data_version, then commits.
+async fn finish_edit(
+    mut tx: Transaction<'_, Sqlite>,
+    upload_id_blob: &[u8],
+    species_ids: &[i64],
+    row_delta: i64,
+) -> Result<i64, ApiError> {
+    let mut species_ids = species_ids.to_vec();
+    species_ids.sort_unstable();
+    species_ids.dedup();
+
+    recompute_tick_flags(&mut tx, upload_id_blob, Some(&species_ids))
+        .await
+        .map_err(|e| e.into_api_error("recomputing ticks", "Database error"))?;
+
+    compute_grid_cell_visibility_tx(&mut tx, upload_id_blob)
+        .await
+        .map_err(|e| e.into_api_error("computing grid cell visibility", "Database error"))?;
+
+    let data_version = db::query_with_timeout(
+        sqlx::query_scalar::<_, i64>(
+            "UPDATE uploads SET
+                row_count = COALESCE(row_count, 0) + ?,
+                data_version = data_version + 1
+            WHERE id = ?
+            RETURNING data_version",
+        )
+        .bind(row_delta)
+        .bind(upload_id_blob)
+        .fetch_one(&mut *tx),
+    )
+    .await
+    .map_err(|e| e.into_api_error("updating upload metadata", "Database error"))?;
+
+    db::query_with_timeout(tx.commit())
+        .await
+        .map_err(|e| e.into_api_error("committing sighting edit", "Database error"))?;
+
+    Ok(data_version)
+}
+
+async fn after_commit(pools: &DbPools, upload_uuid: &Uuid) {
+    let upload_id_blob = &upload_uuid.as_bytes()[..];
+
+    if let Err(e) = compute_and_store_bitmaps(pools.write(), upload_id_blob).await {
+        error!("Failed to compute tick bitmaps: {}", e.body.error);
+    }
+
+    let upload_id = upload_uuid.to_string();
+    invalidate_upload_cache(&upload_id).await;
+    invalidate_name_index_cache(&upload_id);
+
+    match collections_containing(pools.read(), upload_id_blob).await {
+        Ok(ids) => spawn_collection_rebuilds(pools.write().clone(), ids),
+        Err(e) => e.log("finding collections to rebuild"),
+    }
+}
+
+fn validate_name(value: &str, field: &str) -> Result<String, ApiError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(ApiError::bad_request(format!("{field} must not be empty")));
+    }
+    if value.chars().count() > MAX_NAME_CHARS {
+        return Err(ApiError::bad_request(format!(
+            "{field} must be at most {MAX_NAME_CHARS} characters"
+        )));
+    }
+    Ok(value.to_string())
+}
+
+fn validate_scientific_name(value: Option<&str>) -> Result<String, ApiError> {
+    match value.map(str::trim) {
+        Some(value) if !value.is_empty() => validate_name(value, "scientific_name"),
+        _ => Ok(String::new()),
+    }
+}
+
+fn validate_count(count: i64) -> Result<i64, ApiError> {
+    if count < 1 {
+        return Err(ApiError::bad_request("count must be at least 1"));
+    }
+    Ok(count)
+}
+
+fn validate_coordinates(latitude: f64, longitude: f64) -> Result<(f64, f64), ApiError> {
+    if !(-90.0..=90.0).contains(&latitude) {
+        return Err(ApiError::bad_request("latitude must be between -90 and 90"));
+    }
+    if !(-180.0..=180.0).contains(&longitude) {
+        return Err(ApiError::bad_request(
+            "longitude must be between -180 and 180",
+        ));
+    }
+    Ok((latitude, longitude))
+}
+
+fn validate_observed_at(value: &str) -> Result<String, ApiError> {
+    normalise_observed_at(value, DateFormat::Auto)
+        .map_err(|_| ApiError::bad_request("observed_at must be an ISO 8601 date or timestamp"))
+}
@@ -1,5 +1,6 @@
 use crate::db::DbPools;
 use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
@@ -13,7 +14,9 @@ use crate::api_constants;
 use crate::db;
 use crate::error::ApiError;
 use crate::filter::{build_filter_clause, FilterRequest, TableAliases, TickVisibility};
+use crate::location_privacy::LocationPolicy;
 use crate::proto::{pb, Proto};
+use crate::shares::resolve_upload_access;
 use crate::upload::get_upload_data_version;
 use tracing::{trace, warn};
 
@@ -81,7 +84,7 @@ async fn load_name_index(
 ) -> Result<NameIndexResult, ApiError> {
     let species_rows = db::query_with_timeout(
         sqlx::query_as::<_, SpeciesRow>(
-            r"SELECT DISTINCT sp.id, sp.common_name, sp.scientific_name
+            r"SELECT DISTINCT sp.id, sp.common_name, sp.scientific_name, sp.taxon_id
               FROM sightings s
               JOIN species sp ON s.species_id = sp.id
               WHERE s.upload_id = ?
@@ -103,6 +106,7 @@ async fn load_name_index(
         name_index.push(pb::Species {
             common_name: species.common_name.clone(),
             scientific_name: species.scientific_name.clone(),
+            taxon_id: species.taxon_id,
         });
     }
 
@@ -112,6 +116,35 @@ async fn load_name_index(
     })
 }
 
+impl NameIndexResult {
+    /// An index of just the given species, so a share link's filter doesn't
+    /// give away the rest of the upload's species list.
+    fn restricted_to(&self, species_ids: impl IntoIterator<Item = i64>) -> Self {
+        let mut name_index = Vec::new();
+        let mut species_id_to_index = std::collections::HashMap::new();
+
+        for species_id in species_ids {
+            let Some(&full_index) = self.species_id_to_index.get(&species_id) else {
+                continue;
+            };
+            if species_id_to_index.contains_key(&species_id) {
+                continue;
+            }
+            let Some(species) = self.name_index.get(full_index as usize) else {
+                continue;
+            };
+            let index = u32::try_from(name_index.len()).unwrap_or(u32::MAX);
+            species_id_to_index.insert(species_id, index);
+            name_index.push(species.clone());
+        }
+
+        Self {
+            name_index,
+            species_id_to_index,
+        }
+    }
+}
+
 async fn get_or_build_name_index(
     pool: &sqlx::SqlitePool,
     upload_uuid: &Uuid,
@@ -138,6 +171,24 @@ async fn get_or_build_name_index(
     }
 }
 
+/// The id and scientific name of every species in the upload, from the
+/// cached name index.
+pub(crate) async fn upload_species_names(
+    pool: &sqlx::SqlitePool,
+    upload_uuid: &Uuid,
+    data_version: i64,
+) -> Result<Vec<(i64, String)>, ApiError> {
+    let index = get_or_build_name_index(pool, upload_uuid, data_version).await?;
+    Ok(index
+        .species_id_to_index
+        .iter()
+        .filter_map(|(&species_id, &idx)| {
+            let species = index.name_index.get(idx as usize)?;
+            Some((species_id, species.scientific_name.clone()))
+        })
+        .collect())
+}
+
 pub fn invalidate_name_index_cache(upload_id: &str) {
     let Ok(uuid) = Uuid::parse_str(upload_id) else {
         warn!(
@@ -218,8 +269,9 @@ pub struct Sighting {
     pub id: i64,
     pub species_id: i64,
     pub count: Option<i64>,
-    pub latitude: f64,
-    pub longitude: f64,
+    /// `None` when location privacy hides the sighting's species
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
     pub country_code: Option<String>,
     pub region_code: Option<String>,
     pub observed_at: String,
@@ -230,6 +282,7 @@ pub struct SpeciesRow {
     pub id: i64,
     pub common_name: String,
     pub scientific_name: String,
+    pub taxon_id: Option<i64>,
 }
 
 #[derive(Debug)]
@@ -241,7 +294,7 @@ pub struct GroupedSighting {
     pub species_count: i64,
 }
 
-fn parse_sort_direction(sort_dir: Option<&String>) -> &'static str {
+pub(crate) fn parse_sort_direction(sort_dir: Option<&String>) -> &'static str {
     match sort_dir {
         Some(dir) if dir == "asc" => "ASC",
         _ => "DESC",
@@ -272,7 +325,7 @@ fn decode_cursor(cursor_str: &str) -> Result<Cursor, ApiError> {
     serde_json::from_str(&json).map_err(|_| ApiError::bad_request("Invalid cursor data"))
 }
 
-fn wrap_nullable_sort_column(sort_field: &str) -> String {
+pub(crate) fn wrap_nullable_sort_column(sort_field: &str) -> String {
     // country_code is still nullable, so wrap it in COALESCE for consistent NULL handling
     if sort_field == "s.country_code" {
         format!("COALESCE({}, '')", sort_field)
@@ -306,9 +359,10 @@ pub async fn get_sightings(
     State(pools): State<DbPools>,
     Path(upload_id): Path<String>,
     Query(query): Query<SightingsQuery>,
+    headers: HeaderMap,
 ) -> Result<Proto<pb::SightingsResponse>, ApiError> {
-    let upload_uuid = Uuid::parse_str(&upload_id)
-        .map_err(|_| ApiError::bad_request("Invalid upload_id format"))?;
+    let access = resolve_upload_access(pools.read(), &upload_id, &headers).await?;
+    let upload_uuid = *access.upload_uuid();
     let data_version = get_upload_data_version(pools.read(), &upload_uuid).await?;
     let page = query.page.unwrap_or(1).max(1);
     let page_size = query
@@ -326,6 +380,7 @@ pub async fn get_sightings(
         pool: pools.read(),
         upload_id: &upload_uuid.as_bytes()[..],
         filter_json: query.filter.as_ref(),
+        scope_filter: access.scope(),
         year_tick_year: query.year_tick_year,
         country_tick_country: query.country_tick_country.as_ref(),
         aliases: TableAliases::new(Some("s"), Some("sp")),
@@ -461,7 +516,7 @@ pub async fn get_sightings(
             .await
             .map_err(|e| e.into_api_error("loading grouped sightings", "Database error"))?;
 
-        let index_result =
+        let mut index_result =
             get_or_build_name_index(pools.read(), &upload_uuid, data_version).await?;
 
         let mut groups = Vec::new();
@@ -518,6 +573,11 @@ pub async fn get_sightings(
             groups.push(grouped);
         }
 
+        if access.scope().is_some() {
+            index_result =
+                Arc::new(index_result.restricted_to(groups.iter().filter_map(|g| g.species_id)));
+        }
+
         let groups_pb = groups
             .into_iter()
             .map(|g| g.into_proto(&index_result.species_id_to_index))
@@ -580,7 +640,8 @@ pub async fn get_sightings(
 
     let select_sql = format!(
         r"SELECT s.id, s.species_id, s.count, s.latitude, s.longitude,
-            s.country_code, s.region_code, s.observed_at, {} as sort_value
+            s.country_code, s.region_code, s.observed_at, {} as sort_value,
+            sp.scientific_name
             FROM sightings s
             JOIN species sp ON s.species_id = sp.id
             WHERE s.upload_id = ?{}{}
@@ -607,17 +668,20 @@ pub async fn get_sightings(
     let rows = db::query_with_timeout(select_query.fetch_all(pools.read()))
         .await
         .map_err(|e| e.into_api_error("loading sightings", "Database error"))?;
+    let policy = LocationPolicy::load(pools.read(), &access).await?;
 
     let mut sightings = Vec::new();
     let mut next_cursor: Option<String> = None;
 
     for row in rows {
+        let scientific_name: String = row.get(9);
+        let location = policy.apply(&scientific_name, row.get(3), row.get(4));
         let sighting = Sighting {
             id: row.get(0),
             species_id: row.get(1),
             count: row.get(2),
-            latitude: row.get(3),
-            longitude: row.get(4),
+            latitude: location.map(|(lat, _)| lat),
+            longitude: location.map(|(_, lng)| lng),
             country_code: row.get(5),
             region_code: row.get(6),
             observed_at: row.get(7),
@@ -631,7 +695,11 @@ pub async fn get_sightings(
         next_cursor = Some(encode_cursor(&sort_val_str, id));
     }
 
-    let index_result = get_or_build_name_index(pools.read(), &upload_uuid, data_version).await?;
+    let mut index_result =
+        get_or_build_name_index(pools.read(), &upload_uuid, data_version).await?;
+    if access.scope().is_some() {
+        index_result = Arc::new(index_result.restricted_to(sightings.iter().map(|s| s.species_id)));
+    }
 
     let sightings_pb = sightings
         .into_iter()
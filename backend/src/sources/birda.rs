@@ -0,0 +1,147 @@
+use super::dates::{DateFormat, DateOrder};
+use super::{coordinate_field, required_field, RowError, RowParser, SightingSource, SkipReason};
+use crate::error::ApiError;
+use crate::pipeline::{enforce_record_limits, get_field, ParsedSighting};
+use csv_async::{ByteRecord, StringRecord};
+use tracing::error;
+use uuid::Uuid;
+
+const COL_SIGHTING_ID: &str = "sightingId";
+const COL_DATE: &str = "date";
+const COL_LONGITUDE: &str = "longitude";
+const COL_LATITUDE: &str = "latitude";
+const COL_SCIENTIFIC_NAME: &str = "scientificName";
+const COL_COMMON_NAME: &str = "commonName";
+const COL_COUNT: &str = "count";
+
+/// Sightings exported from the Birda app.
+pub struct BirdaSource;
+
+struct BirdaParser {
+    col_map: ColumnMap,
+    date_order: DateOrder,
+}
+
+impl SightingSource for BirdaSource {
+    fn name(&self) -> &'static str {
+        "birda"
+    }
+
+    fn detect(&self, headers: &StringRecord) -> bool {
+        ColumnMap::from_headers(headers).is_valid()
+    }
+
+    fn parser(
+        &self,
+        headers: &StringRecord,
+        date_format: DateFormat,
+    ) -> Result<Box<dyn RowParser>, ApiError> {
+        Ok(Box::new(BirdaParser::new(headers, date_format)?))
+    }
+}
+
+impl BirdaParser {
+    fn new(headers: &StringRecord, date_format: DateFormat) -> Result<Self, ApiError> {
+        let col_map = ColumnMap::from_headers(headers);
+        if !col_map.is_valid() {
+            error!("CSV missing required columns");
+            return Err(ApiError::bad_request(
+                "CSV missing required columns (sightingId, date, longitude, latitude, commonName)",
+            ));
+        }
+        Ok(Self {
+            col_map,
+            date_order: DateOrder::new(date_format),
+        })
+    }
+}
+
+impl RowParser for BirdaParser {
+    fn date_format(&self) -> DateFormat {
+        self.date_order.format()
+    }
+
+    fn settle_date_format(&mut self, format: DateFormat) {
+        self.date_order.settle(format);
+    }
+
+    fn parse_row(&mut self, record: &ByteRecord, row: usize) -> Result<ParsedSighting, RowError> {
+        enforce_record_limits(record, row)?;
+
+        let sighting_id = required_field(record, self.col_map.sighting_id, COL_SIGHTING_ID, row)?;
+        let sighting_uuid = Uuid::parse_str(&sighting_id)
+            .map_err(|_| RowError::skip(row, COL_SIGHTING_ID, SkipReason::InvalidUuid))?;
+        let common_name = required_field(record, self.col_map.common_name, COL_COMMON_NAME, row)?;
+
+        // Birda dates are ISO 8601 timestamps, e.g. 2020-02-14T09:34:18.584Z,
+        // unless a spreadsheet has rewritten them in the local style.
+        let observed_at = required_field(record, self.col_map.date, COL_DATE, row)?;
+        let observed_at = self
+            .date_order
+            .normalise_observed_at(&observed_at)
+            .map_err(|reason| RowError::skip(row, COL_DATE, reason))?;
+
+        let latitude = coordinate_field(record, self.col_map.latitude, COL_LATITUDE, row, 90.0)?;
+        let longitude =
+            coordinate_field(record, self.col_map.longitude, COL_LONGITUDE, row, 180.0)?;
+
+        let count: i32 = get_field(record, self.col_map.count, COL_COUNT, row)?
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1);
+
+        let scientific_name = get_field(
+            record,
+            self.col_map.scientific_name,
+            COL_SCIENTIFIC_NAME,
+            row,
+        )?;
+
+        Ok(ParsedSighting {
+            sighting_uuid,
+            common_name,
+            scientific_name,
+            count,
+            latitude,
+            longitude,
+            observed_at,
+        })
+    }
+}
+
+#[derive(Default)]
+struct ColumnMap {
+    sighting_id: Option<usize>,
+    date: Option<usize>,
+    longitude: Option<usize>,
+    latitude: Option<usize>,
+    scientific_name: Option<usize>,
+    common_name: Option<usize>,
+    count: Option<usize>,
+}
+
+impl ColumnMap {
+    fn from_headers(headers: &StringRecord) -> Self {
+        let mut map = Self::default();
+        for (idx, header) in headers.iter().enumerate() {
+            match header {
+                COL_SIGHTING_ID => map.sighting_id = Some(idx),
+                COL_DATE => map.date = Some(idx),
+                COL_LONGITUDE => map.longitude = Some(idx),
+                COL_LATITUDE => map.latitude = Some(idx),
+                COL_SCIENTIFIC_NAME => map.scientific_name = Some(idx),
+                COL_COMMON_NAME => map.common_name = Some(idx),
+                COL_COUNT => map.count = Some(idx),
+                _ => {}
+            }
+        }
+        map
+    }
+
+    const fn is_valid(&self) -> bool {
+        self.sighting_id.is_some()
+            && self.date.is_some()
+            && self.longitude.is_some()
+            && self.latitude.is_some()
+            && self.common_name.is_some()
+    }
+}
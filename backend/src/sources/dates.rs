@@ -0,0 +1,325 @@
+//! Date and time parsing shared by the import formats.
+//!
+//! Spreadsheet round trips turn ISO timestamps into whatever the user's locale
+//! prefers ("14/02/2020 09:34", "2/14/2020 9:34 AM"), so dates are parsed
+//! here and stored in one ISO 8601 form: `YYYY-MM-DD`, `YYYY-MM-DDTHH:MM:SS`
+//! for local times, or a UTC `...Z` timestamp when the input had an offset.
+//! Year-first dates are always read as ISO order; for numeric dates with the
+//! year last, the upload's [`DateFormat`] decides which number is the day, or
+//! under `Auto`, the rest of the file does (see [`DateOrder`]).
+
+use chrono::{DateTime, NaiveDate, NaiveTime, SecondsFormat};
+use serde::Deserialize;
+
+use super::SkipReason;
+
+// 12-hour forms are what eBird and US spreadsheets write.
+const TIME_FORMATS: [&str; 6] = [
+    "%H:%M:%S%.f",
+    "%H:%M",
+    "%I:%M %p",
+    "%I:%M:%S %p",
+    "%I:%M%p",
+    "%I:%M:%S%p",
+];
+
+/// Order of the day and month in dates that put the year last.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateFormat {
+    /// Work it out from the file: the first date that can only be read one
+    /// way settles it for the rest.
+    #[default]
+    Auto,
+    /// Day first, e.g. 14/02/2020
+    Dmy,
+    /// Month first, e.g. 02/14/2020
+    Mdy,
+}
+
+impl DateFormat {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Dmy => "dmy",
+            Self::Mdy => "mdy",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(Self::Auto),
+            "dmy" => Some(Self::Dmy),
+            "mdy" => Some(Self::Mdy),
+            _ => None,
+        }
+    }
+}
+
+/// The day/month order for the dates in one file. Under `Auto`, it starts
+/// out unknown and is settled by the first year-last date that only reads one
+/// way, so a day-first export isn't half skipped just because most days are
+/// 12 or under. Until then, dates that read either way are ambiguous; the
+/// caller holds those rows back and parses them again once it's settled.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DateOrder {
+    format: DateFormat,
+}
+
+impl DateOrder {
+    pub(crate) const fn new(format: DateFormat) -> Self {
+        Self { format }
+    }
+
+    /// `Auto` until a date has settled the order.
+    pub(crate) const fn format(self) -> DateFormat {
+        self.format
+    }
+
+    /// Settles the order without waiting for a date to, if it isn't yet.
+    pub(crate) fn settle(&mut self, format: DateFormat) {
+        if self.format == DateFormat::Auto {
+            self.format = format;
+        }
+    }
+
+    /// Parses a date with an optional time of day into the stored ISO form.
+    pub(crate) fn normalise_observed_at(&mut self, value: &str) -> Result<String, SkipReason> {
+        let value = value.trim();
+        if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+            return Ok(timestamp
+                .to_utc()
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true));
+        }
+
+        let (date, time) = match value.find(['T', ' ']) {
+            Some(idx) => (&value[..idx], Some(value[idx + 1..].trim())),
+            None => (value, None),
+        };
+        let date = self.parse_date(date)?;
+        match time {
+            Some(time) => {
+                let time = parse_time(time).ok_or(SkipReason::InvalidDate)?;
+                Ok(date
+                    .and_time(time)
+                    .format("%Y-%m-%dT%H:%M:%S%.f")
+                    .to_string())
+            }
+            None => Ok(date.format("%Y-%m-%d").to_string()),
+        }
+    }
+
+    /// Parses a calendar date written with `-`, `/` or `.` separators. Once
+    /// settled, a date the other way round is invalid, since the file
+    /// contradicts itself.
+    pub(crate) fn parse_date(&mut self, value: &str) -> Result<NaiveDate, SkipReason> {
+        let date = parse_date(value, self.format)?;
+        if self.format == DateFormat::Auto {
+            if let Some(format) = settled_format(value) {
+                self.format = format;
+            }
+        }
+        Ok(date)
+    }
+}
+
+/// The order a year-last date has to be read in, if it can only be read one
+/// way.
+fn settled_format(value: &str) -> Option<DateFormat> {
+    let parts: Vec<&str> = value.trim().split(['-', '/', '.']).collect();
+    let [first, second, third] = parts[..] else {
+        return None;
+    };
+    if third.len() != 4 {
+        return None;
+    }
+    let (a, b) = (first.parse::<u32>().ok()?, second.parse::<u32>().ok()?);
+    match (a > 12, b > 12) {
+        (true, false) => Some(DateFormat::Dmy),
+        (false, true) => Some(DateFormat::Mdy),
+        _ => None,
+    }
+}
+
+/// Parses a date with an optional time of day into the stored ISO form.
+pub(crate) fn normalise_observed_at(value: &str, format: DateFormat) -> Result<String, SkipReason> {
+    DateOrder::new(format).normalise_observed_at(value)
+}
+
+/// Parses a calendar date written with `-`, `/` or `.` separators.
+pub(crate) fn parse_date(value: &str, format: DateFormat) -> Result<NaiveDate, SkipReason> {
+    let parts: Vec<&str> = value.trim().split(['-', '/', '.']).collect();
+    let [first, second, third] = parts[..] else {
+        return Err(SkipReason::InvalidDate);
+    };
+    if ![first, second, third]
+        .iter()
+        .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return Err(SkipReason::InvalidDate);
+    }
+    let number = |part: &str| part.parse::<u32>().map_err(|_| SkipReason::InvalidDate);
+
+    let (year, month, day) = if first.len() == 4 {
+        (first, number(second)?, number(third)?)
+    } else if third.len() == 4 {
+        let (a, b) = (number(first)?, number(second)?);
+        let day_first = match format {
+            DateFormat::Dmy => true,
+            DateFormat::Mdy => false,
+            DateFormat::Auto if a > 12 => true,
+            DateFormat::Auto if b > 12 => false,
+            DateFormat::Auto if a == b => true,
+            DateFormat::Auto => return Err(SkipReason::AmbiguousDate),
+        };
+        if day_first {
+            (third, b, a)
+        } else {
+            (third, a, b)
+        }
+    } else {
+        // Two-digit years can't be placed in a century
+        return Err(SkipReason::InvalidDate);
+    };
+
+    let year = year.parse().map_err(|_| SkipReason::InvalidDate)?;
+    NaiveDate::from_ymd_opt(year, month, day).ok_or(SkipReason::InvalidDate)
+}
+
+pub(crate) fn parse_time(value: &str) -> Option<NaiveTime> {
+    let value = value.trim();
+    TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(value, format).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalise(value: &str, format: DateFormat) -> Result<String, SkipReason> {
+        normalise_observed_at(value, format)
+    }
+
+    #[test]
+    fn offsets_become_utc() {
+        assert_eq!(
+            normalise("2020-02-14T09:34:18.584Z", DateFormat::Auto).unwrap(),
+            "2020-02-14T09:34:18.584Z"
+        );
+        assert_eq!(
+            normalise("2020-02-14T10:34:18+01:00", DateFormat::Auto).unwrap(),
+            "2020-02-14T09:34:18Z"
+        );
+    }
+
+    #[test]
+    fn year_first_is_iso_order() {
+        for value in ["2020-02-14", "2020/02/14", "2020.02.14", "2020-2-14"] {
+            assert_eq!(normalise(value, DateFormat::Mdy).unwrap(), "2020-02-14");
+        }
+    }
+
+    #[test]
+    fn year_last_follows_format() {
+        assert_eq!(
+            normalise("03/04/2020", DateFormat::Dmy).unwrap(),
+            "2020-04-03"
+        );
+        assert_eq!(
+            normalise("03.04.2020", DateFormat::Dmy).unwrap(),
+            "2020-04-03"
+        );
+        assert_eq!(
+            normalise("03/04/2020", DateFormat::Mdy).unwrap(),
+            "2020-03-04"
+        );
+        assert_eq!(
+            normalise("14/02/2020", DateFormat::Auto).unwrap(),
+            "2020-02-14"
+        );
+        assert_eq!(
+            normalise("2/14/2020", DateFormat::Auto).unwrap(),
+            "2020-02-14"
+        );
+        assert_eq!(
+            normalise("05/05/2020", DateFormat::Auto).unwrap(),
+            "2020-05-05"
+        );
+        assert_eq!(
+            normalise("03/04/2020", DateFormat::Auto),
+            Err(SkipReason::AmbiguousDate)
+        );
+    }
+
+    #[test]
+    fn times_of_day() {
+        assert_eq!(
+            normalise("14/02/2020 09:34", DateFormat::Auto).unwrap(),
+            "2020-02-14T09:34:00"
+        );
+        assert_eq!(
+            normalise("2/14/2020 9:34 PM", DateFormat::Auto).unwrap(),
+            "2020-02-14T21:34:00"
+        );
+        assert_eq!(
+            normalise("2020-02-14T09:34:18.5", DateFormat::Auto).unwrap(),
+            "2020-02-14T09:34:18.500"
+        );
+        assert_eq!(parse_time("07:30 AM"), NaiveTime::from_hms_opt(7, 30, 0));
+        assert_eq!(
+            normalise("2020-02-14 25:00", DateFormat::Auto),
+            Err(SkipReason::InvalidDate)
+        );
+    }
+
+    #[test]
+    fn unplaceable_dates_are_invalid() {
+        for value in ["14/02/20", "2020-02-30", "31/04/2020", "14-Feb-2020", ""] {
+            assert_eq!(
+                normalise(value, DateFormat::Auto),
+                Err(SkipReason::InvalidDate),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn first_unambiguous_date_settles_the_order() {
+        let mut order = DateOrder::new(DateFormat::Auto);
+        assert_eq!(
+            order.parse_date("03/04/2020"),
+            Err(SkipReason::AmbiguousDate)
+        );
+        // Year-first and same-number dates don't settle anything
+        order.parse_date("2020-01-20").unwrap();
+        order.parse_date("05/05/2020").unwrap();
+        assert_eq!(order.format(), DateFormat::Auto);
+
+        order.parse_date("20/01/2020").unwrap();
+        assert_eq!(order.format(), DateFormat::Dmy);
+        assert_eq!(
+            order.parse_date("03/04/2020"),
+            NaiveDate::from_ymd_opt(2020, 4, 3).ok_or(SkipReason::InvalidDate)
+        );
+
+        let mut order = DateOrder::new(DateFormat::Auto);
+        order.normalise_observed_at("1/20/2020 7:30 AM").unwrap();
+        assert_eq!(order.format(), DateFormat::Mdy);
+        assert_eq!(
+            order.normalise_observed_at("03/04/2020").unwrap(),
+            "2020-03-04"
+        );
+    }
+
+    #[test]
+    fn contradicting_the_settled_order_is_invalid() {
+        let mut order = DateOrder::new(DateFormat::Auto);
+        order.parse_date("20/01/2020").unwrap();
+        assert_eq!(order.parse_date("01/20/2020"), Err(SkipReason::InvalidDate));
+        // A given format is never overridden
+        let mut order = DateOrder::new(DateFormat::Mdy);
+        assert_eq!(order.parse_date("20/01/2020"), Err(SkipReason::InvalidDate));
+        assert_eq!(order.format(), DateFormat::Mdy);
+    }
+}
@@ -0,0 +1,202 @@
+use super::dates::{parse_time, DateFormat, DateOrder};
+use super::{coordinate_field, required_field, RowError, RowParser, SightingSource};
+use crate::error::ApiError;
+use crate::pipeline::{enforce_record_limits, get_field, ParsedSighting};
+use csv_async::{ByteRecord, StringRecord};
+use sha2::{Digest, Sha256};
+use uuid::{Builder, Uuid};
+
+// Column names from eBird's "Download My Data" export (MyEBirdData.csv).
+const COL_SUBMISSION_ID: &str = "Submission ID";
+const COL_COMMON_NAME: &str = "Common Name";
+const COL_SCIENTIFIC_NAME: &str = "Scientific Name";
+const COL_COUNT: &str = "Count";
+const COL_LATITUDE: &str = "Latitude";
+const COL_LONGITUDE: &str = "Longitude";
+const COL_DATE: &str = "Date";
+const COL_TIME: &str = "Time";
+// Checklist details with nowhere to go in a sighting. They're reported back
+// as ignored rather than silently dropped.
+const COL_DURATION: &str = "Duration (Min)";
+const COL_PROTOCOL: &str = "Protocol";
+const COL_LOCATION: &str = "Location";
+
+/// eBird's "Download My Data" export.
+pub struct EbirdSource;
+
+struct EbirdParser {
+    col_map: ColumnMap,
+    date_order: DateOrder,
+}
+
+impl SightingSource for EbirdSource {
+    fn name(&self) -> &'static str {
+        "ebird"
+    }
+
+    fn detect(&self, headers: &StringRecord) -> bool {
+        ColumnMap::from_headers(headers).is_valid()
+    }
+
+    fn parser(
+        &self,
+        headers: &StringRecord,
+        date_format: DateFormat,
+    ) -> Result<Box<dyn RowParser>, ApiError> {
+        Ok(Box::new(EbirdParser::new(headers, date_format)?))
+    }
+}
+
+impl EbirdParser {
+    fn new(headers: &StringRecord, date_format: DateFormat) -> Result<Self, ApiError> {
+        let col_map = ColumnMap::from_headers(headers);
+        if !col_map.is_valid() {
+            return Err(ApiError::bad_request(
+                "eBird CSV missing required columns (Submission ID, Common Name, Count, Latitude, Longitude, Date)",
+            ));
+        }
+        Ok(Self {
+            col_map,
+            date_order: DateOrder::new(date_format),
+        })
+    }
+}
+
+impl RowParser for EbirdParser {
+    fn date_format(&self) -> DateFormat {
+        self.date_order.format()
+    }
+
+    fn settle_date_format(&mut self, format: DateFormat) {
+        self.date_order.settle(format);
+    }
+
+    fn ignored_columns(&self) -> Vec<&'static str> {
+        [
+            (self.col_map.duration, COL_DURATION),
+            (self.col_map.protocol, COL_PROTOCOL),
+            (self.col_map.location, COL_LOCATION),
+        ]
+        .into_iter()
+        .filter_map(|(idx, column)| idx.map(|_| column))
+        .collect()
+    }
+
+    fn parse_row(&mut self, record: &ByteRecord, row: usize) -> Result<ParsedSighting, RowError> {
+        enforce_record_limits(record, row)?;
+
+        let submission_id =
+            required_field(record, self.col_map.submission_id, COL_SUBMISSION_ID, row)?;
+        let common_name = required_field(record, self.col_map.common_name, COL_COMMON_NAME, row)?;
+        let date = required_field(record, self.col_map.date, COL_DATE, row)?;
+        // eBird writes YYYY-MM-DD, but spreadsheet edits may not.
+        let date = self
+            .date_order
+            .parse_date(&date)
+            .map_err(|reason| RowError::skip(row, COL_DATE, reason))?;
+        // Times are e.g. "07:30 AM"; an unreadable one just drops the time.
+        let time = get_field(record, self.col_map.time, COL_TIME, row)?
+            .and_then(|value| parse_time(&value));
+
+        let latitude = coordinate_field(record, self.col_map.latitude, COL_LATITUDE, row, 90.0)?;
+        let longitude =
+            coordinate_field(record, self.col_map.longitude, COL_LONGITUDE, row, 180.0)?;
+
+        // "X" means the species was present but not counted.
+        let count: i32 = get_field(record, self.col_map.count, COL_COUNT, row)?
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1);
+
+        let scientific_name = get_field(
+            record,
+            self.col_map.scientific_name,
+            COL_SCIENTIFIC_NAME,
+            row,
+        )?;
+
+        let observed_at = match time {
+            Some(time) => date.and_time(time).format("%Y-%m-%dT%H:%M:%S").to_string(),
+            None => date.format("%Y-%m-%d").to_string(),
+        };
+
+        let sighting_uuid = sighting_uuid(
+            &submission_id,
+            &common_name,
+            scientific_name.as_deref().unwrap_or_default(),
+        );
+
+        Ok(ParsedSighting {
+            sighting_uuid,
+            common_name,
+            scientific_name,
+            count,
+            latitude,
+            longitude,
+            observed_at,
+        })
+    }
+}
+
+// eBird has no per-observation ID, only a checklist (submission) ID. A checklist
+// lists each taxon at most once, so hashing the submission ID with the names
+// gives a stable identifier that survives re-exports.
+fn sighting_uuid(submission_id: &str, common_name: &str, scientific_name: &str) -> Uuid {
+    let mut hasher = Sha256::new();
+    hasher.update(submission_id.as_bytes());
+    hasher.update([0]);
+    hasher.update(common_name.as_bytes());
+    hasher.update([0]);
+    hasher.update(scientific_name.as_bytes());
+    let digest = hasher.finalize();
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    Builder::from_custom_bytes(bytes).into_uuid()
+}
+
+#[derive(Default)]
+struct ColumnMap {
+    submission_id: Option<usize>,
+    common_name: Option<usize>,
+    scientific_name: Option<usize>,
+    count: Option<usize>,
+    latitude: Option<usize>,
+    longitude: Option<usize>,
+    date: Option<usize>,
+    time: Option<usize>,
+    duration: Option<usize>,
+    protocol: Option<usize>,
+    location: Option<usize>,
+}
+
+impl ColumnMap {
+    fn from_headers(headers: &StringRecord) -> Self {
+        let mut map = Self::default();
+        for (idx, header) in headers.iter().enumerate() {
+            match header.trim_start_matches('\u{feff}') {
+                COL_SUBMISSION_ID => map.submission_id = Some(idx),
+                COL_COMMON_NAME => map.common_name = Some(idx),
+                COL_SCIENTIFIC_NAME => map.scientific_name = Some(idx),
+                COL_COUNT => map.count = Some(idx),
+                COL_LATITUDE => map.latitude = Some(idx),
+                COL_LONGITUDE => map.longitude = Some(idx),
+                COL_DATE => map.date = Some(idx),
+                COL_TIME => map.time = Some(idx),
+                COL_DURATION => map.duration = Some(idx),
+                COL_PROTOCOL => map.protocol = Some(idx),
+                COL_LOCATION => map.location = Some(idx),
+                _ => {}
+            }
+        }
+        map
+    }
+
+    const fn is_valid(&self) -> bool {
+        self.submission_id.is_some()
+            && self.common_name.is_some()
+            && self.count.is_some()
+            && self.latitude.is_some()
+            && self.longitude.is_some()
+            && self.date.is_some()
+    }
+}
@@ -0,0 +1,164 @@
+//! Import formats for uploaded sighting exports.
+//!
+//! Each supported app or site implements [`SightingSource`]. Uploads are
+//! matched against [`SOURCES`] in order using only the CSV header row, so a
+//! new format only needs its own module and an entry in that list.
+
+mod birda;
+pub(crate) mod dates;
+mod ebird;
+
+pub use birda::BirdaSource;
+pub use dates::DateFormat;
+pub use ebird::EbirdSource;
+
+use crate::error::ApiError;
+use crate::pipeline::{get_field, validate_header_limits, ParsedSighting};
+use csv_async::{ByteRecord, StringRecord};
+use tracing::error;
+
+pub trait SightingSource: Sync {
+    /// Short identifier reported back to clients, e.g. "birda".
+    fn name(&self) -> &'static str;
+
+    /// Whether the header row looks like this source's export.
+    fn detect(&self, headers: &StringRecord) -> bool;
+
+    /// `date_format` says how to read dates that put the year last.
+    fn parser(
+        &self,
+        headers: &StringRecord,
+        date_format: DateFormat,
+    ) -> Result<Box<dyn RowParser>, ApiError>;
+}
+
+pub trait RowParser: Send {
+    /// Rows that can't be imported are returned as `RowError::Skip` so the
+    /// uploader can be told why; `RowError::Fatal` aborts the whole upload.
+    /// `row` is 1-based and excludes the header. A row skipped as
+    /// `AmbiguousDate` may be parsed again once the file's date order is
+    /// known.
+    fn parse_row(&mut self, record: &ByteRecord, row: usize) -> Result<ParsedSighting, RowError>;
+
+    /// The day/month order, once a date in the file has settled it; `Auto`
+    /// until then. See [`dates::DateOrder`].
+    fn date_format(&self) -> DateFormat;
+
+    /// Picks the day/month order when the file hasn't settled it.
+    fn settle_date_format(&mut self, format: DateFormat);
+
+    /// Columns present in the file that the format knows about but that
+    /// aren't stored, so the uploader can be told they were dropped.
+    fn ignored_columns(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SkipReason {
+    MissingValue,
+    InvalidNumber,
+    OutOfRange,
+    InvalidUuid,
+    InvalidDate,
+    /// Day and month could be either way round, no format was given and no
+    /// other date in the file settles it
+    AmbiguousDate,
+}
+
+impl SkipReason {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::MissingValue => "missing_value",
+            Self::InvalidNumber => "invalid_number",
+            Self::OutOfRange => "out_of_range",
+            Self::InvalidUuid => "invalid_uuid",
+            Self::InvalidDate => "invalid_date",
+            Self::AmbiguousDate => "ambiguous_date",
+        }
+    }
+}
+
+/// A CSV row left out of an import. `row` is 1-based and excludes the header,
+/// matching the row numbers used in upload error messages.
+#[derive(Debug, Clone)]
+pub struct SkippedRow {
+    pub row: usize,
+    pub column: &'static str,
+    pub reason: SkipReason,
+}
+
+pub enum RowError {
+    Skip(SkippedRow),
+    Fatal(ApiError),
+}
+
+impl RowError {
+    pub const fn skip(row: usize, column: &'static str, reason: SkipReason) -> Self {
+        Self::Skip(SkippedRow {
+            row,
+            column,
+            reason,
+        })
+    }
+}
+
+impl From<ApiError> for RowError {
+    fn from(err: ApiError) -> Self {
+        Self::Fatal(err)
+    }
+}
+
+pub(crate) fn required_field(
+    record: &ByteRecord,
+    idx: Option<usize>,
+    column: &'static str,
+    row: usize,
+) -> Result<String, RowError> {
+    get_field(record, idx, column, row)?
+        .ok_or_else(|| RowError::skip(row, column, SkipReason::MissingValue))
+}
+
+/// Parses a latitude or longitude, rejecting values outside `-limit..=limit`.
+pub(crate) fn coordinate_field(
+    record: &ByteRecord,
+    idx: Option<usize>,
+    column: &'static str,
+    row: usize,
+    limit: f64,
+) -> Result<f64, RowError> {
+    let value = required_field(record, idx, column, row)?;
+    let parsed = value
+        .parse::<f64>()
+        .map_err(|_| RowError::skip(row, column, SkipReason::InvalidNumber))?;
+    if !parsed.is_finite() || parsed.abs() > limit {
+        return Err(RowError::skip(row, column, SkipReason::OutOfRange));
+    }
+    Ok(parsed)
+}
+
+/// Checked in order; the first source whose `detect` matches wins.
+pub static SOURCES: &[&dyn SightingSource] = &[&BirdaSource, &EbirdSource];
+
+pub struct DetectedSource {
+    pub format: &'static str,
+    pub parser: Box<dyn RowParser>,
+}
+
+pub fn detect(headers: &StringRecord, date_format: DateFormat) -> Result<DetectedSource, ApiError> {
+    validate_header_limits(headers)?;
+
+    let Some(source) = SOURCES.iter().find(|source| source.detect(headers)) else {
+        let names: Vec<_> = SOURCES.iter().map(|source| source.name()).collect();
+        error!("CSV headers did not match any known export format");
+        return Err(ApiError::bad_request(format!(
+            "CSV missing required columns (expected one of: {})",
+            names.join(", ")
+        )));
+    };
+
+    Ok(DetectedSource {
+        format: source.name(),
+        parser: source.parser(headers, date_format)?,
+    })
+}
@@ -0,0 +1,599 @@
+//! Static overview maps of an upload, as a PNG or SVG, for link previews and
+//! printed reports where there's no browser to draw the interactive map. The
+//! map is fitted to the sightings matching the filter, or to a bounding box
+//! given in the request, and drawn over a plain land and sea basemap. Land
+//! comes from Natural Earth's land polygons when REDGROUSE_LAND_POLYGONS_PATH
+//! points at them, and otherwise from the country boundaries bundled for
+//! geocoding. Those are simplified and take in coastal waters, so without
+//! the land polygons the coastline is only drawn on maps zoomed out far
+//! enough for that not to show.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::Response;
+use futures::TryStreamExt;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Deserialize;
+use sqlx::Row;
+use tokio::sync::Semaphore;
+use tokio::time::timeout;
+use tracing::{error, info};
+
+use crate::config;
+use crate::db::{self, DbPools};
+use crate::error::ApiError;
+use crate::filter::{build_filter_clause, FilterRequest, TableAliases, TickVisibility};
+use crate::location_privacy::LocationPolicy;
+use crate::pipeline::is_land;
+use crate::polygons::{FeatureCollection, PolygonIndex};
+use crate::raster::{
+    hex, Canvas, Colour, SightingKind, COLOUR_WHITE, SIGHTING_RADIUS, SIGHTING_STROKE_WIDTH,
+};
+use crate::shares::resolve_upload_access;
+use crate::upload::get_upload_data_version;
+
+// The usual size for Open Graph images
+const DEFAULT_WIDTH: u32 = 1200;
+const DEFAULT_HEIGHT: u32 = 630;
+const MIN_SIZE: u32 = 64;
+const MAX_SIZE: u32 = 2048;
+// Share of each side left empty around the sightings when fitting the map
+const FIT_PADDING: f64 = 0.08;
+// Pixels per world width at zoom 14, the closest a fitted map gets
+const MAX_SCALE: f64 = 256.0 * 16384.0;
+// What's shown when there are no sightings to fit to
+const WORLD_BOUNDS: [f64; 4] = [-180.0, -60.0, 180.0, 75.0];
+const MAX_MERCATOR_LATITUDE: f64 = 85.051_128_78;
+// The basemap is drawn in square cells this many pixels across
+const BASEMAP_CELL_SIZE: u32 = 2;
+// Pixels per world width at zoom 2, the closest the coastline is drawn at
+// from the country boundaries
+const MAX_BASEMAP_SCALE: f64 = 256.0 * 4.0;
+// Pixels per world width at zoom 8, the closest the 1:50m land polygons hold
+// up at
+const MAX_LAND_POLYGON_SCALE: f64 = 256.0 * 256.0;
+const COLOUR_SEA: Colour = [0xDB, 0xEA, 0xF5];
+const COLOUR_LAND: Colour = [0xF5, 0xF5, 0xF4];
+const COLOUR_COAST: Colour = [0xA8, 0xA2, 0x9E];
+const COLOUR_ATTRIBUTION: Colour = [0x57, 0x53, 0x4E];
+const COUNTRY_BOUNDARIES_ATTRIBUTION: &str = "© OpenStreetMap contributors";
+const LAND_POLYGONS_ATTRIBUTION: &str = "Made with Natural Earth";
+// Rendering is CPU bound, so only a few maps are drawn at once
+const RENDER_MAX_CONCURRENCY: usize = 4;
+const RENDER_WAIT_TIMEOUT_MS: u64 = 2000;
+static RENDER_GUARD: Lazy<Arc<Semaphore>> =
+    Lazy::new(|| Arc::new(Semaphore::new(RENDER_MAX_CONCURRENCY)));
+
+static LAND_POLYGONS: OnceCell<Option<PolygonIndex<()>>> = OnceCell::new();
+
+/// Loads the land polygons from REDGROUSE_LAND_POLYGONS_PATH, if it's set.
+/// Called once at startup, like the time zone boundaries, so a bad file
+/// stops the server before it serves anything.
+pub fn load_land_polygons() -> anyhow::Result<()> {
+    let land = match config::land_polygons_path() {
+        Some(path) => {
+            let collection =
+                FeatureCollection::<serde::de::IgnoredAny>::load(&path).map_err(|err| {
+                    anyhow::anyhow!(
+                        "Failed to load land polygons from {}: {err}",
+                        path.display()
+                    )
+                })?;
+            let land = PolygonIndex::new(
+                collection
+                    .features
+                    .into_iter()
+                    .map(|feature| ((), feature.geometry)),
+            );
+            info!(
+                "Loaded {} land polygons from {}",
+                land.len(),
+                path.display()
+            );
+            Some(land)
+        }
+        None => None,
+    };
+    if LAND_POLYGONS.set(land).is_err() {
+        anyhow::bail!("Land polygons are already loaded");
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+enum MapFormat {
+    Png,
+    Svg,
+}
+
+impl MapFormat {
+    const fn content_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Svg => "image/svg+xml",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StaticMapQuery {
+    filter: Option<String>,
+    year_tick_year: Option<i32>,
+    country_tick_country: Option<String>,
+    tick_filter: Option<String>,
+    bbox: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+fn parse_size(value: Option<u32>, default: u32, name: &str) -> Result<u32, ApiError> {
+    let size = value.unwrap_or(default);
+    if !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+        return Err(ApiError::bad_request(format!(
+            "{name} must be between {MIN_SIZE} and {MAX_SIZE} pixels"
+        )));
+    }
+    Ok(size)
+}
+
+/// Reads `min_lng,min_lat,max_lng,max_lat`, as returned by the bbox endpoint.
+fn parse_bbox(value: &str) -> Result<[f64; 4], ApiError> {
+    let invalid = || ApiError::bad_request("bbox must be min_lng,min_lat,max_lng,max_lat");
+    let values = value
+        .split(',')
+        .map(|part| part.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+    let [min_lng, min_lat, max_lng, max_lat] = values[..] else {
+        return Err(invalid());
+    };
+    let longitudes = -180.0..=180.0;
+    let latitudes = -90.0..=90.0;
+    let in_range = longitudes.contains(&min_lng)
+        && longitudes.contains(&max_lng)
+        && latitudes.contains(&min_lat)
+        && latitudes.contains(&max_lat);
+    if !in_range || min_lng >= max_lng || min_lat >= max_lat {
+        return Err(invalid());
+    }
+    Ok([min_lng, min_lat, max_lng, max_lat])
+}
+
+/// Web Mercator, with the world running from 0 to 1 across and down.
+fn world_x(longitude: f64) -> f64 {
+    (longitude + 180.0) / 360.0
+}
+
+fn world_y(latitude: f64) -> f64 {
+    let lat_rad = latitude
+        .clamp(-MAX_MERCATOR_LATITUDE, MAX_MERCATOR_LATITUDE)
+        .to_radians();
+    (1.0 - (lat_rad.tan() + 1.0 / lat_rad.cos()).ln() / std::f64::consts::PI) / 2.0
+}
+
+fn latitude_at(y: f64) -> f64 {
+    (std::f64::consts::PI * (1.0 - 2.0 * y))
+        .sinh()
+        .atan()
+        .to_degrees()
+}
+
+fn longitude_at(x: f64) -> f64 {
+    // Maps wider than the world wrap around
+    (x * 360.0).rem_euclid(360.0) - 180.0
+}
+
+/// Which part of the world the image shows.
+#[derive(Clone, Copy)]
+struct Viewport {
+    left: f64,
+    top: f64,
+    // Pixels per world width
+    scale: f64,
+    width: u32,
+    height: u32,
+}
+
+impl Viewport {
+    /// The closest view that shows all of `bounds`, with `padding` of the
+    /// image left around them on each side.
+    fn fit(bounds: [f64; 4], width: u32, height: u32, padding: f64) -> Self {
+        let [min_lng, min_lat, max_lng, max_lat] = bounds;
+        let (x_min, x_max) = (world_x(min_lng), world_x(max_lng));
+        let (y_min, y_max) = (world_y(max_lat), world_y(min_lat));
+        let usable_width = f64::from(width) * (1.0 - 2.0 * padding);
+        let usable_height = f64::from(height) * (1.0 - 2.0 * padding);
+        let scale = (usable_width / (x_max - x_min))
+            .min(usable_height / (y_max - y_min))
+            .min(MAX_SCALE);
+        Self {
+            left: (x_min + x_max) / 2.0 - f64::from(width) / 2.0 / scale,
+            top: (y_min + y_max) / 2.0 - f64::from(height) / 2.0 / scale,
+            scale,
+            width,
+            height,
+        }
+    }
+
+    fn project(&self, latitude: f64, longitude: f64) -> (f64, f64) {
+        (
+            (world_x(longitude) - self.left) * self.scale,
+            (world_y(latitude) - self.top) * self.scale,
+        )
+    }
+
+    fn location(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            latitude_at(self.top + y / self.scale),
+            longitude_at(self.left + x / self.scale),
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Ground {
+    Sea,
+    Land,
+    // Land next to the sea
+    Coast,
+}
+
+/// Land and sea over a grid of cells covering the image.
+struct Basemap {
+    columns: u32,
+    rows: u32,
+    cells: Vec<Ground>,
+    // Credit for the data the coastline was drawn from, if one was
+    attribution: Option<&'static str>,
+}
+
+impl Basemap {
+    fn new(viewport: &Viewport) -> Self {
+        let columns = viewport.width.div_ceil(BASEMAP_CELL_SIZE);
+        let rows = viewport.height.div_ceil(BASEMAP_CELL_SIZE);
+        let land_polygons = LAND_POLYGONS.get().and_then(Option::as_ref);
+        let (max_scale, attribution) = match land_polygons {
+            Some(_) => (MAX_LAND_POLYGON_SCALE, LAND_POLYGONS_ATTRIBUTION),
+            None => (MAX_BASEMAP_SCALE, COUNTRY_BOUNDARIES_ATTRIBUTION),
+        };
+        // Closer in, it's all drawn as land rather than showing a misleading
+        // coastline
+        if viewport.scale > max_scale {
+            return Self {
+                columns,
+                rows,
+                cells: vec![Ground::Land; (columns * rows) as usize],
+                attribution: None,
+            };
+        }
+
+        let cell_size = f64::from(BASEMAP_CELL_SIZE);
+        let land: Vec<bool> = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let (latitude, longitude) = viewport.location(
+                    (f64::from(column) + 0.5) * cell_size,
+                    (f64::from(row) + 0.5) * cell_size,
+                );
+                match land_polygons {
+                    Some(land) => land.find(latitude, longitude).is_some(),
+                    None => is_land(latitude, longitude),
+                }
+            })
+            .collect();
+
+        let is_sea = |column: Option<u32>, row: Option<u32>| match (column, row) {
+            (Some(column), Some(row)) if column < columns && row < rows => {
+                !land[(row * columns + column) as usize]
+            }
+            _ => false,
+        };
+        let cells = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                if !land[(row * columns + column) as usize] {
+                    return Ground::Sea;
+                }
+                let coast = is_sea(column.checked_sub(1), Some(row))
+                    || is_sea(column.checked_add(1), Some(row))
+                    || is_sea(Some(column), row.checked_sub(1))
+                    || is_sea(Some(column), row.checked_add(1));
+                if coast {
+                    Ground::Coast
+                } else {
+                    Ground::Land
+                }
+            })
+            .collect();
+
+        Self {
+            columns,
+            rows,
+            cells,
+            attribution: Some(attribution),
+        }
+    }
+
+    /// Runs of adjacent cells of the given kind along each row, as
+    /// `(x, y, width)` in pixels.
+    fn runs(&self, ground: Ground) -> Vec<(u32, u32, u32)> {
+        let mut runs = Vec::new();
+        for row in 0..self.rows {
+            let cells = &self.cells[(row * self.columns) as usize..][..self.columns as usize];
+            let mut start = None;
+            for (column, cell) in (0..).zip(cells.iter().chain([&Ground::Sea])) {
+                match (start, *cell == ground) {
+                    (None, true) => start = Some(column),
+                    (Some(first), false) => {
+                        runs.push((
+                            first * BASEMAP_CELL_SIZE,
+                            row * BASEMAP_CELL_SIZE,
+                            (column - first) * BASEMAP_CELL_SIZE,
+                        ));
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        runs
+    }
+}
+
+/// Where each sighting is drawn on the image. Sightings that would be drawn
+/// on top of one another of the same kind are only drawn once, and ticks go
+/// on top.
+fn place_markers(
+    viewport: &Viewport,
+    sightings: &[(f64, f64, SightingKind)],
+) -> Vec<(f64, f64, SightingKind)> {
+    let reach = SIGHTING_RADIUS + SIGHTING_STROKE_WIDTH;
+    let x_range = -reach..f64::from(viewport.width) + reach;
+    let y_range = -reach..f64::from(viewport.height) + reach;
+    let mut drawn = HashSet::new();
+    let mut markers: Vec<_> = sightings
+        .iter()
+        .filter_map(|&(latitude, longitude, kind)| {
+            let (x, y) = viewport.project(latitude, longitude);
+            let visible = x_range.contains(&x) && y_range.contains(&y);
+            let new = drawn.insert((x.round() as i64, y.round() as i64, kind));
+            (visible && new).then_some((x, y, kind))
+        })
+        .collect();
+    markers.sort_by_key(|(_, _, kind)| *kind);
+    markers
+}
+
+fn render_png(
+    viewport: &Viewport,
+    basemap: &Basemap,
+    markers: &[(f64, f64, SightingKind)],
+) -> Result<Vec<u8>, ApiError> {
+    let mut canvas = Canvas::filled(viewport.width, viewport.height, COLOUR_SEA);
+    for (ground, colour) in [(Ground::Land, COLOUR_LAND), (Ground::Coast, COLOUR_COAST)] {
+        for (x, y, width) in basemap.runs(ground) {
+            canvas.fill_rect(x, y, width, BASEMAP_CELL_SIZE, colour);
+        }
+    }
+    for &(x, y, kind) in markers {
+        canvas.draw_sighting(x, y, kind, 1.0);
+    }
+    // The canvas can't draw text, so the credit goes in the file instead
+    let text: &[(&str, &str)] = match basemap.attribution {
+        Some(attribution) => &[("Copyright", attribution)],
+        None => &[],
+    };
+    canvas.to_png_with_text(text).map_err(|e| {
+        error!("Failed to encode static map: {}", e);
+        ApiError::internal("Map rendering error")
+    })
+}
+
+fn render_svg(
+    viewport: &Viewport,
+    basemap: &Basemap,
+    markers: &[(f64, f64, SightingKind)],
+) -> Vec<u8> {
+    let (width, height) = (viewport.width, viewport.height);
+    let mut svg = String::new();
+    // Writing to a String can't fail
+    let _ = write!(
+        svg,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n\
+         <rect width=\"{width}\" height=\"{height}\" fill=\"{}\"/>\n",
+        hex(COLOUR_SEA)
+    );
+    for (ground, colour) in [(Ground::Land, COLOUR_LAND), (Ground::Coast, COLOUR_COAST)] {
+        let _ = write!(svg, "<path fill=\"{}\" d=\"", hex(colour));
+        for (x, y, run_width) in basemap.runs(ground) {
+            let _ = write!(
+                svg,
+                "M{x} {y}h{run_width}v{BASEMAP_CELL_SIZE}h-{run_width}z"
+            );
+        }
+        svg.push_str("\"/>\n");
+    }
+    let _ = writeln!(
+        svg,
+        "<g stroke=\"{}\" stroke-width=\"{SIGHTING_STROKE_WIDTH}\">",
+        hex(COLOUR_WHITE)
+    );
+    for &(x, y, kind) in markers {
+        let _ = writeln!(
+            svg,
+            "<circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"{SIGHTING_RADIUS}\" fill=\"{}\"/>",
+            hex(kind.colour())
+        );
+    }
+    svg.push_str("</g>\n");
+    if let Some(attribution) = basemap.attribution {
+        let _ = writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"10\" text-anchor=\"end\" fill=\"{}\">{attribution}</text>",
+            width.saturating_sub(4),
+            height.saturating_sub(4),
+            hex(COLOUR_ATTRIBUTION)
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg.into_bytes()
+}
+
+async fn static_map(
+    pools: DbPools,
+    upload_id: &str,
+    headers: &HeaderMap,
+    query: StaticMapQuery,
+    format: MapFormat,
+) -> Result<Response, ApiError> {
+    let access = resolve_upload_access(pools.read(), upload_id, headers).await?;
+    let policy = LocationPolicy::load(pools.read(), &access).await?;
+    let upload_uuid = *access.upload_uuid();
+    let data_version = get_upload_data_version(pools.read(), &upload_uuid).await?;
+
+    let width = parse_size(query.width, DEFAULT_WIDTH, "width")?;
+    let height = parse_size(query.height, DEFAULT_HEIGHT, "height")?;
+    let bbox = query.bbox.as_deref().map(parse_bbox).transpose()?;
+
+    let tick_visibility = TickVisibility::from_query(query.tick_filter.as_deref())
+        .map(|vis| vis.with_required(query.year_tick_year, query.country_tick_country.as_ref()))?;
+    let filter_sql = build_filter_clause(FilterRequest {
+        pool: pools.read(),
+        upload_id: &upload_uuid.as_bytes()[..],
+        filter_json: query.filter.as_ref(),
+        scope_filter: access.scope(),
+        year_tick_year: query.year_tick_year,
+        country_tick_country: query.country_tick_country.as_ref(),
+        aliases: TableAliases::new(Some("s"), Some("sp")),
+        tick_visibility: &tick_visibility,
+    })
+    .await?;
+
+    let sql = format!(
+        "SELECT s.latitude, s.longitude, sp.scientific_name, s.lifer, s.year_tick, s.country_tick
+        FROM sightings s
+        JOIN species sp ON s.species_id = sp.id
+        WHERE s.upload_id = ?{}",
+        filter_sql.clause()
+    );
+    let mut db_query = sqlx::query(&sql).bind(&upload_uuid.as_bytes()[..]);
+    for param in filter_sql.params() {
+        db_query = db_query.bind(param);
+    }
+
+    // Positions as shown to the caller, after location privacy
+    let sightings = db::query_with_timeout(async {
+        let mut sightings = Vec::new();
+        let mut rows = db_query.fetch(pools.read());
+        while let Some(row) = rows.try_next().await? {
+            let Some((latitude, longitude)) = policy.apply(
+                row.get("scientific_name"),
+                row.get("latitude"),
+                row.get("longitude"),
+            ) else {
+                continue;
+            };
+            let kind = SightingKind::new(
+                row.get::<i64, _>("lifer") > 0,
+                row.get::<i64, _>("year_tick") > 0,
+                row.get::<i64, _>("country_tick") > 0,
+            );
+            sightings.push((latitude, longitude, kind));
+        }
+        Ok(sightings)
+    })
+    .await
+    .map_err(|e| e.into_api_error("loading static map sightings", "Database error"))?;
+
+    let viewport = match bbox {
+        Some(bbox) => Viewport::fit(bbox, width, height, 0.0),
+        None => {
+            let bounds = sightings
+                .iter()
+                .fold(None, |bounds: Option<[f64; 4]>, &(lat, lng, _)| {
+                    Some(match bounds {
+                        Some([min_lng, min_lat, max_lng, max_lat]) => [
+                            lng.min(min_lng),
+                            lat.min(min_lat),
+                            lng.max(max_lng),
+                            lat.max(max_lat),
+                        ],
+                        None => [lng, lat, lng, lat],
+                    })
+                });
+            match bounds {
+                Some(bounds) => Viewport::fit(bounds, width, height, FIT_PADDING),
+                None => Viewport::fit(WORLD_BOUNDS, width, height, 0.0),
+            }
+        }
+    };
+
+    let _permit = match timeout(
+        Duration::from_millis(RENDER_WAIT_TIMEOUT_MS),
+        RENDER_GUARD.clone().acquire_owned(),
+    )
+    .await
+    {
+        Ok(Ok(permit)) => permit,
+        Ok(Err(_)) => return Err(ApiError::service_unavailable("Map renderer unavailable")),
+        Err(_) => {
+            return Err(ApiError::service_unavailable(
+                "Map renderer is busy, please retry",
+            ))
+        }
+    };
+    let image = tokio::task::spawn_blocking(move || {
+        let basemap = Basemap::new(&viewport);
+        let markers = place_markers(&viewport, &sightings);
+        match format {
+            MapFormat::Png => render_png(&viewport, &basemap, &markers),
+            MapFormat::Svg => Ok(render_svg(&viewport, &basemap, &markers)),
+        }
+    })
+    .await
+    .map_err(|_| ApiError::internal("Map rendering task failed"))??;
+
+    // As with tiles, exact locations and password-protected uploads stay out
+    // of shared caches
+    let cache_control = if access.is_owner() || access.is_password_protected() {
+        "private, max-age=3600"
+    } else {
+        "public, max-age=3600"
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(header::CACHE_CONTROL, cache_control)
+        .header(header::VARY, header::AUTHORIZATION.as_str())
+        .header("x-upload-version", data_version.to_string())
+        .body(Body::from(image))
+        .map_err(|err| {
+            error!("Failed to build static map response: {}", err);
+            ApiError::internal("Failed to build response")
+        })
+}
+
+pub async fn static_map_png(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    Query(query): Query<StaticMapQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    static_map(pools, &upload_id, &headers, query, MapFormat::Png).await
+}
+
+pub async fn static_map_svg(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    Query(query): Query<StaticMapQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    static_map(pools, &upload_id, &headers, query, MapFormat::Svg).await
+}
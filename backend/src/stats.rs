@@ -2,8 +2,11 @@ use crate::db::{self, DbPools, DbQueryError};
 use crate::error::ApiError;
 use crate::filter::{build_filter_clause, CountQuery, FilterRequest, TableAliases};
 use crate::proto::{pb, Proto};
+use crate::shares::resolve_upload_access;
+use crate::ticks::tick_key_sql;
 use crate::upload::get_upload_data_version;
 use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
 use sqlx::Row;
 use uuid::Uuid;
 
@@ -11,9 +14,10 @@ pub async fn get_stats(
     State(pools): State<DbPools>,
     Path(upload_id): Path<String>,
     Query(query): Query<CountQuery>,
+    headers: HeaderMap,
 ) -> Result<Proto<pb::StatsResponse>, ApiError> {
-    let upload_uuid = Uuid::parse_str(&upload_id)
-        .map_err(|_| ApiError::bad_request("Invalid upload_id format"))?;
+    let access = resolve_upload_access(pools.read(), &upload_id, &headers).await?;
+    let upload_uuid = *access.upload_uuid();
     let data_version = get_upload_data_version(pools.read(), &upload_uuid).await?;
 
     let needs_join = if let Some(filter_json) = &query.filter {
@@ -21,7 +25,7 @@ pub async fn get_stats(
         filter.needs_species_join()
     } else {
         false
-    };
+    } || access.scope_needs_species_join();
 
     let aliases = if needs_join {
         TableAliases::new(Some("s"), Some("sp"))
@@ -34,6 +38,7 @@ pub async fn get_stats(
         pool: pools.read(),
         upload_id: &upload_uuid.as_bytes()[..],
         filter_json: query.filter.as_ref(),
+        scope_filter: access.scope(),
         year_tick_year: query.year_tick_year,
         country_tick_country: query.country_tick_country.as_ref(),
         aliases,
@@ -55,7 +60,6 @@ pub async fn get_stats(
             SUM(CASE WHEN {prefix}lifer = 1 THEN 1 ELSE 0 END) as total_lifers,
             SUM(CASE WHEN {prefix}year_tick = 1 THEN 1 ELSE 0 END) as total_year_ticks,
             SUM(CASE WHEN {prefix}country_tick = 1 THEN 1 ELSE 0 END) as total_country_ticks,
-            COUNT(DISTINCT {prefix}species_id) as total_species,
             COUNT(DISTINCT {prefix}country_code) as total_countries,
             COUNT(DISTINCT {prefix}region_code) as total_regions,
             MIN({prefix}observed_at) as first_sighting,
@@ -82,19 +86,22 @@ pub async fn get_stats(
     let total_lifers: i64 = row.get("total_lifers");
     let total_year_ticks: i64 = row.get("total_year_ticks");
     let total_country_ticks: i64 = row.get("total_country_ticks");
-    let total_species: i64 = row.get("total_species");
     let total_countries: i64 = row.get("total_countries");
     let total_regions: i64 = row.get("total_regions");
     let first_sighting: Option<String> = row.get("first_sighting");
     let latest_sighting: Option<String> = row.get("latest_sighting");
     let total_individuals: Option<i64> = row.get("total_individuals");
 
+    let total_species = count_species(pools.read(), &upload_uuid, &filter_sql, needs_join)
+        .await
+        .map_err(|e| e.into_api_error("counting species", "Database error"))?;
+
     let hours_birding_minutes =
         compute_birding_time(pools.read(), &upload_uuid, &filter_sql, needs_join)
             .await
             .map_err(|e| e.into_api_error("computing birding time", "Database error"))?;
 
-    let top_species = get_top_species(pools.read(), &upload_uuid, &filter_sql, needs_join)
+    let top_species = get_top_species(pools.read(), &upload_uuid, &filter_sql)
         .await
         .map_err(|e| e.into_api_error("loading top species", "Database error"))?;
 
@@ -102,6 +109,26 @@ pub async fn get_stats(
         .await
         .map_err(|e| e.into_api_error("loading country stats", "Database error"))?;
 
+    let family_stats = get_rank_stats(
+        pools.read(),
+        &upload_uuid,
+        &filter_sql,
+        needs_join,
+        TaxonRank::Family,
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading family stats", "Database error"))?;
+
+    let order_stats = get_rank_stats(
+        pools.read(),
+        &upload_uuid,
+        &filter_sql,
+        needs_join,
+        TaxonRank::Order,
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading order stats", "Database error"))?;
+
     let (lifers_timeline, sightings_timeline) =
         compute_timelines(pools.read(), &upload_uuid, &filter_sql, needs_join)
             .await
@@ -131,9 +158,50 @@ pub async fn get_stats(
         lifers_timeline,
         sightings_timeline,
         longest_streak_days,
+        family_stats,
+        order_stats,
     }))
 }
 
+/// Distinct species that tick: subspecies groups count as their parent and
+/// spuhs, slashes, hybrids and domestic forms don't count at all.
+async fn count_species(
+    pool: &sqlx::SqlitePool,
+    upload_uuid: &Uuid,
+    filter_sql: &crate::filter::FilterSql,
+    needs_join: bool,
+) -> Result<i64, DbQueryError> {
+    let (table_name, join_clause) = if needs_join {
+        ("sightings s", " JOIN species sp ON s.species_id = sp.id")
+    } else {
+        ("sightings", "")
+    };
+
+    let sightings_prefix = if needs_join { "s." } else { "" };
+
+    let sql = format!(
+        "SELECT COUNT(DISTINCT {tick_key})
+         FROM (
+            SELECT DISTINCT {prefix}species_id as species_id
+            FROM {table}{join}
+            WHERE {prefix}upload_id = ?{filter}
+         ) f
+         JOIN species tsp ON tsp.id = f.species_id",
+        tick_key = tick_key_sql("tsp"),
+        prefix = sightings_prefix,
+        table = table_name,
+        join = join_clause,
+        filter = filter_sql.clause()
+    );
+
+    let mut db_query = sqlx::query_scalar(&sql).bind(&upload_uuid.as_bytes()[..]);
+    for param in filter_sql.params() {
+        db_query = db_query.bind(param);
+    }
+
+    db::query_with_timeout(db_query.fetch_one(pool)).await
+}
+
 async fn compute_birding_time(
     pool: &sqlx::SqlitePool,
     upload_uuid: &Uuid,
@@ -174,23 +242,15 @@ async fn get_top_species(
     pool: &sqlx::SqlitePool,
     upload_uuid: &Uuid,
     filter_sql: &crate::filter::FilterSql,
-    needs_join: bool,
 ) -> Result<Vec<pb::SpeciesCount>, DbQueryError> {
-    let (table_name, join_clause) = if needs_join {
-        ("sightings s", " JOIN species sp ON s.species_id = sp.id")
-    } else {
-        ("sightings s", " JOIN species sp ON s.species_id = sp.id")
-    };
-
+    // Species names are always needed here, so the join isn't optional
     let sql = format!(
         "SELECT sp.common_name, sp.scientific_name, COUNT(*) as cnt
-         FROM {table}{join}
+         FROM sightings s JOIN species sp ON s.species_id = sp.id
          WHERE s.upload_id = ?{filter}
          GROUP BY sp.id
          ORDER BY cnt DESC
          LIMIT 20",
-        table = table_name,
-        join = join_clause,
         filter = filter_sql.clause()
     );
 
@@ -258,6 +318,83 @@ async fn get_country_stats(
         .collect())
 }
 
+#[derive(Clone, Copy)]
+enum TaxonRank {
+    Family,
+    Order,
+}
+
+impl TaxonRank {
+    const fn column(self) -> &'static str {
+        match self {
+            Self::Family => "t.family",
+            Self::Order => "t.order_name",
+        }
+    }
+}
+
+/// Per-family or per-order totals. Species the taxonomy didn't recognise
+/// have no rank and are left out; `species` counts the same way as
+/// `total_species`.
+async fn get_rank_stats(
+    pool: &sqlx::SqlitePool,
+    upload_uuid: &Uuid,
+    filter_sql: &crate::filter::FilterSql,
+    needs_join: bool,
+    rank: TaxonRank,
+) -> Result<Vec<pb::TaxonRankCount>, DbQueryError> {
+    let (table_name, join_clause) = if needs_join {
+        ("sightings s", " JOIN species sp ON s.species_id = sp.id")
+    } else {
+        ("sightings", "")
+    };
+
+    let sightings_prefix = if needs_join { "s." } else { "" };
+
+    // Filter in a subquery first so the filter's unqualified columns can't
+    // clash with the taxonomy tables.
+    let sql = format!(
+        "SELECT
+            {rank} as name,
+            COUNT(DISTINCT {tick_key}) as species,
+            COUNT(*) as sightings,
+            SUM(f.lifer) as lifers
+         FROM (
+            SELECT {prefix}species_id as species_id, {prefix}lifer as lifer
+            FROM {table}{join}
+            WHERE {prefix}upload_id = ?{filter}
+         ) f
+         JOIN species tsp ON tsp.id = f.species_id
+         JOIN taxa t ON t.id = tsp.taxon_id
+         WHERE {rank} IS NOT NULL
+         GROUP BY {rank}
+         ORDER BY species DESC, name",
+        rank = rank.column(),
+        tick_key = tick_key_sql("tsp"),
+        prefix = sightings_prefix,
+        table = table_name,
+        join = join_clause,
+        filter = filter_sql.clause()
+    );
+
+    let mut db_query = sqlx::query(&sql).bind(&upload_uuid.as_bytes()[..]);
+    for param in filter_sql.params() {
+        db_query = db_query.bind(param);
+    }
+
+    let rows = db::query_with_timeout(db_query.fetch_all(pool)).await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| pb::TaxonRankCount {
+            name: row.get("name"),
+            species: row.get::<i64, _>("species"),
+            sightings: row.get::<i64, _>("sightings"),
+            lifers: row.get::<i64, _>("lifers"),
+        })
+        .collect())
+}
+
 async fn compute_timelines(
     pool: &sqlx::SqlitePool,
     upload_uuid: &Uuid,
@@ -274,11 +411,11 @@ async fn compute_timelines(
 
     let sql = format!(
         "SELECT
-            DATE({prefix}observed_at) as date,
+            {prefix}local_date as date,
             {prefix}lifer
          FROM {table}{join}
          WHERE {prefix}upload_id = ?{filter}
-         ORDER BY {prefix}observed_at",
+         ORDER BY {prefix}local_date, {prefix}observed_at",
         prefix = sightings_prefix,
         table = table_name,
         join = join_clause,
@@ -363,7 +500,7 @@ async fn compute_longest_streak(
     let sightings_prefix = if needs_join { "s." } else { "" };
 
     let sql = format!(
-        "SELECT DISTINCT DATE({prefix}observed_at) as date
+        "SELECT DISTINCT {prefix}local_date as date
          FROM {table}{join}
          WHERE {prefix}upload_id = ?{filter}
          ORDER BY date",
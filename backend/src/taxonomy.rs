@@ -0,0 +1,726 @@
+//! Bird taxonomy used to reconcile the species names found in imports.
+//!
+//! Exports spell the same bird differently ("Gray Heron", "Grey Heron",
+//! "Parus caeruleus" for Cyanistes caeruleus), so names are matched against
+//! the taxonomy and stored under its canonical names. Names the taxonomy does
+//! not know are stored as given.
+//!
+//! Deployments point `REDGROUSE_TAXONOMY_PATH` at the eBird/Clements checklist
+//! as eBird publishes it. Without one, the small sample in `data/taxonomy.csv`
+//! is used, which only covers common European and North American birds. Its
+//! names and synonyms are kept as alternate names for the full checklist, so
+//! IOC spellings and old names still match.
+
+use std::collections::{HashMap, HashSet};
+
+use once_cell::sync::Lazy;
+use sqlx::{FromRow, QueryBuilder, Sqlite, SqlitePool, Transaction};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::bitmaps::compute_and_store_bitmaps;
+use crate::config;
+use crate::db::{self, DbPools, DbQueryError};
+use crate::pipeline::{resolve_species_id, SQLITE_MAX_VARIABLES};
+use crate::sightings::invalidate_name_index_cache;
+use crate::ticks::recompute_tick_flags;
+use crate::tiles::invalidate_upload_cache;
+use crate::upload::compute_grid_cell_visibility_tx;
+
+const TAXONOMY_CSV: &str = include_str!("../data/taxonomy.csv");
+const SYNONYMS_CSV: &str = include_str!("../data/taxonomy_synonyms.csv");
+
+const TAXONOMY_HEADER: &str =
+    "taxon_id,category,common_name,scientific_name,order,family,report_as";
+const SYNONYMS_HEADER: &str = "taxon_id,name";
+
+// 8 bound columns per taxa row, kept under SQLite's variable limit.
+const TAXA_INSERT_BATCH_SIZE: usize = 100;
+// One bound variable per species in the affected-upload lookup.
+const SPECIES_SCOPE_BATCH_SIZE: usize = SQLITE_MAX_VARIABLES;
+
+static TAXONOMY: Lazy<Taxonomy> = Lazy::new(|| {
+    Taxonomy::load().unwrap_or_else(|err| {
+        error!("Failed to load taxonomy: {}", err);
+        panic!("Taxonomy is invalid. Application cannot start without it.");
+    })
+});
+
+/// eBird-style taxon categories. Only species and subspecies groups count
+/// towards ticks; the rest are identifications that stop short of a species.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaxonCategory {
+    Species,
+    /// Identifiable subspecies group, counted as the species it reports as
+    Issf,
+    /// Genus- or family-level identification, e.g. "gull sp."
+    Spuh,
+    /// One of two species, e.g. "Marsh/Willow Tit"
+    Slash,
+    Hybrid,
+    Domestic,
+}
+
+impl TaxonCategory {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "species" => Some(Self::Species),
+            "issf" => Some(Self::Issf),
+            "spuh" => Some(Self::Spuh),
+            "slash" => Some(Self::Slash),
+            "hybrid" => Some(Self::Hybrid),
+            "domestic" => Some(Self::Domestic),
+            _ => None,
+        }
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Species => "species",
+            Self::Issf => "issf",
+            Self::Spuh => "spuh",
+            Self::Slash => "slash",
+            Self::Hybrid => "hybrid",
+            Self::Domestic => "domestic",
+        }
+    }
+
+    pub const fn is_countable(self) -> bool {
+        matches!(self, Self::Species | Self::Issf)
+    }
+
+    /// Best guess for names the taxonomy doesn't know, from the eBird naming
+    /// conventions for non-species entries.
+    fn infer(common_name: &str, scientific_name: &str) -> Self {
+        let names = [normalise(common_name), normalise(scientific_name)];
+        if names.iter().any(|name| name.ends_with(" sp.")) {
+            Self::Spuh
+        } else if names
+            .iter()
+            .any(|name| name.contains(" x ") || name.contains("(hybrid)"))
+        {
+            Self::Hybrid
+        } else if names.iter().any(|name| name.contains('/')) {
+            Self::Slash
+        } else if names.iter().any(|name| name.contains("(domestic type)")) {
+            Self::Domestic
+        } else {
+            Self::Species
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Taxon {
+    pub id: i64,
+    pub category: TaxonCategory,
+    pub common_name: String,
+    pub scientific_name: String,
+    pub order: Option<String>,
+    pub family: Option<String>,
+    pub genus: Option<String>,
+    /// Species an `issf` (subspecies group) entry counts towards.
+    pub report_as: Option<i64>,
+}
+
+pub struct Taxonomy {
+    taxa: Vec<Taxon>,
+    by_id: HashMap<i64, usize>,
+    by_name: HashMap<String, usize>,
+}
+
+/// How a species counts towards ticks.
+pub struct SpeciesClass<'a> {
+    pub taxon: Option<&'a Taxon>,
+    pub countable: bool,
+    /// Species a subspecies group rolls up to.
+    pub parent: Option<&'a Taxon>,
+}
+
+impl Taxonomy {
+    pub fn global() -> &'static Self {
+        &TAXONOMY
+    }
+
+    pub fn taxa(&self) -> &[Taxon] {
+        &self.taxa
+    }
+
+    /// Matches on the scientific name first, as common names vary far more
+    /// between checklists, then falls back to the common name.
+    pub fn lookup(&self, common_name: &str, scientific_name: &str) -> Option<&Taxon> {
+        [scientific_name, common_name]
+            .into_iter()
+            .filter(|name| !name.trim().is_empty())
+            .find_map(|name| self.by_name.get(&normalise(name)))
+            .map(|&idx| &self.taxa[idx])
+    }
+
+    pub fn get(&self, id: i64) -> Option<&Taxon> {
+        self.by_id.get(&id).map(|&idx| &self.taxa[idx])
+    }
+
+    /// Names the taxonomy doesn't know still count as species unless they
+    /// look like a spuh, slash, hybrid or domestic form.
+    pub fn classify(&self, common_name: &str, scientific_name: &str) -> SpeciesClass<'_> {
+        match self.lookup(common_name, scientific_name) {
+            Some(taxon) => SpeciesClass {
+                taxon: Some(taxon),
+                countable: taxon.category.is_countable(),
+                parent: taxon.report_as.and_then(|id| self.get(id)),
+            },
+            None => SpeciesClass {
+                taxon: None,
+                countable: TaxonCategory::infer(common_name, scientific_name).is_countable(),
+                parent: None,
+            },
+        }
+    }
+
+    fn load() -> Result<Self, String> {
+        let bundled = Self::parse(TAXONOMY_CSV, SYNONYMS_CSV)?;
+        let Some(path) = config::taxonomy_path() else {
+            return Ok(bundled);
+        };
+
+        let data = std::fs::read_to_string(&path)
+            .map_err(|err| format!("reading {}: {err}", path.display()))?;
+        let mut taxonomy =
+            Self::parse_ebird(&data).map_err(|err| format!("{}: {err}", path.display()))?;
+        taxonomy.adopt_names(&bundled);
+        info!(
+            "Loaded {} taxa from {}",
+            taxonomy.taxa.len(),
+            path.display()
+        );
+        Ok(taxonomy)
+    }
+
+    fn parse(taxonomy_csv: &str, synonyms_csv: &str) -> Result<Self, String> {
+        let mut taxa = Vec::new();
+
+        for (line_no, fields) in csv_rows(taxonomy_csv, TAXONOMY_HEADER, 7)? {
+            let id = parse_id(fields[0], line_no)?;
+            let report_as = match fields[6] {
+                "" => None,
+                value => Some(parse_id(value, line_no)?),
+            };
+            let category = TaxonCategory::parse(fields[1])
+                .ok_or_else(|| format!("line {line_no}: unknown category {:?}", fields[1]))?;
+            taxa.push(Taxon {
+                id,
+                category,
+                common_name: fields[2].to_string(),
+                scientific_name: fields[3].to_string(),
+                order: non_empty(fields[4]),
+                family: non_empty(fields[5]),
+                genus: None,
+                report_as,
+            });
+        }
+
+        let mut taxonomy = Self::index(taxa)?;
+        for (line_no, fields) in csv_rows(synonyms_csv, SYNONYMS_HEADER, 2)? {
+            let id = parse_id(fields[0], line_no)?;
+            let idx = *taxonomy
+                .by_id
+                .get(&id)
+                .ok_or_else(|| format!("synonyms line {line_no}: unknown taxon_id {id}"))?;
+            insert_name(&mut taxonomy.by_name, fields[1], idx)?;
+        }
+        Ok(taxonomy)
+    }
+
+    /// Reads the checklist eBird publishes (`eBird_taxonomy_v2024.csv` and
+    /// the like), using the taxon order as the ID. Columns are found by name,
+    /// since they've moved between releases.
+    fn parse_ebird(data: &str) -> Result<Self, String> {
+        let mut lines = data
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let header = match lines.next() {
+            Some((_, line)) => split_quoted(line.trim_start_matches('\u{feff}'))?,
+            None => return Err("file is empty".to_string()),
+        };
+        let column = |names: &[&str]| {
+            header
+                .iter()
+                .position(|field| names.iter().any(|name| field.eq_ignore_ascii_case(name)))
+                .ok_or_else(|| format!("missing column {}", names[0]))
+        };
+        let id_col = column(&["TAXON_ORDER"])?;
+        let category_col = column(&["CATEGORY"])?;
+        let code_col = column(&["SPECIES_CODE"])?;
+        let common_col = column(&["PRIMARY_COM_NAME"])?;
+        let scientific_col = column(&["SCI_NAME"])?;
+        let order_col = column(&["ORDER", "ORDER1"])?;
+        let family_col = column(&["FAMILY"])?;
+        let report_as_col = column(&["REPORT_AS"])?;
+
+        let mut taxa = Vec::new();
+        let mut ids_by_code = HashMap::new();
+        let mut report_as_codes = Vec::new();
+        for (idx, line) in lines {
+            let line_no = idx + 1;
+            let fields = split_quoted(line).map_err(|err| format!("line {line_no}: {err}"))?;
+            let field = |col: usize| fields.get(col).map_or("", String::as_str);
+
+            let id = parse_id(field(id_col), line_no)?;
+            let report_as = field(report_as_col);
+            let category = match field(category_col) {
+                // Forms and intergrades count as the species they report as
+                "form" | "intergrade" if !report_as.is_empty() => TaxonCategory::Issf,
+                "form" | "intergrade" => TaxonCategory::Spuh,
+                value => TaxonCategory::parse(value)
+                    .ok_or_else(|| format!("line {line_no}: unknown category {value:?}"))?,
+            };
+            ids_by_code.insert(field(code_col).to_string(), id);
+            report_as_codes.push((line_no, report_as.to_string()));
+            taxa.push(Taxon {
+                id,
+                category,
+                common_name: field(common_col).to_string(),
+                scientific_name: field(scientific_col).to_string(),
+                order: non_empty(field(order_col)),
+                // "Ardeidae (Herons, Egrets, and Bitterns)"
+                family: field(family_col).split(" (").next().and_then(non_empty),
+                genus: None,
+                report_as: None,
+            });
+        }
+
+        for (taxon, (line_no, code)) in taxa.iter_mut().zip(report_as_codes) {
+            if !code.is_empty() {
+                let report_as = ids_by_code.get(&code).ok_or_else(|| {
+                    format!("line {line_no}: reports as unknown species code {code:?}")
+                })?;
+                taxon.report_as = Some(*report_as);
+            }
+        }
+
+        Self::index(taxa)
+    }
+
+    /// Adds the names another taxonomy knows for each of this one's taxa,
+    /// matched on scientific name, where they don't already mean something.
+    fn adopt_names(&mut self, other: &Self) {
+        for (name, &other_idx) in &other.by_name {
+            let scientific_name = normalise(&other.taxa[other_idx].scientific_name);
+            if let Some(&idx) = self.by_name.get(&scientific_name) {
+                self.by_name.entry(name.clone()).or_insert(idx);
+            }
+        }
+    }
+
+    fn index(mut taxa: Vec<Taxon>) -> Result<Self, String> {
+        let mut by_id = HashMap::new();
+        for (idx, taxon) in taxa.iter().enumerate() {
+            if by_id.insert(taxon.id, idx).is_some() {
+                return Err(format!("duplicate taxon_id {}", taxon.id));
+            }
+        }
+
+        // A spuh or hybrid only gets a genus if it names one a species uses,
+        // which keeps "Larinae sp." (a subfamily) out of the genus list.
+        let species_genera: HashSet<String> = taxa
+            .iter()
+            .filter(|taxon| taxon.category == TaxonCategory::Species)
+            .filter_map(|taxon| single_genus(&taxon.scientific_name))
+            .map(str::to_string)
+            .collect();
+        for taxon in &mut taxa {
+            taxon.genus = single_genus(&taxon.scientific_name)
+                .filter(|genus| species_genera.contains(*genus))
+                .map(str::to_string);
+        }
+
+        let mut by_name = HashMap::new();
+        for (idx, taxon) in taxa.iter().enumerate() {
+            if let Some(report_as) = taxon.report_as {
+                let parent = by_id.get(&report_as).map(|&parent| &taxa[parent]);
+                if parent.is_none_or(|parent| parent.category != TaxonCategory::Species) {
+                    return Err(format!(
+                        "taxon {} must report as a species, not {report_as}",
+                        taxon.id
+                    ));
+                }
+            }
+            insert_name(&mut by_name, &taxon.scientific_name, idx)?;
+            insert_name(&mut by_name, &taxon.common_name, idx)?;
+        }
+
+        Ok(Self {
+            taxa,
+            by_id,
+            by_name,
+        })
+    }
+}
+
+/// Lowercases and collapses whitespace, treating hyphens as spaces and curly
+/// apostrophes as straight ones, so "Black-headed Gull" matches
+/// "black headed gull".
+fn normalise(name: &str) -> String {
+    name.replace('-', " ")
+        .replace('\u{2019}', "'")
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The genus a scientific name belongs to, if every capitalised word in it
+/// agrees ("Corvus corone x cornix" does, "Mareca strepera x Anas
+/// platyrhynchos" doesn't).
+fn single_genus(scientific_name: &str) -> Option<&str> {
+    let mut genera = scientific_name
+        .split(|c: char| c.is_whitespace() || c == '/')
+        .filter(|word| word.starts_with(char::is_uppercase));
+    let first = genera.next()?;
+    genera.all(|genus| genus == first).then_some(first)
+}
+
+fn insert_name(by_name: &mut HashMap<String, usize>, name: &str, idx: usize) -> Result<(), String> {
+    match by_name.insert(normalise(name), idx) {
+        Some(existing) if existing != idx => Err(format!("name {name:?} maps to two taxa")),
+        _ => Ok(()),
+    }
+}
+
+/// The bundled files hold no quoted fields, so a plain split is enough.
+fn csv_rows<'a>(
+    data: &'a str,
+    header: &str,
+    columns: usize,
+) -> Result<Vec<(usize, Vec<&'a str>)>, String> {
+    let mut lines = data.lines().enumerate();
+    match lines.next() {
+        Some((_, first)) if first.trim() == header => {}
+        _ => return Err(format!("expected header {header:?}")),
+    }
+
+    lines
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() == columns {
+                Ok((idx + 1, fields))
+            } else {
+                Err(format!(
+                    "line {}: expected {columns} columns, found {}",
+                    idx + 1,
+                    fields.len()
+                ))
+            }
+        })
+        .collect()
+}
+
+/// Splits a line of a CSV file that may quote fields, as the eBird checklist
+/// does for family names with commas in them. Quoted fields can't span lines.
+fn split_quoted(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted field".to_string());
+    }
+    fields.push(field);
+    Ok(fields
+        .iter()
+        .map(|field| field.trim().to_string())
+        .collect())
+}
+
+fn parse_id(value: &str, line_no: usize) -> Result<i64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("line {line_no}: invalid taxon ID {value:?}"))
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Mirrors the bundled taxonomy into the `taxa` table. Rows are only ever
+/// upserted: species keep pointing at taxa dropped from a newer bundle.
+pub async fn sync_taxa(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let taxa = Taxonomy::global().taxa();
+    let mut tx = pool.begin().await?;
+
+    for chunk in taxa.chunks(TAXA_INSERT_BATCH_SIZE) {
+        let mut qb = QueryBuilder::<Sqlite>::new(
+            "INSERT INTO taxa (id, category, common_name, scientific_name, order_name, family, genus, report_as) ",
+        );
+        qb.push_values(chunk, |mut row, taxon| {
+            row.push_bind(taxon.id)
+                .push_bind(taxon.category.as_str())
+                .push_bind(taxon.common_name.as_str())
+                .push_bind(taxon.scientific_name.as_str())
+                .push_bind(taxon.order.as_deref())
+                .push_bind(taxon.family.as_deref())
+                .push_bind(taxon.genus.as_deref())
+                .push_bind(taxon.report_as);
+        });
+        qb.push(
+            " ON CONFLICT(id) DO UPDATE SET
+                category = excluded.category,
+                common_name = excluded.common_name,
+                scientific_name = excluded.scientific_name,
+                order_name = excluded.order_name,
+                family = excluded.family,
+                genus = excluded.genus,
+                report_as = excluded.report_as",
+        );
+        qb.build().execute(&mut *tx).await?;
+    }
+
+    tx.commit().await?;
+    info!("Synced {} taxa", taxa.len());
+    Ok(())
+}
+
+#[derive(FromRow)]
+struct StoredSpecies {
+    id: i64,
+    common_name: String,
+    scientific_name: String,
+    taxon_id: Option<i64>,
+    countable: bool,
+    parent_species_id: Option<i64>,
+}
+
+/// Brings species stored under an older taxonomy (or none) in line with the
+/// bundled one: links names it now recognises, merges species stored under a
+/// synonym into the canonical species, and updates how each counts towards
+/// ticks. Uploads holding affected species have their ticks recomputed.
+pub async fn reconcile_species(pools: &DbPools) -> Result<(), DbQueryError> {
+    let stored = db::query_with_timeout(
+        sqlx::query_as::<_, StoredSpecies>(
+            "SELECT id, common_name, scientific_name, taxon_id, countable, parent_species_id
+             FROM species",
+        )
+        .fetch_all(pools.read()),
+    )
+    .await?;
+
+    let taxonomy = Taxonomy::global();
+    let mut updated = 0;
+    let mut reclassified = Vec::new();
+    let mut touched_uploads = HashSet::new();
+
+    for species in stored {
+        let class = taxonomy.classify(&species.common_name, &species.scientific_name);
+
+        if let Some(taxon) = class.taxon {
+            if taxon.common_name != species.common_name
+                || taxon.scientific_name != species.scientific_name
+            {
+                touched_uploads.extend(merge_species(pools.write(), species.id, taxon).await?);
+                updated += 1;
+                continue;
+            }
+        }
+
+        match sync_species_class(pools.write(), &species, &class).await? {
+            ClassChange::None => {}
+            ClassChange::Linked => updated += 1,
+            ClassChange::Ticks => {
+                updated += 1;
+                reclassified.push(species.id);
+            }
+        }
+    }
+
+    if !reclassified.is_empty() {
+        touched_uploads.extend(refresh_reclassified(pools.write(), &reclassified).await?);
+    }
+
+    for upload_id_blob in touched_uploads {
+        if let Err(e) = compute_and_store_bitmaps(pools.write(), &upload_id_blob).await {
+            error!("Failed to compute tick bitmaps: {}", e.body.error);
+        }
+        if let Ok(upload_uuid) = Uuid::from_slice(&upload_id_blob) {
+            let upload_id = upload_uuid.to_string();
+            invalidate_upload_cache(&upload_id).await;
+            invalidate_name_index_cache(&upload_id);
+        }
+    }
+
+    if updated > 0 {
+        info!("Reconciled {} species with the taxonomy", updated);
+    }
+    Ok(())
+}
+
+enum ClassChange {
+    None,
+    /// Only the taxon link changed
+    Linked,
+    /// Countability or the parent species changed, so ticks need redoing
+    Ticks,
+}
+
+async fn sync_species_class(
+    pool: &SqlitePool,
+    species: &StoredSpecies,
+    class: &SpeciesClass<'_>,
+) -> Result<ClassChange, DbQueryError> {
+    let taxon_id = class.taxon.map(|taxon| taxon.id);
+    if class.parent.is_none()
+        && species.parent_species_id.is_none()
+        && species.taxon_id == taxon_id
+        && species.countable == class.countable
+    {
+        return Ok(ClassChange::None);
+    }
+
+    let mut tx = db::query_with_timeout(pool.begin()).await?;
+
+    let parent_species_id = match class.parent {
+        Some(parent) => {
+            Some(resolve_species_id(&mut tx, &parent.common_name, &parent.scientific_name).await?)
+        }
+        None => None,
+    };
+
+    let ticks_changed =
+        species.countable != class.countable || species.parent_species_id != parent_species_id;
+    if !ticks_changed && species.taxon_id == taxon_id {
+        return Ok(ClassChange::None);
+    }
+
+    db::query_with_timeout(
+        sqlx::query(
+            "UPDATE species SET taxon_id = ?, countable = ?, parent_species_id = ? WHERE id = ?",
+        )
+        .bind(taxon_id)
+        .bind(class.countable)
+        .bind(parent_species_id)
+        .bind(species.id)
+        .execute(&mut *tx),
+    )
+    .await?;
+
+    db::query_with_timeout(tx.commit()).await?;
+
+    Ok(if ticks_changed {
+        ClassChange::Ticks
+    } else {
+        ClassChange::Linked
+    })
+}
+
+/// Recomputes ticks in every upload holding one of the reclassified species.
+async fn refresh_reclassified(
+    pool: &SqlitePool,
+    species_ids: &[i64],
+) -> Result<Vec<Vec<u8>>, DbQueryError> {
+    let mut upload_ids = Vec::new();
+    for chunk in species_ids.chunks(SPECIES_SCOPE_BATCH_SIZE) {
+        let mut qb = QueryBuilder::<Sqlite>::new(
+            "SELECT DISTINCT upload_id FROM sightings WHERE species_id IN (",
+        );
+        let mut separated = qb.separated(", ");
+        for species_id in chunk {
+            separated.push_bind(*species_id);
+        }
+        qb.push(")");
+        upload_ids.extend(
+            db::query_with_timeout(qb.build_query_scalar::<Vec<u8>>().fetch_all(pool)).await?,
+        );
+    }
+    upload_ids.sort_unstable();
+    upload_ids.dedup();
+
+    for upload_id_blob in &upload_ids {
+        let mut tx = db::query_with_timeout(pool.begin()).await?;
+        recompute_tick_flags(&mut tx, upload_id_blob, Some(species_ids)).await?;
+        finish_refresh(&mut tx, upload_id_blob).await?;
+        db::query_with_timeout(tx.commit()).await?;
+    }
+
+    Ok(upload_ids)
+}
+
+/// Moves every sighting of `species_id` to the taxon's canonical species and
+/// drops the old row. Returns the uploads whose sightings moved.
+async fn merge_species(
+    pool: &SqlitePool,
+    species_id: i64,
+    taxon: &Taxon,
+) -> Result<Vec<Vec<u8>>, DbQueryError> {
+    let mut tx = db::query_with_timeout(pool.begin()).await?;
+
+    let canonical_id =
+        resolve_species_id(&mut tx, &taxon.common_name, &taxon.scientific_name).await?;
+
+    let upload_ids = db::query_with_timeout(
+        sqlx::query_scalar::<_, Vec<u8>>(
+            "SELECT DISTINCT upload_id FROM sightings WHERE species_id = ?",
+        )
+        .bind(species_id)
+        .fetch_all(&mut *tx),
+    )
+    .await?;
+
+    db::query_with_timeout(
+        sqlx::query("UPDATE sightings SET species_id = ? WHERE species_id = ?")
+            .bind(canonical_id)
+            .bind(species_id)
+            .execute(&mut *tx),
+    )
+    .await?;
+
+    db::query_with_timeout(
+        sqlx::query("UPDATE species SET parent_species_id = ? WHERE parent_species_id = ?")
+            .bind(canonical_id)
+            .bind(species_id)
+            .execute(&mut *tx),
+    )
+    .await?;
+
+    db::query_with_timeout(
+        sqlx::query("DELETE FROM species WHERE id = ?")
+            .bind(species_id)
+            .execute(&mut *tx),
+    )
+    .await?;
+
+    for upload_id_blob in &upload_ids {
+        recompute_tick_flags(&mut tx, upload_id_blob, Some(&[canonical_id])).await?;
+        finish_refresh(&mut tx, upload_id_blob).await?;
+    }
+
+    db::query_with_timeout(tx.commit()).await?;
+    Ok(upload_ids)
+}
+
+async fn finish_refresh(
+    tx: &mut Transaction<'_, Sqlite>,
+    upload_id_blob: &[u8],
+) -> Result<(), DbQueryError> {
+    compute_grid_cell_visibility_tx(tx, upload_id_blob).await?;
+
+    db::query_with_timeout(
+        sqlx::query("UPDATE uploads SET data_version = data_version + 1 WHERE id = ?")
+            .bind(upload_id_blob)
+            .execute(&mut **tx),
+    )
+    .await?;
+
+    Ok(())
+}
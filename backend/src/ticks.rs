@@ -0,0 +1,143 @@
+use crate::db::{self, DbQueryError};
+use crate::pipeline::vis_rank_sql;
+use sqlx::{QueryBuilder, Sqlite, Transaction};
+
+// 1 bound column per species, kept under SQLite's variable limit
+const SCOPE_INSERT_BATCH_SIZE: usize = 500;
+
+/// SQL for the species a sighting ticks: the species itself, the parent
+/// species for a subspecies group, or NULL for spuhs, slashes, hybrids and
+/// domestic forms, which never tick.
+pub(crate) fn tick_key_sql(species_alias: &str) -> String {
+    format!(
+        "CASE WHEN {species_alias}.countable = 1 THEN COALESCE({species_alias}.parent_species_id, {species_alias}.id) END"
+    )
+}
+
+/// Recomputes lifer/year/country tick flags for sightings in an upload from
+/// chronological order (local date, then `observed_at`, then insertion order).
+///
+/// Rows are written without ticks, since files aren't necessarily in date
+/// order, so this runs over the whole upload after a create or replace. Later
+/// changes (appends, collections, single sighting edits) only affect the
+/// species involved: ticks only depend on other sightings of the same species,
+/// so `species_ids` limits the work to the species that actually changed
+/// (plus any subspecies groups sharing their ticks).
+pub(crate) async fn recompute_tick_flags(
+    tx: &mut Transaction<'_, Sqlite>,
+    upload_id_blob: &[u8],
+    species_ids: Option<&[i64]>,
+) -> Result<(), DbQueryError> {
+    let scoped = match species_ids {
+        Some(species_ids) => {
+            fill_tick_scope(tx, species_ids).await?;
+            true
+        }
+        None => false,
+    };
+
+    let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+        "UPDATE sightings SET
+            lifer = t.tick_key IS NOT NULL AND t.species_rn = 1,
+            year_tick = t.tick_key IS NOT NULL AND t.year_rn = 1,
+            country_tick = t.tick_key IS NOT NULL AND t.has_country AND t.country_rn = 1
+        FROM (
+            SELECT id, tick_key,
+                ROW_NUMBER() OVER (PARTITION BY tick_key ORDER BY local_date, observed_at, id) AS species_rn,
+                ROW_NUMBER() OVER (PARTITION BY tick_key, year ORDER BY local_date, observed_at, id) AS year_rn,
+                ROW_NUMBER() OVER (PARTITION BY tick_key, country_code ORDER BY local_date, observed_at, id) AS country_rn,
+                country_code IS NOT NULL AND country_code != '' AND UPPER(country_code) != 'XX' AS has_country
+            FROM (
+                SELECT s.id, s.local_date, s.observed_at, s.year, s.country_code, {tick_key} AS tick_key
+                FROM sightings s JOIN species sp ON sp.id = s.species_id
+                WHERE s.upload_id = ",
+        tick_key = tick_key_sql("sp")
+    ));
+    qb.push_bind(upload_id_blob);
+    push_species_scope(&mut qb, scoped);
+    qb.push(")) AS t WHERE sightings.id = t.id");
+    db::query_with_timeout(qb.build().execute(&mut **tx)).await?;
+
+    refresh_vis_ranks(tx, upload_id_blob, scoped).await?;
+
+    if scoped {
+        db::query_with_timeout(sqlx::query("DROP TABLE temp.tick_scope").execute(&mut **tx))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Fills `temp.tick_scope` with a set of species widened to every species
+/// sharing their ticks, so a subspecies group and its parent species are
+/// always recomputed together. The IDs go through a table rather than an
+/// `IN (...)` list since an append can touch more species than SQLite allows
+/// bound variables.
+async fn fill_tick_scope(
+    tx: &mut Transaction<'_, Sqlite>,
+    species_ids: &[i64],
+) -> Result<(), DbQueryError> {
+    db::query_with_timeout(
+        sqlx::query("CREATE TEMP TABLE IF NOT EXISTS tick_scope (species_id INTEGER PRIMARY KEY)")
+            .execute(&mut **tx),
+    )
+    .await?;
+    db::query_with_timeout(sqlx::query("DELETE FROM temp.tick_scope").execute(&mut **tx)).await?;
+
+    for chunk in species_ids.chunks(SCOPE_INSERT_BATCH_SIZE) {
+        let mut qb: QueryBuilder<Sqlite> =
+            QueryBuilder::new("INSERT OR IGNORE INTO temp.tick_scope (species_id) ");
+        qb.push_values(chunk, |mut b, species_id| {
+            b.push_bind(*species_id);
+        });
+        db::query_with_timeout(qb.build().execute(&mut **tx)).await?;
+    }
+
+    db::query_with_timeout(
+        sqlx::query(&format!(
+            "INSERT OR IGNORE INTO temp.tick_scope (species_id)
+            SELECT sp.id FROM species sp
+            WHERE {} IN (SELECT {} FROM species k JOIN temp.tick_scope c ON c.species_id = k.id)",
+            tick_key_sql("sp"),
+            tick_key_sql("k")
+        ))
+        .execute(&mut **tx),
+    )
+    .await?;
+
+    Ok(())
+}
+
+fn push_species_scope(qb: &mut QueryBuilder<'_, Sqlite>, scoped: bool) {
+    if scoped {
+        qb.push(" AND species_id IN (SELECT species_id FROM temp.tick_scope)");
+    }
+}
+
+/// Ticks always get vis_rank 0. Anything else left at 0 (a former tick, or a
+/// grid cell pick from a previous pass) goes back to its hashed rank so the
+/// grid pass can choose again.
+async fn refresh_vis_ranks(
+    tx: &mut Transaction<'_, Sqlite>,
+    upload_id_blob: &[u8],
+    scoped: bool,
+) -> Result<(), DbQueryError> {
+    let mut qb: QueryBuilder<Sqlite> =
+        QueryBuilder::new("UPDATE sightings SET vis_rank = 0 WHERE upload_id = ");
+    qb.push_bind(upload_id_blob);
+    push_species_scope(&mut qb, scoped);
+    qb.push(" AND (lifer = 1 OR year_tick = 1 OR country_tick = 1)");
+    db::query_with_timeout(qb.build().execute(&mut **tx)).await?;
+
+    let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(format!(
+        "UPDATE sightings SET vis_rank = {} WHERE upload_id = ",
+        vis_rank_sql("sighting_uuid")
+    ));
+    qb.push_bind(upload_id_blob);
+    push_species_scope(&mut qb, scoped);
+    qb.push(
+        " AND vis_rank = 0 AND lifer = 0 AND year_tick = 0 AND country_tick = 0 AND length(sighting_uuid) = 16",
+    );
+    db::query_with_timeout(qb.build().execute(&mut **tx)).await?;
+
+    Ok(())
+}
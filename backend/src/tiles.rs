@@ -1,36 +1,52 @@
 use crate::db::DbPools;
 use axum::extract::{Path, Query, State};
-use axum::http::{header, StatusCode};
+use axum::http::{header, HeaderMap, StatusCode};
 use axum::response::{IntoResponse, Response};
 use moka::future::Cache;
 use mvt::{GeomEncoder, GeomType, Tile};
 use once_cell::sync::Lazy;
+use roaring::RoaringBitmap;
 use serde::Deserialize;
 use sha2::{Digest, Sha256};
-use sqlx::Row;
+use sqlx::sqlite::{Sqlite, SqliteArguments};
+use sqlx::{QueryBuilder, Row};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Semaphore;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio::time::timeout;
 use tracing::{debug, error};
+use uuid::Uuid;
 
 use crate::db;
 use crate::error::ApiError;
 use crate::filter::{build_filter_clause, FilterRequest, FilterSql, TableAliases, TickVisibility};
+use crate::location_privacy::{lat_degrees, lng_degrees, LocationMode, LocationPolicy};
+use crate::pipeline::SQLITE_MAX_VARIABLES;
+use crate::raster::{Canvas, SightingKind, SIGHTING_RADIUS, SIGHTING_STROKE_WIDTH};
+use crate::shares::{resolve_upload_access, UploadAccess};
+use crate::sightings::upload_species_names;
 use crate::upload::get_upload_data_version;
-use uuid::Uuid;
 
 const TILE_EXTENT: u32 = 4096;
-// Maximum vis_rank value (0-10000). When threshold equals this, all points are included.
-const MAX_VIS_RANK: i32 = 10000;
+// Deeper than any map client asks for
+const MAX_ZOOM: u32 = 24;
+// Web Mercator stops short of the poles, where it stretches to infinity
+const MAX_LATITUDE: f64 = 85.051_128_779_806_59;
 // Tile cache size limit: ~50MB (assuming average tile size of ~10KB, cache ~5000 tiles)
 const TILE_CACHE_SIZE: u64 = 50 * 1024 * 1024;
 const TILE_ENCODER_MAX_CONCURRENCY: usize = 128;
 const TILE_ENCODER_WAIT_TIMEOUT_MS: u64 = 500;
 static TILE_ENCODER_GUARD: Lazy<Arc<Semaphore>> =
     Lazy::new(|| Arc::new(Semaphore::new(TILE_ENCODER_MAX_CONCURRENCY)));
-const BBOX_CANDIDATE_LIMIT_MULTIPLIER: i64 = 4;
-const BBOX_CANDIDATE_LIMIT_MAX: i64 = 1_000_000;
+// Point tiles spread their sample over this many cells across and down
+const SAMPLE_GRID_SIZE: u32 = 64;
+// PNG tiles are the usual 256px square
+const RASTER_TILE_SIZE: u32 = 256;
+// Density tiles split each tile into this many cells across and down
+const DENSITY_GRID_SIZE: u32 = 64;
+// Cluster tiles group sightings into cells of 256 tile units, about 32px on screen
+const CLUSTER_GRID_SIZE: u32 = 16;
 
 // LRU cache for tiles: key is (upload_id, z, x, y, filter_hash), value is encoded MVT bytes
 static TILE_CACHE: Lazy<Cache<String, Vec<u8>>> = Lazy::new(|| {
@@ -105,6 +121,261 @@ fn latlng_to_tile_coords(latlng: LatLng, tile_coords: TileCoordinates) -> TileCo
     TileCoords { tile_x, tile_y }
 }
 
+impl Bbox {
+    /// The box grown by `km` on every side.
+    fn grown(&self, km: f64) -> Self {
+        let lat_margin = lat_degrees(km);
+        let lon_margin = lng_degrees(km, self.lat_min.abs().max(self.lat_max.abs()) + lat_margin);
+        Self {
+            lon_min: self.lon_min - lon_margin,
+            lat_min: self.lat_min - lat_margin,
+            lon_max: self.lon_max + lon_margin,
+            lat_max: self.lat_max + lat_margin,
+        }
+    }
+}
+
+/// A grid of equal cells over the whole Web Mercator map, `cells` across and
+/// down. A tile at zoom z split `size` ways is part of the grid with
+/// 2^z * size cells, so cells line up between neighbouring tiles.
+#[derive(Clone, Copy)]
+struct CellGrid {
+    cells: f64,
+}
+
+/// A block of cells in a grid, from its top-left cell.
+struct CellRange {
+    first_column: i64,
+    first_row: i64,
+    columns: i64,
+    rows: i64,
+}
+
+impl CellRange {
+    fn for_tile(tile: TileCoordinates, size: u32) -> Self {
+        Self {
+            first_column: i64::from(tile.x) * i64::from(size),
+            first_row: i64::from(tile.y) * i64::from(size),
+            columns: i64::from(size),
+            rows: i64::from(size),
+        }
+    }
+
+    fn contains(&self, column: i64, row: i64) -> bool {
+        (0..self.columns).contains(&column) && (0..self.rows).contains(&row)
+    }
+}
+
+impl CellGrid {
+    fn for_tile(tile: TileCoordinates, size: u32) -> Self {
+        let n = 2_f64.powi(i32::try_from(tile.z).unwrap_or(i32::MAX));
+        Self {
+            cells: n * f64::from(size),
+        }
+    }
+
+    /// The finest grid, no finer than this one, whose cells are at least
+    /// `km` across everywhere in `bbox`. Cells are only ever halved, so they
+    /// still line up with this grid's.
+    fn coarsened(self, km: f64, bbox: &Bbox) -> Self {
+        let widest = bbox.lat_min.abs().max(bbox.lat_max.abs()).min(MAX_LATITUDE);
+        let most = 360.0 / lng_degrees(km, widest);
+        if most >= self.cells {
+            return self;
+        }
+        Self {
+            cells: 2_f64.powi(most.log2().floor() as i32).max(1.0),
+        }
+    }
+
+    fn column(self, longitude: f64) -> i64 {
+        ((longitude + 180.0) / 360.0 * self.cells).floor() as i64
+    }
+
+    fn row(self, latitude: f64) -> i64 {
+        let lat_rad = latitude.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+        let y = (1.0 - (lat_rad.tan() + 1.0 / lat_rad.cos()).ln() / std::f64::consts::PI) / 2.0;
+        (y * self.cells).floor() as i64
+    }
+
+    /// The longitude of a column's left edge; fractions reach into it.
+    fn longitude(self, column: f64) -> f64 {
+        column / self.cells * 360.0 - 180.0
+    }
+
+    /// The latitude of a row's top edge; fractions reach into it.
+    fn latitude(self, row: f64) -> f64 {
+        (std::f64::consts::PI * (1.0 - 2.0 * row / self.cells))
+            .sinh()
+            .atan()
+            .to_degrees()
+    }
+
+    /// Every cell `bbox` touches.
+    fn range(self, bbox: &Bbox) -> CellRange {
+        let last = self.cells as i64 - 1;
+        let first_column = self.column(bbox.lon_min).clamp(0, last);
+        let first_row = self.row(bbox.lat_max).clamp(0, last);
+        CellRange {
+            first_column,
+            first_row,
+            columns: self.column(bbox.lon_max).clamp(0, last) - first_column + 1,
+            rows: self.row(bbox.lat_min).clamp(0, last) - first_row + 1,
+        }
+    }
+
+    /// The box covering the whole of every cell in `range`.
+    fn bbox(self, range: &CellRange) -> Bbox {
+        Bbox {
+            lon_min: self.longitude(range.first_column as f64),
+            lat_min: self.latitude((range.first_row + range.rows) as f64),
+            lon_max: self.longitude((range.first_column + range.columns) as f64),
+            lat_max: self.latitude(range.first_row as f64),
+        }
+    }
+
+    /// The latitude and longitude of a cell's centre.
+    fn centre(self, column: i64, row: i64) -> (f64, f64) {
+        (
+            self.latitude(row as f64 + 0.5),
+            self.longitude(column as f64 + 0.5),
+        )
+    }
+}
+
+// A sighting's column of a grid, from the grid's width in cells per degree
+// and the range's first column
+const CELL_COLUMN_SQL: &str = "(CAST((s.longitude + 180.0) * ? AS INTEGER) - ?)";
+
+/// SQL for the cell of a range each sighting falls in, counted from the
+/// range's top-left cell. Sightings outside the range come out outside it.
+struct CellSql {
+    row: String,
+    column_scale: f64,
+    first_column: i64,
+    row_edges: Vec<f64>,
+}
+
+impl CellSql {
+    fn new(grid: CellGrid, range: &CellRange) -> Self {
+        // SQLite has no logarithms to project a latitude with, so the row is
+        // counted from the row edges the sighting is below
+        let row_edges: Vec<f64> = (range.first_row..=range.first_row + range.rows)
+            .map(|row| grid.latitude(row as f64))
+            .collect();
+        Self {
+            row: format!("(-1{})", " + (s.latitude <= ?)".repeat(row_edges.len())),
+            column_scale: grid.cells / 360.0,
+            first_column: range.first_column,
+            row_edges,
+        }
+    }
+
+    /// Binds the parameters of `CELL_COLUMN_SQL` then `row`.
+    fn bind<'q>(
+        &self,
+        mut query: sqlx::query::Query<'q, Sqlite, SqliteArguments<'q>>,
+    ) -> sqlx::query::Query<'q, Sqlite, SqliteArguments<'q>> {
+        query = query.bind(self.column_scale).bind(self.first_column);
+        for edge in &self.row_edges {
+            query = query.bind(*edge);
+        }
+        query
+    }
+}
+
+/// Species that location privacy moves the same way, written into SQL.
+enum SpeciesSet {
+    Only(Vec<i64>),
+    Except(Vec<i64>),
+}
+
+impl SpeciesSet {
+    fn clause(&self) -> String {
+        let (operator, ids) = match self {
+            Self::Except(ids) if ids.is_empty() => return String::new(),
+            Self::Only(ids) => ("IN", ids),
+            Self::Except(ids) => ("NOT IN", ids),
+        };
+        // The ids come from the database rather than the request, and there
+        // can be more of them than SQLite takes parameters
+        let ids: Vec<String> = ids.iter().map(ToString::to_string).collect();
+        format!("AND s.species_id {operator} ({})", ids.join(", "))
+    }
+}
+
+/// The upload's species, grouped by how location privacy shows them.
+struct SpeciesModes {
+    default: LocationMode,
+    // Species with a mode other than the default, grouped by mode
+    others: Vec<(LocationMode, Vec<i64>)>,
+}
+
+impl SpeciesModes {
+    async fn load(
+        pool: &sqlx::SqlitePool,
+        policy: &LocationPolicy,
+        upload_uuid: &Uuid,
+        data_version: i64,
+    ) -> Result<Self, ApiError> {
+        let default = policy.default_mode();
+        let mut others: Vec<(LocationMode, Vec<i64>)> = Vec::new();
+        if policy.is_exact() {
+            return Ok(Self { default, others });
+        }
+
+        for (species_id, scientific_name) in
+            upload_species_names(pool, upload_uuid, data_version).await?
+        {
+            let mode = policy.mode_for(&scientific_name);
+            if mode == default {
+                continue;
+            }
+            match others.iter_mut().find(|(other, _)| *other == mode) {
+                Some((_, ids)) => ids.push(species_id),
+                None => others.push((mode, vec![species_id])),
+            }
+        }
+        Ok(Self { default, others })
+    }
+
+    /// The species whose sightings are shown at all.
+    fn visible(&self) -> SpeciesSet {
+        let with_hidden = |hidden: bool| {
+            self.others
+                .iter()
+                .filter(|(mode, _)| (*mode == LocationMode::Hide) == hidden)
+                .flat_map(|(_, ids)| ids.iter().copied())
+                .collect()
+        };
+        if self.default == LocationMode::Hide {
+            SpeciesSet::Only(with_hidden(false))
+        } else {
+            SpeciesSet::Except(with_hidden(true))
+        }
+    }
+
+    /// Each mode that shows sightings, with the species it covers. Species
+    /// added since the list was loaded fall under the default.
+    fn shown(&self) -> Vec<(LocationMode, SpeciesSet)> {
+        let mut shown = Vec::new();
+        if self.default != LocationMode::Hide {
+            let others = self
+                .others
+                .iter()
+                .flat_map(|(_, ids)| ids.iter().copied())
+                .collect();
+            shown.push((self.default, SpeciesSet::Except(others)));
+        }
+        for (mode, ids) in &self.others {
+            if *mode != LocationMode::Hide {
+                shown.push((*mode, SpeciesSet::Only(ids.clone())));
+            }
+        }
+        shown
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct TileQuery {
     filter: Option<String>,
@@ -121,17 +392,65 @@ pub struct TilePath {
     pub y: String,
 }
 
+/// The kind of tile a request is for. Each has its own cache entries.
+#[derive(Debug, Clone, Copy)]
+enum TileLayer {
+    Points,
+    Density,
+    Clusters,
+    Raster,
+}
+
+impl TileLayer {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Points => "points",
+            Self::Density => "density",
+            Self::Clusters => "clusters",
+            Self::Raster => "png",
+        }
+    }
+
+    const fn content_type(self) -> &'static str {
+        match self {
+            Self::Raster => "image/png",
+            Self::Points | Self::Density | Self::Clusters => "application/x-protobuf",
+        }
+    }
+
+    /// How far beyond the tile, in tile units, sightings are drawn from.
+    /// Markers on PNG tiles are drawn whole, so they need the ones just over
+    /// the edge.
+    fn buffer(self) -> f64 {
+        match self {
+            Self::Raster => {
+                let marker_px = SIGHTING_RADIUS + SIGHTING_STROKE_WIDTH + 1.0;
+                marker_px * f64::from(TILE_EXTENT) / f64::from(RASTER_TILE_SIZE)
+            }
+            Self::Points | Self::Density | Self::Clusters => 0.0,
+        }
+    }
+}
+
 fn compute_filter_hash(
     filter: Option<&String>,
     tick_filter: Option<&String>,
     tick_visibility: &TickVisibility,
     year_tick_year: Option<i32>,
     country_tick_country: Option<&String>,
+    access: &UploadAccess,
 ) -> String {
     let mut hasher = Sha256::new();
     if let Some(f) = filter {
         hasher.update(f.as_bytes());
     }
+    // Tiles for a share link depend on what the share lets through
+    if let Some(scope) = access.scope() {
+        hasher.update(serde_json::to_vec(scope).unwrap_or_default());
+    }
+    hasher.update([u8::from(access.hides_locations())]);
+    // Owners see exact locations, everyone else what location privacy allows
+    hasher.update([u8::from(access.is_owner())]);
     if let Some(tf) = tick_filter {
         hasher.update(tf.as_bytes());
     }
@@ -152,6 +471,14 @@ fn compute_filter_hash(
     hex::encode(hasher.finalize())
 }
 
+/// The sightings chosen for a point tile, moved to where the caller may see
+/// them, how many there are in it, and whether any were left out.
+struct TileSample {
+    rows: Vec<RowData>,
+    total: usize,
+    truncated: bool,
+}
+
 struct RowData {
     id: i64,
     latitude: f64,
@@ -165,28 +492,114 @@ struct RowData {
     country_tick: i32,
 }
 
+/// The matching sightings in one cell of a grid, counted in SQL.
+struct CellTotals {
+    // From the top-left cell of the range asked for
+    column: i64,
+    row: i64,
+    sightings: u64,
+    individuals: u64,
+    species: RoaringBitmap,
+    lifers: u64,
+    year_ticks: u64,
+    country_ticks: u64,
+    // Mean position of the cell's sightings
+    latitude: f64,
+    longitude: f64,
+    best: i64,
+}
+
+/// A cell's totals and where they're shown in the tile.
+struct PlacedCell {
+    cell: (u32, u32),
+    point: TileCoords,
+    totals: CellTotals,
+}
+
+// Clusters show their most representative sighting: lifers first, then year
+// and country ticks, as on the map, with ties going to the oldest sighting so
+// the pick doesn't change between requests. SQL ranks both at once with MAX
+// over the kind of tick times this, less the sighting id.
+const BEST_SIGHTING_SCALE: i64 = 1 << 40;
+
+/// The sighting whose rank is `best`, as worked out in SQL.
+const fn best_sighting_id(best: i64) -> i64 {
+    (best.div_euclid(BEST_SIGHTING_SCALE) + 1) * BEST_SIGHTING_SCALE - best
+}
+
+/// Totals for one cell of a density tile.
+#[derive(Default)]
+struct DensityCell {
+    sightings: u64,
+    individuals: u64,
+    species: RoaringBitmap,
+}
+
+type DensityGrid = BTreeMap<(u32, u32), DensityCell>;
+
+/// Every sighting in one cell of a cluster tile, summarised.
+#[derive(Default)]
+struct Cluster {
+    sightings: u64,
+    individuals: u64,
+    species: RoaringBitmap,
+    lifers: u64,
+    year_ticks: u64,
+    country_ticks: u64,
+    // Summed tile positions, for placing the cluster at its members' centre
+    sum_x: f64,
+    sum_y: f64,
+    // Rank of the best sighting, until it's loaded into `best`
+    best_rank: Option<i64>,
+    best: Option<RowData>,
+}
+
+impl Cluster {
+    fn centre(&self) -> (f64, f64) {
+        let n = self.sightings.max(1) as f64;
+        (self.sum_x / n, self.sum_y / n)
+    }
+}
+
+type ClusterGrid = BTreeMap<(u32, u32), Cluster>;
+
 struct TileRequest {
-    upload_uuid: Uuid,
+    access: UploadAccess,
+    policy: LocationPolicy,
+    species_modes: SpeciesModes,
+    layer: TileLayer,
     tile_pos: TileCoordinates,
     bbox: Bbox,
+    query_bbox: Bbox,
     cache_key: String,
     filter_sql: FilterSql,
-    include_all_points: bool,
-    vis_rank_threshold: i32,
     max_points: i64,
     data_version: i64,
 }
 
 impl TileRequest {
-    async fn build(pools: &DbPools, path: TilePath, query: TileQuery) -> Result<Self, ApiError> {
-        let upload_uuid = Uuid::parse_str(&path.upload_id)
-            .map_err(|_| ApiError::bad_request("Invalid upload_id format"))?;
+    async fn build(
+        pools: &DbPools,
+        path: TilePath,
+        query: TileQuery,
+        headers: &HeaderMap,
+        layer: TileLayer,
+    ) -> Result<Self, ApiError> {
+        let access = resolve_upload_access(pools.read(), &path.upload_id, headers).await?;
+        let policy = LocationPolicy::load(pools.read(), &access).await?;
+        let upload_uuid = *access.upload_uuid();
         let y: u32 = path
             .y
             .trim_end_matches(".pbf")
+            .trim_end_matches(".png")
             .parse()
             .map_err(|_| ApiError::bad_request("Invalid y coordinate"))?;
+        if path.z > MAX_ZOOM || path.x >> path.z != 0 || y >> path.z != 0 {
+            return Err(ApiError::bad_request("Tile out of range"));
+        }
         let data_version = get_upload_data_version(pools.read(), &upload_uuid).await?;
+        let species_modes =
+            SpeciesModes::load(pools.read(), &policy, &upload_uuid, data_version).await?;
 
         let tile_pos = TileCoordinates {
             z: path.z,
@@ -194,6 +607,18 @@ impl TileRequest {
             y,
         };
         let bbox = tile_to_bbox(tile_pos);
+        // Locations are moved after loading, so look far enough beyond the
+        // tile to catch sightings that land inside it
+        let near = bbox.grown(policy.max_shift_km());
+        // A degree of latitude never covers fewer pixels than one of
+        // longitude, so the longitude width of the buffer does for both
+        let buffer_margin = (bbox.lon_max - bbox.lon_min) * layer.buffer() / f64::from(TILE_EXTENT);
+        let query_bbox = Bbox {
+            lon_min: near.lon_min - buffer_margin,
+            lat_min: near.lat_min - buffer_margin,
+            lon_max: near.lon_max + buffer_margin,
+            lat_max: near.lat_max + buffer_margin,
+        };
 
         let TileQuery {
             filter,
@@ -210,12 +635,14 @@ impl TileRequest {
             &tick_visibility,
             year_tick_year,
             country_tick_country.as_ref(),
+            &access,
         );
 
         let filter_sql = build_filter_clause(FilterRequest {
             pool: pools.read(),
             upload_id: &upload_uuid.as_bytes()[..],
             filter_json: filter.as_ref(),
+            scope_filter: access.scope(),
             year_tick_year,
             country_tick_country: country_tick_country.as_ref(),
             aliases: TableAliases::new(Some("s"), Some("sp")),
@@ -224,21 +651,28 @@ impl TileRequest {
         .await?;
 
         let cache_key = format!(
-            "{}:{}:{}:{}:{}:{}",
-            path.upload_id, data_version, path.z, path.x, y, filter_hash
+            "{}:{}:{}:{}:{}:{}:{}",
+            upload_uuid,
+            data_version,
+            layer.as_str(),
+            path.z,
+            path.x,
+            y,
+            filter_hash
         );
 
-        let (vis_rank_threshold, include_all_points) = zoom_threshold(path.z);
         let max_points = max_points_for_zoom(path.z);
 
         Ok(Self {
-            upload_uuid,
+            access,
+            policy,
+            species_modes,
+            layer,
             tile_pos,
             bbox,
+            query_bbox,
             cache_key,
             filter_sql,
-            include_all_points,
-            vis_rank_threshold,
             max_points,
             data_version,
         })
@@ -249,7 +683,7 @@ impl TileRequest {
     }
 
     fn upload_id_bytes(&self) -> &[u8] {
-        self.upload_uuid.as_bytes()
+        self.access.upload_id_blob()
     }
 
     fn tile_pos(&self) -> TileCoordinates {
@@ -263,6 +697,51 @@ impl TileRequest {
     fn data_version(&self) -> i64 {
         self.data_version
     }
+
+    /// Where a location falls in the tile, if it's inside it or the layer's
+    /// buffer around it.
+    fn tile_point(&self, latitude: f64, longitude: f64) -> Option<TileCoords> {
+        let coords = latlng_to_tile_coords(
+            LatLng {
+                lat: latitude,
+                lng: longitude,
+            },
+            self.tile_pos,
+        );
+        let buffer = self.layer.buffer();
+        let range = -buffer..f64::from(TILE_EXTENT) + buffer;
+        let inside = range.contains(&coords.tile_x) && range.contains(&coords.tile_y);
+        inside.then_some(coords)
+    }
+
+    /// Exact locations shown to the owner, and anything behind a view
+    /// password, mustn't land in shared caches.
+    fn cache_control(&self) -> &'static str {
+        if self.access.is_owner() || self.access.is_password_protected() {
+            "private, max-age=3600"
+        } else {
+            "public, max-age=3600"
+        }
+    }
+
+    /// Moves sightings to where the caller may see them, dropping any that
+    /// end up outside the tile and its buffer.
+    fn present_rows(&self, rows: Vec<RowData>) -> Vec<RowData> {
+        if self.policy.is_exact() {
+            return rows;
+        }
+        rows.into_iter()
+            .filter_map(|mut row| {
+                (row.latitude, row.longitude) = self.policy.apply(
+                    row.scientific_name.as_deref().unwrap_or_default(),
+                    row.latitude,
+                    row.longitude,
+                )?;
+                self.tile_point(row.latitude, row.longitude)?;
+                Some(row)
+            })
+            .collect()
+    }
 }
 
 struct TileDataFetcher<'a> {
@@ -274,166 +753,407 @@ impl<'a> TileDataFetcher<'a> {
         Self { pools }
     }
 
-    async fn fetch_rows(&self, request: &TileRequest) -> Result<Vec<RowData>, ApiError> {
-        if request.include_all_points {
-            self.fetch_with_rtree(request).await
-        } else {
-            self.fetch_with_vis_rank(request).await
-        }
-    }
-
-    async fn fetch_with_rtree(&self, request: &TileRequest) -> Result<Vec<RowData>, ApiError> {
-        let candidate_limit = (request
-            .max_points
-            .saturating_mul(BBOX_CANDIDATE_LIMIT_MULTIPLIER))
-        .max(request.max_points)
-        .min(BBOX_CANDIDATE_LIMIT_MAX);
-
+    /// Samples the sightings matching the filter near the tile, up to the
+    /// zoom level's limit, spread evenly across it. Each round takes the next
+    /// sighting from every cell of the sample grid, lowest vis_rank first, so
+    /// sparse areas keep their sightings however dense others are. Ticks have
+    /// vis_rank 0, so they're taken first, and a sighting's place depends
+    /// only on the others in its cell, which keeps the choice consistent
+    /// between neighbouring tiles. Cells go by where sightings were seen, as
+    /// location privacy moves them after they're chosen.
+    async fn fetch_sample(&self, request: &TileRequest) -> Result<TileSample, ApiError> {
+        let range = CellRange::for_tile(request.tile_pos, SAMPLE_GRID_SIZE);
+        let cell_sql = CellSql::new(
+            CellGrid::for_tile(request.tile_pos, SAMPLE_GRID_SIZE),
+            &range,
+        );
+        let last_cell = SAMPLE_GRID_SIZE - 1;
         let sql = format!(
             r#"
-            WITH bbox AS (
-                SELECT id
-                FROM sightings_geo
-                WHERE max_lat >= ? AND min_lat <= ?
-                  AND max_lon >= ? AND min_lon <= ?
-                LIMIT ?
+            WITH candidates AS (
+                SELECT
+                    s.id,
+                    COALESCE(s.vis_rank, 0) AS vis_rank,
+                    {CELL_COLUMN_SQL} AS cell_x,
+                    {} AS cell_y
+                FROM sightings AS s
+                JOIN species sp ON s.species_id = sp.id
+                JOIN sightings_geo AS sg ON sg.id = s.id
+                WHERE s.upload_id = ?
+                  AND sg.max_lat >= ? AND sg.min_lat <= ?
+                  AND sg.max_lon >= ? AND sg.min_lon <= ?
+                {}
+                {}
             )
             SELECT
-                s.id,
-                s.latitude,
-                s.longitude,
-                sp.common_name,
-                sp.scientific_name,
-                s.count,
-                s.observed_at,
-                s.lifer,
-                s.year_tick,
-                s.country_tick
-            FROM bbox
-            JOIN sightings AS s ON s.id = bbox.id
-            JOIN species sp ON s.species_id = sp.id
-            WHERE s.upload_id = ?{}
+                id,
+                (
+                    SELECT COUNT(*) FROM candidates
+                    WHERE cell_x BETWEEN 0 AND {last_cell}
+                      AND cell_y BETWEEN 0 AND {last_cell}
+                ) AS total,
+                (SELECT COUNT(*) FROM candidates) AS candidates
+            FROM (
+                SELECT
+                    id,
+                    vis_rank,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY cell_x, cell_y ORDER BY vis_rank, id
+                    ) AS round
+                FROM candidates
+            )
+            ORDER BY round, vis_rank, id
             LIMIT ?
             "#,
-            request.filter_sql.clause()
+            cell_sql.row,
+            request.filter_sql.clause(),
+            request.species_modes.visible().clause()
         );
 
-        let mut db_query = sqlx::query(&sql)
-            .bind(request.bbox.lat_min)
-            .bind(request.bbox.lat_max)
-            .bind(request.bbox.lon_min)
-            .bind(request.bbox.lon_max)
-            .bind(candidate_limit)
-            .bind(request.upload_id_bytes());
+        let mut db_query = cell_sql
+            .bind(sqlx::query(&sql))
+            .bind(request.upload_id_bytes())
+            .bind(request.query_bbox.lat_min)
+            .bind(request.query_bbox.lat_max)
+            .bind(request.query_bbox.lon_min)
+            .bind(request.query_bbox.lon_max);
 
         for param in request.filter_sql.params() {
             db_query = db_query.bind(param);
         }
         db_query = db_query.bind(request.max_points);
 
-        let rows = db::query_with_timeout(db_query.fetch_all(self.pools.read()))
+        let sampled = db::query_with_timeout(db_query.fetch_all(self.pools.read()))
             .await
-            .map_err(|e| e.into_api_error("loading tile sightings", "Database error"))?;
+            .map_err(|e| e.into_api_error("sampling tile sightings", "Database error"))?;
+
+        let ids: Vec<i64> = sampled.iter().map(|row| row.get("id")).collect();
+        let rows = request.present_rows(self.fetch_sightings(&ids).await?);
+
+        if !request.policy.is_exact() {
+            // Counting moved sightings by where they were seen would show
+            // which tile a sensitive sighting is really in, so only those
+            // shown here count, and the tile is only known to be truncated
+            // when the sample is full
+            let limit = usize::try_from(request.max_points).unwrap_or(usize::MAX);
+            return Ok(TileSample {
+                total: rows.len(),
+                truncated: ids.len() >= limit,
+                rows,
+            });
+        }
+        let (total, candidates) = sampled.first().map_or((0, 0), |row| {
+            (row.get::<i64, _>("total"), row.get::<i64, _>("candidates"))
+        });
+        Ok(TileSample {
+            rows,
+            total: usize::try_from(total).unwrap_or(0),
+            truncated: usize::try_from(candidates).unwrap_or(0) > ids.len(),
+        })
+    }
 
-        Ok(rows
-            .into_iter()
-            .map(|row| RowData {
-                id: row.get("id"),
-                latitude: row.get("latitude"),
-                longitude: row.get("longitude"),
-                common_name: row.get("common_name"),
-                scientific_name: row.get("scientific_name"),
-                count: row.get("count"),
-                observed_at: row.get("observed_at"),
-                lifer: row.get("lifer"),
-                year_tick: row.get("year_tick"),
-                country_tick: row.get("country_tick"),
-            })
-            .collect())
+    /// Loads the given sightings, in the order given.
+    async fn fetch_sightings(&self, ids: &[i64]) -> Result<Vec<RowData>, ApiError> {
+        let mut found = HashMap::with_capacity(ids.len());
+        for chunk in ids.chunks(SQLITE_MAX_VARIABLES) {
+            let mut qb = QueryBuilder::new(
+                "SELECT
+                    s.id,
+                    s.latitude,
+                    s.longitude,
+                    sp.common_name,
+                    sp.scientific_name,
+                    s.count,
+                    s.observed_at,
+                    s.lifer,
+                    s.year_tick,
+                    s.country_tick
+                FROM sightings AS s
+                JOIN species sp ON s.species_id = sp.id
+                WHERE s.id IN (",
+            );
+            let mut separated = qb.separated(", ");
+            for id in chunk {
+                separated.push_bind(*id);
+            }
+            qb.push(")");
+
+            let rows = db::query_with_timeout(qb.build().fetch_all(self.pools.read()))
+                .await
+                .map_err(|e| e.into_api_error("loading tile sightings", "Database error"))?;
+            found.extend(rows.into_iter().map(|row| {
+                let row = RowData {
+                    id: row.get("id"),
+                    latitude: row.get("latitude"),
+                    longitude: row.get("longitude"),
+                    common_name: row.get("common_name"),
+                    scientific_name: row.get("scientific_name"),
+                    count: row.get("count"),
+                    observed_at: row.get("observed_at"),
+                    lifer: row.get("lifer"),
+                    year_tick: row.get("year_tick"),
+                    country_tick: row.get("country_tick"),
+                };
+                (row.id, row)
+            }));
+        }
+
+        // Sightings deleted since they were sampled are skipped
+        Ok(ids.iter().filter_map(|id| found.remove(id)).collect())
     }
 
-    async fn fetch_with_vis_rank(&self, request: &TileRequest) -> Result<Vec<RowData>, ApiError> {
+    /// Counts the matching sightings of `species` in each cell of `grid`
+    /// that `range` covers, looking only within `bbox`. Cells without any
+    /// are left out.
+    async fn fetch_cell_totals(
+        &self,
+        request: &TileRequest,
+        grid: CellGrid,
+        range: &CellRange,
+        bbox: &Bbox,
+        species: &SpeciesSet,
+    ) -> Result<Vec<CellTotals>, ApiError> {
+        let cell_sql = CellSql::new(grid, range);
         let sql = format!(
             r#"
             SELECT
-                s.id,
-                s.latitude,
-                s.longitude,
-                sp.common_name,
-                sp.scientific_name,
-                s.count,
-                s.observed_at,
-                s.lifer,
-                s.year_tick,
-                s.country_tick
+                {CELL_COLUMN_SQL} AS cell_x,
+                {} AS cell_y,
+                COUNT(*) AS sightings,
+                SUM(MAX(COALESCE(s.count, 0), 0)) AS individuals,
+                GROUP_CONCAT(DISTINCT s.species_id) AS species,
+                SUM(COALESCE(s.lifer, 0) > 0) AS lifers,
+                SUM(COALESCE(s.year_tick, 0) > 0) AS year_ticks,
+                SUM(COALESCE(s.country_tick, 0) > 0) AS country_ticks,
+                AVG(s.latitude) AS latitude,
+                AVG(s.longitude) AS longitude,
+                MAX(
+                    ((COALESCE(s.lifer, 0) > 0) * 4
+                        + (COALESCE(s.year_tick, 0) > 0) * 2
+                        + (COALESCE(s.country_tick, 0) > 0)) * {BEST_SIGHTING_SCALE}
+                    - s.id
+                ) AS best
             FROM sightings AS s
             JOIN species sp ON s.species_id = sp.id
             JOIN sightings_geo AS sg ON sg.id = s.id
             WHERE s.upload_id = ?
-              AND s.vis_rank < ?
               AND sg.max_lat >= ? AND sg.min_lat <= ?
               AND sg.max_lon >= ? AND sg.min_lon <= ?
             {}
-            LIMIT ?
+            {}
+            GROUP BY cell_x, cell_y
             "#,
-            request.filter_sql.clause()
+            cell_sql.row,
+            request.filter_sql.clause(),
+            species.clause()
         );
 
-        let mut db_query = sqlx::query(&sql)
+        let mut db_query = cell_sql
+            .bind(sqlx::query(&sql))
             .bind(request.upload_id_bytes())
-            .bind(request.vis_rank_threshold)
-            .bind(request.bbox.lat_min)
-            .bind(request.bbox.lat_max)
-            .bind(request.bbox.lon_min)
-            .bind(request.bbox.lon_max);
+            .bind(bbox.lat_min)
+            .bind(bbox.lat_max)
+            .bind(bbox.lon_min)
+            .bind(bbox.lon_max);
 
         for param in request.filter_sql.params() {
             db_query = db_query.bind(param);
         }
-        db_query = db_query.bind(request.max_points);
 
         let rows = db::query_with_timeout(db_query.fetch_all(self.pools.read()))
             .await
-            .map_err(|e| e.into_api_error("loading tile sightings", "Database error"))?;
+            .map_err(|e| e.into_api_error("counting tile sightings", "Database error"))?;
 
         Ok(rows
             .into_iter()
-            .map(|row| RowData {
-                id: row.get("id"),
-                latitude: row.get("latitude"),
-                longitude: row.get("longitude"),
-                common_name: row.get("common_name"),
-                scientific_name: row.get("scientific_name"),
-                count: row.get("count"),
-                observed_at: row.get("observed_at"),
-                lifer: row.get("lifer"),
-                year_tick: row.get("year_tick"),
-                country_tick: row.get("country_tick"),
+            .filter_map(|row| {
+                let (column, row_index): (i64, i64) = (row.get("cell_x"), row.get("cell_y"));
+                // The index can return sightings just outside the box
+                if !range.contains(column, row_index) {
+                    return None;
+                }
+                let species: Option<String> = row.get("species");
+                let count = |column: &str| u64::try_from(row.get::<i64, _>(column)).unwrap_or(0);
+                Some(CellTotals {
+                    column,
+                    row: row_index,
+                    sightings: count("sightings"),
+                    individuals: count("individuals"),
+                    species: species
+                        .unwrap_or_default()
+                        .split(',')
+                        .filter_map(|id| id.parse::<u32>().ok())
+                        .collect(),
+                    lifers: count("lifers"),
+                    year_ticks: count("year_ticks"),
+                    country_ticks: count("country_ticks"),
+                    latitude: row.get("latitude"),
+                    longitude: row.get("longitude"),
+                    best: row.get("best"),
+                })
             })
             .collect())
     }
+
+    /// Counts every matching sighting near the tile into the cells of a
+    /// `size` by `size` grid over it. Sightings location privacy moves are
+    /// counted in cells at least as wide as they're moved, and each of those
+    /// is shown at its centre, moved the same way, so the counts give away no
+    /// more than the sightings themselves would.
+    async fn fetch_cells(
+        &self,
+        request: &TileRequest,
+        size: u32,
+    ) -> Result<Vec<PlacedCell>, ApiError> {
+        let tile_grid = CellGrid::for_tile(request.tile_pos, size);
+        let mut cells = Vec::new();
+        for (mode, species) in request.species_modes.shown() {
+            if mode == LocationMode::Exact {
+                let range = CellRange::for_tile(request.tile_pos, size);
+                let totals = self
+                    .fetch_cell_totals(request, tile_grid, &range, &request.bbox, &species)
+                    .await?;
+                cells.extend(totals.into_iter().filter_map(|totals| {
+                    let cell = (
+                        u32::try_from(totals.column).ok()?,
+                        u32::try_from(totals.row).ok()?,
+                    );
+                    let point = latlng_to_tile_coords(
+                        LatLng {
+                            lat: totals.latitude,
+                            lng: totals.longitude,
+                        },
+                        request.tile_pos,
+                    );
+                    Some(PlacedCell {
+                        cell,
+                        point,
+                        totals,
+                    })
+                }));
+                continue;
+            }
+
+            let shift_km = mode.max_shift_km();
+            let near = request.bbox.grown(shift_km);
+            let grid = tile_grid.coarsened(shift_km, &near);
+            let range = grid.range(&near);
+            let totals = self
+                .fetch_cell_totals(request, grid, &range, &grid.bbox(&range), &species)
+                .await?;
+            for totals in totals {
+                let (latitude, longitude) = grid.centre(
+                    range.first_column + totals.column,
+                    range.first_row + totals.row,
+                );
+                let Some((latitude, longitude)) =
+                    request.policy.apply_mode(mode, latitude, longitude)
+                else {
+                    continue;
+                };
+                let Some(point) = request.tile_point(latitude, longitude) else {
+                    continue;
+                };
+                cells.push(PlacedCell {
+                    cell: grid_cell(&point, size),
+                    point,
+                    totals,
+                });
+            }
+        }
+        Ok(cells)
+    }
+
+    /// Counts every matching sighting near the tile into the density grid.
+    async fn fetch_density(&self, request: &TileRequest) -> Result<DensityGrid, ApiError> {
+        let mut grid = DensityGrid::new();
+        for PlacedCell { cell, totals, .. } in self.fetch_cells(request, DENSITY_GRID_SIZE).await? {
+            let density = grid.entry(cell).or_default();
+            density.sightings += totals.sightings;
+            density.individuals += totals.individuals;
+            density.species |= totals.species;
+        }
+        Ok(grid)
+    }
+
+    /// Counts every matching sighting near the tile into clusters, then
+    /// loads the most representative sighting of each.
+    async fn fetch_clusters(&self, request: &TileRequest) -> Result<ClusterGrid, ApiError> {
+        let mut grid = ClusterGrid::new();
+        for PlacedCell {
+            cell,
+            point,
+            totals,
+        } in self.fetch_cells(request, CLUSTER_GRID_SIZE).await?
+        {
+            let cluster = grid.entry(cell).or_default();
+            cluster.sightings += totals.sightings;
+            cluster.individuals += totals.individuals;
+            cluster.species |= totals.species;
+            cluster.lifers += totals.lifers;
+            cluster.year_ticks += totals.year_ticks;
+            cluster.country_ticks += totals.country_ticks;
+            cluster.sum_x += point.tile_x * totals.sightings as f64;
+            cluster.sum_y += point.tile_y * totals.sightings as f64;
+            cluster.best_rank = cluster.best_rank.max(Some(totals.best));
+        }
+
+        let ids: Vec<i64> = grid
+            .values()
+            .filter_map(|cluster| cluster.best_rank.map(best_sighting_id))
+            .collect();
+        let mut best: HashMap<i64, RowData> = self
+            .fetch_sightings(&ids)
+            .await?
+            .into_iter()
+            .map(|row| (row.id, row))
+            .collect();
+        for cluster in grid.values_mut() {
+            cluster.best = cluster
+                .best_rank
+                .and_then(|rank| best.remove(&best_sighting_id(rank)));
+        }
+        Ok(grid)
+    }
+}
+
+/// The cell of a `size` by `size` grid over the tile that a point falls in.
+/// Cells line up with those of neighbouring tiles and of the zoom levels
+/// either side, so clusters don't shift as the map is panned.
+fn grid_cell(point: &TileCoords, size: u32) -> (u32, u32) {
+    let cell_size = f64::from(TILE_EXTENT) / f64::from(size);
+    let max_cell = size - 1;
+    (
+        ((point.tile_x / cell_size) as u32).min(max_cell),
+        ((point.tile_y / cell_size) as u32).min(max_cell),
+    )
+}
+
+async fn acquire_encoder_permit() -> Result<OwnedSemaphorePermit, ApiError> {
+    match timeout(
+        Duration::from_millis(TILE_ENCODER_WAIT_TIMEOUT_MS),
+        TILE_ENCODER_GUARD.clone().acquire_owned(),
+    )
+    .await
+    {
+        Ok(Ok(permit)) => Ok(permit),
+        Ok(Err(_)) => Err(ApiError::service_unavailable("Tile encoder unavailable")),
+        Err(_) => Err(ApiError::service_unavailable(
+            "Tile renderer is busy, please retry",
+        )),
+    }
 }
 
 struct TileEncoder;
 
 impl TileEncoder {
-    async fn encode(tile_pos: TileCoordinates, rows: Vec<RowData>) -> Result<Vec<u8>, ApiError> {
-        let _encoder_permit = match timeout(
-            Duration::from_millis(TILE_ENCODER_WAIT_TIMEOUT_MS),
-            TILE_ENCODER_GUARD.clone().acquire_owned(),
-        )
-        .await
-        {
-            Ok(Ok(permit)) => permit,
-            Ok(Err(_)) => {
-                return Err(ApiError::service_unavailable("Tile encoder unavailable"));
-            }
-            Err(_) => {
-                return Err(ApiError::service_unavailable(
-                    "Tile renderer is busy, please retry",
-                ));
-            }
-        };
+    async fn encode(
+        tile_pos: TileCoordinates,
+        rows: Vec<RowData>,
+        total: usize,
+        truncated: bool,
+    ) -> Result<Vec<u8>, ApiError> {
+        let _encoder_permit = acquire_encoder_permit().await?;
 
         tokio::task::spawn_blocking(move || {
             let mut tile = Tile::new(TILE_EXTENT);
@@ -486,6 +1206,24 @@ impl TileEncoder {
                 return Err(ApiError::internal("Tile encoding error"));
             }
 
+            // A single feature saying whether the tile holds every sighting
+            let centre = f64::from(TILE_EXTENT) / 2.0;
+            let info = GeomEncoder::new(GeomType::Point)
+                .point(centre, centre)
+                .and_then(mvt::GeomEncoder::encode)
+                .map_err(|e| {
+                    error!("Failed to encode geometry: {}", e);
+                    ApiError::internal("Tile encoding error")
+                })?;
+            let mut feature = tile.create_layer("tile_info").into_feature(info);
+            feature.add_tag_uint("sightings", u64::try_from(total).unwrap_or(u64::MAX));
+            feature.add_tag_uint("shown", u64::try_from(point_count).unwrap_or(u64::MAX));
+            feature.add_tag_bool("truncated", truncated);
+            if let Err(e) = tile.add_layer(feature.into_layer()) {
+                error!("Failed to add layer to tile: {}", e);
+                return Err(ApiError::internal("Tile encoding error"));
+            }
+
             match tile.to_bytes() {
                 Ok(bytes) => {
                     debug!("Generated tile with {} points", point_count);
@@ -500,16 +1238,155 @@ impl TileEncoder {
         .await
         .map_err(|_| ApiError::internal("Tile encoding task failed"))?
     }
-}
 
-fn zoom_threshold(z: u32) -> (i32, bool) {
-    let threshold = match z {
-        0..=2 => 100,
-        3..=4 => 1000,
-        5..=7 => 5000,
-        _ => MAX_VIS_RANK,
-    };
-    (threshold, threshold >= MAX_VIS_RANK)
+    async fn encode_density(grid: DensityGrid) -> Result<Vec<u8>, ApiError> {
+        let _encoder_permit = acquire_encoder_permit().await?;
+
+        tokio::task::spawn_blocking(move || {
+            let mut tile = Tile::new(TILE_EXTENT);
+            let mut layer = tile.create_layer("density");
+            let cell_size = f64::from(TILE_EXTENT) / f64::from(DENSITY_GRID_SIZE);
+
+            // Each cell is a point at its centre, which suits heatmap layers
+            for ((cell_x, cell_y), totals) in grid {
+                let geom_data = match GeomEncoder::new(GeomType::Point)
+                    .point(
+                        (f64::from(cell_x) + 0.5) * cell_size,
+                        (f64::from(cell_y) + 0.5) * cell_size,
+                    )
+                    .and_then(mvt::GeomEncoder::encode)
+                {
+                    Ok(data) => data,
+                    Err(e) => {
+                        error!("Failed to encode geometry: {}", e);
+                        continue;
+                    }
+                };
+
+                let mut feature = layer.into_feature(geom_data);
+                feature.set_id(u64::from(cell_y * DENSITY_GRID_SIZE + cell_x));
+                feature.add_tag_uint("sightings", totals.sightings);
+                feature.add_tag_uint("species", totals.species.len());
+                feature.add_tag_uint("individuals", totals.individuals);
+                layer = feature.into_layer();
+            }
+
+            if let Err(e) = tile.add_layer(layer) {
+                error!("Failed to add layer to tile: {}", e);
+                return Err(ApiError::internal("Tile encoding error"));
+            }
+            tile.to_bytes().map_err(|e| {
+                error!("Failed to encode tile: {}", e);
+                ApiError::internal("Tile encoding error")
+            })
+        })
+        .await
+        .map_err(|_| ApiError::internal("Tile encoding task failed"))?
+    }
+
+    async fn encode_clusters(grid: ClusterGrid) -> Result<Vec<u8>, ApiError> {
+        let _encoder_permit = acquire_encoder_permit().await?;
+
+        tokio::task::spawn_blocking(move || {
+            let mut tile = Tile::new(TILE_EXTENT);
+            let mut layer = tile.create_layer("clusters");
+
+            for ((cell_x, cell_y), cluster) in grid {
+                let Some(best) = &cluster.best else {
+                    continue;
+                };
+                let (x, y) = cluster.centre();
+                let geom_data = match GeomEncoder::new(GeomType::Point)
+                    .point(x, y)
+                    .and_then(mvt::GeomEncoder::encode)
+                {
+                    Ok(data) => data,
+                    Err(e) => {
+                        error!("Failed to encode geometry: {}", e);
+                        continue;
+                    }
+                };
+
+                let mut feature = layer.into_feature(geom_data);
+                feature.set_id(u64::from(cell_y * CLUSTER_GRID_SIZE + cell_x));
+                feature.add_tag_uint("sightings", cluster.sightings);
+                feature.add_tag_uint("species", cluster.species.len());
+                feature.add_tag_uint("individuals", cluster.individuals);
+                feature.add_tag_uint("lifers", cluster.lifers);
+                feature.add_tag_uint("year_ticks", cluster.year_ticks);
+                feature.add_tag_uint("country_ticks", cluster.country_ticks);
+                // The representative sighting, tagged as in the sightings layer
+                feature.add_tag_uint("sighting_id", u64::try_from(best.id).unwrap_or(0));
+                feature.add_tag_string("name", &best.common_name);
+                if let Some(scientific_name) = &best.scientific_name {
+                    feature.add_tag_string("scientific_name", scientific_name);
+                }
+                feature.add_tag_uint("count", u64::try_from(best.count.max(0)).unwrap_or(0));
+                feature.add_tag_string("observed_at", &best.observed_at);
+                feature.add_tag_uint("lifer", u64::try_from(best.lifer.max(0)).unwrap_or(0));
+                feature.add_tag_uint(
+                    "year_tick",
+                    u64::try_from(best.year_tick.max(0)).unwrap_or(0),
+                );
+                feature.add_tag_uint(
+                    "country_tick",
+                    u64::try_from(best.country_tick.max(0)).unwrap_or(0),
+                );
+                layer = feature.into_layer();
+            }
+
+            if let Err(e) = tile.add_layer(layer) {
+                error!("Failed to add layer to tile: {}", e);
+                return Err(ApiError::internal("Tile encoding error"));
+            }
+            tile.to_bytes().map_err(|e| {
+                error!("Failed to encode tile: {}", e);
+                ApiError::internal("Tile encoding error")
+            })
+        })
+        .await
+        .map_err(|_| ApiError::internal("Tile encoding task failed"))?
+    }
+
+    /// Draws the sightings the way the map's circle layer does, with lifers
+    /// on top, then country ticks, then year ticks.
+    async fn encode_png(
+        tile_pos: TileCoordinates,
+        rows: Vec<RowData>,
+    ) -> Result<Vec<u8>, ApiError> {
+        let _encoder_permit = acquire_encoder_permit().await?;
+
+        tokio::task::spawn_blocking(move || {
+            let mut markers: Vec<(SightingKind, f64, f64)> = rows
+                .iter()
+                .map(|row| {
+                    let kind =
+                        SightingKind::new(row.lifer > 0, row.year_tick > 0, row.country_tick > 0);
+                    let coords = latlng_to_tile_coords(
+                        LatLng {
+                            lat: row.latitude,
+                            lng: row.longitude,
+                        },
+                        tile_pos,
+                    );
+                    (kind, coords.tile_x, coords.tile_y)
+                })
+                .collect();
+            markers.sort_by_key(|(kind, _, _)| *kind);
+
+            let mut canvas = Canvas::new(RASTER_TILE_SIZE, RASTER_TILE_SIZE);
+            let scale = f64::from(RASTER_TILE_SIZE) / f64::from(TILE_EXTENT);
+            for (kind, x, y) in markers {
+                canvas.draw_sighting(x * scale, y * scale, kind, 1.0);
+            }
+            canvas.to_png().map_err(|e| {
+                error!("Failed to encode PNG tile: {}", e);
+                ApiError::internal("Tile encoding error")
+            })
+        })
+        .await
+        .map_err(|_| ApiError::internal("Tile encoding task failed"))?
+    }
 }
 
 fn max_points_for_zoom(z: u32) -> i64 {
@@ -532,12 +1409,33 @@ pub async fn invalidate_upload_cache(upload_id: &str) {
     }
 }
 
+fn tile_response(request: &TileRequest, data: Vec<u8>) -> Result<Response, ApiError> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, request.layer.content_type())
+        .header(header::CACHE_CONTROL, request.cache_control())
+        .header(header::VARY, header::AUTHORIZATION.as_str())
+        .header("x-upload-version", request.data_version().to_string())
+        .body(axum::body::Body::from(data))
+        .map_err(|err| {
+            error!("Failed to build tile response: {}", err);
+            ApiError::internal("Failed to build response")
+        })
+}
+
 pub async fn get_tile(
     State(pools): State<DbPools>,
     Path(path): Path<TilePath>,
     Query(query): Query<TileQuery>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, ApiError> {
-    let request = TileRequest::build(&pools, path, query).await?;
+    // The same route serves PNG tiles, for clients that can't draw vector tiles
+    let layer = if path.y.ends_with(".png") {
+        TileLayer::Raster
+    } else {
+        TileLayer::Points
+    };
+    let request = TileRequest::build(&pools, path, query, &headers, layer).await?;
     let tile_pos = request.tile_pos();
     let bbox = request.bbox();
 
@@ -548,38 +1446,73 @@ pub async fn get_tile(
 
     if let Some(cached_data) = TILE_CACHE.get(request.cache_key()).await {
         debug!("Tile cache hit: {}", request.cache_key());
-        let response = Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "application/x-protobuf")
-            .header(header::CACHE_CONTROL, "public, max-age=3600")
-            .header("x-upload-version", request.data_version().to_string())
-            .body(axum::body::Body::from(cached_data))
-            .map_err(|err| {
-                error!("Failed to build cached tile response: {}", err);
-                ApiError::internal("Failed to build response")
-            })?;
-        return Ok(response);
+        return tile_response(&request, cached_data);
     }
 
     let fetcher = TileDataFetcher::new(&pools);
-    let rows = fetcher.fetch_rows(&request).await?;
-    let data = TileEncoder::encode(request.tile_pos(), rows).await?;
+    let sample = fetcher.fetch_sample(&request).await?;
+    let rows = sample.rows;
+    let data = match layer {
+        TileLayer::Raster => TileEncoder::encode_png(request.tile_pos(), rows).await?,
+        _ => TileEncoder::encode(request.tile_pos(), rows, sample.total, sample.truncated).await?,
+    };
 
     TILE_CACHE
         .insert(request.cache_key().to_string(), data.clone())
         .await;
     debug!("Tile cached: {}", request.cache_key());
 
-    let response = Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "application/x-protobuf")
-        .header(header::CACHE_CONTROL, "public, max-age=3600")
-        .header("x-upload-version", request.data_version().to_string())
-        .body(axum::body::Body::from(data))
-        .map_err(|err| {
-            error!("Failed to build tile response: {}", err);
-            ApiError::internal("Failed to build response")
-        })?;
+    tile_response(&request, data)
+}
+
+/// Sighting density over a grid of cells in each tile, counted over every
+/// sighting matching the filter rather than the sample point tiles show.
+pub async fn get_density_tile(
+    State(pools): State<DbPools>,
+    Path(path): Path<TilePath>,
+    Query(query): Query<TileQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let request = TileRequest::build(&pools, path, query, &headers, TileLayer::Density).await?;
+
+    if let Some(cached_data) = TILE_CACHE.get(request.cache_key()).await {
+        debug!("Density tile cache hit: {}", request.cache_key());
+        return tile_response(&request, cached_data);
+    }
+
+    let cells = TileDataFetcher::new(&pools).fetch_density(&request).await?;
+    let data = TileEncoder::encode_density(cells).await?;
+
+    TILE_CACHE
+        .insert(request.cache_key().to_string(), data.clone())
+        .await;
+
+    tile_response(&request, data)
+}
+
+/// Sightings grouped into clusters on a grid over each tile. Every sighting
+/// matching the filter is counted, so cluster sizes are exact at any zoom.
+pub async fn get_cluster_tile(
+    State(pools): State<DbPools>,
+    Path(path): Path<TilePath>,
+    Query(query): Query<TileQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let request = TileRequest::build(&pools, path, query, &headers, TileLayer::Clusters).await?;
+
+    if let Some(cached_data) = TILE_CACHE.get(request.cache_key()).await {
+        debug!("Cluster tile cache hit: {}", request.cache_key());
+        return tile_response(&request, cached_data);
+    }
+
+    let clusters = TileDataFetcher::new(&pools)
+        .fetch_clusters(&request)
+        .await?;
+    let data = TileEncoder::encode_clusters(clusters).await?;
+
+    TILE_CACHE
+        .insert(request.cache_key().to_string(), data.clone())
+        .await;
 
-    Ok(response)
+    tile_response(&request, data)
 }
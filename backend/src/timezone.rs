@@ -0,0 +1,455 @@
+//! Local calendar dates for sightings, derived from their coordinates.
+//!
+//! Birda exports timestamps in UTC, so a dawn sighting in Australia would
+//! otherwise land on the previous day and a New Year's Eve owl would count
+//! towards the wrong year. Each sighting's IANA time zone comes from the time
+//! zone boundary polygons published by timezone-boundary-builder (or tz_world
+//! converted to GeoJSON), loaded from REDGROUSE_TZ_BOUNDARIES_PATH. Offsets
+//! then come from the tz database (via `chrono-tz`), so daylight saving
+//! follows the rules in force on the day, including past ones.
+//!
+//! Without boundary data, or for a point outside every polygon, the zone is
+//! looked up in `data/timezones.csv` by the region or country code geocoding
+//! already found. That table only splits multi-zone regions by longitude, so
+//! it can be an hour out near zone lines. Coordinates outside any listed
+//! country fall back to nautical time.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Deserialize;
+use sqlx::{FromRow, SqlitePool};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::bitmaps::compute_and_store_bitmaps;
+use crate::config;
+use crate::db::{self, DbPools, DbQueryError};
+use crate::pipeline::local_day;
+use crate::polygons::{FeatureCollection, PolygonIndex};
+use crate::ticks::recompute_tick_flags;
+use crate::tiles::invalidate_upload_cache;
+
+const TIMEZONES_CSV: &str = include_str!("../data/timezones.csv");
+const TIMEZONES_HEADER: &str = "region,from_lon,tz";
+
+const BACKFILL_BATCH_SIZE: i64 = 1000;
+
+static TIMEZONES: Lazy<TimeZones> = Lazy::new(|| {
+    TimeZones::parse(TIMEZONES_CSV).unwrap_or_else(|err| {
+        error!("Failed to load bundled time zones: {}", err);
+        panic!("Bundled time zones are invalid. Application cannot start without them.");
+    })
+});
+
+static BOUNDARIES: OnceCell<Option<Boundaries>> = OnceCell::new();
+
+/// Loads the time zone boundaries from REDGROUSE_TZ_BOUNDARIES_PATH, if it's
+/// set. Called once at startup, so a bad file stops the server before it
+/// serves anything. Until then, every lookup falls back to the bundled table.
+pub fn load_boundaries() -> anyhow::Result<()> {
+    let boundaries = match config::tz_boundaries_path() {
+        Some(path) => {
+            let boundaries = Boundaries::load(&path).map_err(|err| {
+                anyhow::anyhow!(
+                    "Failed to load time zone boundaries from {}: {err}",
+                    path.display()
+                )
+            })?;
+            info!(
+                "Loaded {} time zone polygons from {}",
+                boundaries.index.len(),
+                path.display()
+            );
+            Some(boundaries)
+        }
+        None => None,
+    };
+    if BOUNDARIES.set(boundaries).is_err() {
+        anyhow::bail!("Time zone boundaries are already loaded");
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Zone {
+    /// Westernmost longitude the row applies from
+    from_lon: f64,
+    tz: Tz,
+}
+
+impl Zone {
+    /// Whole hours from UTC by longitude, as used at sea.
+    fn nautical(longitude: f64) -> Self {
+        let hours = (longitude / 15.0).round() as i32;
+        // The Etc zones count the other way: Etc/GMT-5 is UTC+5
+        let tz = format!("Etc/GMT{:+}", -hours).parse().unwrap_or(Tz::UTC);
+        Self {
+            from_lon: -180.0,
+            tz,
+        }
+    }
+}
+
+struct TimeZones {
+    /// Zones per region or country code, ordered by `from_lon`
+    by_region: HashMap<String, Vec<Zone>>,
+}
+
+impl TimeZones {
+    fn global() -> &'static Self {
+        &TIMEZONES
+    }
+
+    fn zone(&self, region_code: Option<&str>, country_code: &str, longitude: f64) -> Zone {
+        region_code
+            .and_then(|region| self.by_region.get(region))
+            .or_else(|| self.by_region.get(country_code))
+            .map_or_else(
+                || Zone::nautical(longitude),
+                |zones| {
+                    // Before the first split, the first row still applies
+                    zones
+                        .iter()
+                        .rev()
+                        .find(|zone| zone.from_lon <= longitude)
+                        .copied()
+                        .unwrap_or(zones[0])
+                },
+            )
+    }
+
+    fn parse(data: &str) -> Result<Self, String> {
+        let mut lines = data.lines().enumerate();
+        match lines.next() {
+            Some((_, first)) if first.trim() == TIMEZONES_HEADER => {}
+            _ => return Err(format!("expected header {TIMEZONES_HEADER:?}")),
+        }
+
+        let mut by_region: HashMap<String, Vec<Zone>> = HashMap::new();
+        for (idx, line) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+            let line_no = idx + 1;
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [region, from_lon, tz] = fields[..] else {
+                return Err(format!(
+                    "line {line_no}: expected 3 columns, found {}",
+                    fields.len()
+                ));
+            };
+
+            let from_lon = if from_lon.is_empty() {
+                -180.0
+            } else {
+                from_lon
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|lon| (-180.0..=180.0).contains(lon))
+                    .ok_or_else(|| format!("line {line_no}: invalid longitude {from_lon:?}"))?
+            };
+            let zone = Zone {
+                from_lon,
+                tz: tz
+                    .parse()
+                    .map_err(|_| format!("line {line_no}: unknown time zone {tz:?}"))?,
+            };
+
+            let zones = by_region.entry(region.to_string()).or_default();
+            if zones
+                .iter()
+                .any(|existing| existing.from_lon == zone.from_lon)
+            {
+                return Err(format!(
+                    "line {line_no}: {region} already has a zone from longitude {from_lon}"
+                ));
+            }
+            zones.push(zone);
+        }
+
+        for zones in by_region.values_mut() {
+            zones.sort_by(|a, b| a.from_lon.total_cmp(&b.from_lon));
+        }
+        Ok(Self { by_region })
+    }
+}
+
+#[derive(Deserialize)]
+struct BoundaryProperties {
+    // timezone-boundary-builder uses tzid, tz_world TZID
+    #[serde(alias = "TZID")]
+    tzid: String,
+}
+
+/// Time zone polygons from timezone-boundary-builder or tz_world.
+struct Boundaries {
+    index: PolygonIndex<Tz>,
+}
+
+impl Boundaries {
+    fn global() -> Option<&'static Self> {
+        BOUNDARIES.get().and_then(Option::as_ref)
+    }
+
+    fn load(path: &std::path::Path) -> Result<Self, String> {
+        let collection = FeatureCollection::<BoundaryProperties>::load(path)?;
+        let features = collection
+            .features
+            .into_iter()
+            .map(|feature| {
+                let tzid = feature.properties.tzid;
+                let tz: Tz = tzid
+                    .parse()
+                    .map_err(|_| format!("unknown time zone {tzid:?}"))?;
+                Ok((tz, feature.geometry))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self {
+            index: PolygonIndex::new(features),
+        })
+    }
+
+    fn zone(&self, lat: f64, lon: f64) -> Option<Tz> {
+        self.index.find(lat, lon).copied()
+    }
+}
+
+/// The calendar date a sighting was made on, where it was made.
+pub(crate) fn local_date(
+    observed_at: &str,
+    country_code: &str,
+    region_code: Option<&str>,
+    latitude: f64,
+    longitude: f64,
+) -> Option<NaiveDate> {
+    local_datetime(observed_at, country_code, region_code, latitude, longitude)
+        .map(|(date, _)| date)
+}
+
+/// The local date and, when the timestamp has one, time of day a sighting was
+/// made.
+///
+/// Timestamps carrying a UTC offset are moved into the local time zone.
+/// Timestamps without one (eBird's checklist date and time) are already
+/// local, so they're taken as written.
+pub(crate) fn local_datetime(
+    observed_at: &str,
+    country_code: &str,
+    region_code: Option<&str>,
+    latitude: f64,
+    longitude: f64,
+) -> Option<(NaiveDate, Option<NaiveTime>)> {
+    let Ok(timestamp) = DateTime::parse_from_rfc3339(observed_at) else {
+        if let Ok(local) = NaiveDateTime::parse_from_str(observed_at, "%Y-%m-%dT%H:%M:%S%.f") {
+            return Some((local.date(), Some(local.time())));
+        }
+        return crate::pipeline::parse_observed_date(observed_at).map(|date| (date, None));
+    };
+
+    let tz = Boundaries::global()
+        .and_then(|boundaries| boundaries.zone(latitude, longitude))
+        .unwrap_or_else(|| {
+            TimeZones::global()
+                .zone(region_code, country_code, longitude)
+                .tz
+        });
+    let local = tz.from_utc_datetime(&timestamp.naive_utc()).naive_local();
+    Some((local.date(), Some(local.time())))
+}
+
+#[derive(FromRow)]
+struct PendingSighting {
+    id: i64,
+    upload_id: Vec<u8>,
+    observed_at: String,
+    country_code: String,
+    region_code: Option<String>,
+    latitude: f64,
+    longitude: f64,
+    year: i64,
+}
+
+/// Fills in `local_date` for sightings stored before it existed, moving the
+/// year of any that were filed under the wrong one in UTC. Runs before the
+/// server accepts requests, since date queries rely on the column. Returns
+/// the uploads whose years moved, which need their ticks recomputed.
+pub async fn backfill_local_dates(pool: &SqlitePool) -> Result<HashSet<Vec<u8>>, sqlx::Error> {
+    let mut moved_uploads = HashSet::new();
+    let mut filled = 0;
+
+    loop {
+        let pending = sqlx::query_as::<_, PendingSighting>(
+            "SELECT id, upload_id, observed_at, country_code, region_code, latitude, longitude, year
+             FROM sightings WHERE local_date IS NULL LIMIT ?",
+        )
+        .bind(BACKFILL_BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+        if pending.is_empty() {
+            break;
+        }
+
+        let mut tx = pool.begin().await?;
+        for sighting in &pending {
+            let (date, year) = local_day(
+                &sighting.observed_at,
+                &sighting.country_code,
+                sighting.region_code.as_deref(),
+                sighting.latitude,
+                sighting.longitude,
+            );
+            sqlx::query("UPDATE sightings SET local_date = ?, year = ? WHERE id = ?")
+                .bind(date.as_str())
+                .bind(year)
+                .bind(sighting.id)
+                .execute(&mut *tx)
+                .await?;
+
+            if i64::from(year) != sighting.year {
+                moved_uploads.insert(sighting.upload_id.clone());
+            }
+        }
+        tx.commit().await?;
+        filled += pending.len();
+    }
+
+    if filled > 0 {
+        info!("Filled in local dates for {} sightings", filled);
+    }
+    Ok(moved_uploads)
+}
+
+/// Recomputes ticks for uploads whose sightings moved to another year.
+pub async fn refresh_moved_years(
+    pools: &DbPools,
+    upload_ids: HashSet<Vec<u8>>,
+) -> Result<(), DbQueryError> {
+    for upload_id_blob in upload_ids {
+        let mut tx = pools.write().begin().await?;
+        recompute_tick_flags(&mut tx, &upload_id_blob, None).await?;
+        db::query_with_timeout(
+            sqlx::query("UPDATE uploads SET data_version = data_version + 1 WHERE id = ?")
+                .bind(&upload_id_blob)
+                .execute(&mut *tx),
+        )
+        .await?;
+        tx.commit().await?;
+
+        if let Err(e) = compute_and_store_bitmaps(pools.write(), &upload_id_blob).await {
+            error!("Failed to compute tick bitmaps: {}", e.body.error);
+        }
+        if let Ok(upload_uuid) = Uuid::from_slice(&upload_id_blob) {
+            invalidate_upload_cache(&upload_uuid.to_string()).await;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(observed_at: &str, region: &str, longitude: f64) -> String {
+        let country = region.split('-').next().unwrap_or(region);
+        let (date, time) = local_datetime(observed_at, country, Some(region), 0.0, longitude)
+            .expect("valid timestamp");
+        format!("{date} {}", time.expect("timestamp has a time"))
+    }
+
+    #[test]
+    fn bundled_zones_load() {
+        assert!(!TimeZones::global().by_region.is_empty());
+    }
+
+    #[test]
+    fn eu_summer_time_starts_at_one_utc() {
+        assert_eq!(
+            local("2024-03-31T00:59:00Z", "DE", 13.4),
+            "2024-03-31 01:59:00"
+        );
+        assert_eq!(
+            local("2024-03-31T01:00:00Z", "DE", 13.4),
+            "2024-03-31 03:00:00"
+        );
+    }
+
+    #[test]
+    fn eu_summer_time_ends_at_one_utc() {
+        assert_eq!(
+            local("2024-10-27T00:59:00Z", "GB", -0.1),
+            "2024-10-27 01:59:00"
+        );
+        assert_eq!(
+            local("2024-10-27T01:00:00Z", "GB", -0.1),
+            "2024-10-27 01:00:00"
+        );
+    }
+
+    #[test]
+    fn us_daylight_time_follows_the_2007_change() {
+        // From 2007 clocks go forward on the second Sunday of March
+        assert_eq!(
+            local("2007-03-11T07:00:00Z", "US-NY", -74.0),
+            "2007-03-11 03:00:00"
+        );
+        // Before, it was the first Sunday of April, so a late evening in
+        // March 2006 was still standard time and still the previous day
+        assert_eq!(
+            local("2006-03-20T04:30:00Z", "US-NY", -74.0),
+            "2006-03-19 23:30:00"
+        );
+        assert_eq!(
+            local("2006-04-02T07:00:00Z", "US-NY", -74.0),
+            "2006-04-02 03:00:00"
+        );
+    }
+
+    #[test]
+    fn southern_summer_time_spans_new_year() {
+        assert_eq!(
+            local("2023-12-31T13:30:00Z", "AU-NSW", 151.2),
+            "2024-01-01 00:30:00"
+        );
+        assert_eq!(
+            local("2023-12-31T13:30:00Z", "AU-QLD", 153.0),
+            "2023-12-31 23:30:00"
+        );
+        assert_eq!(
+            local("2024-04-06T14:00:00Z", "NZ", 174.8),
+            "2024-04-07 02:00:00"
+        );
+        assert_eq!(
+            local("2024-04-06T13:59:00Z", "NZ", 174.8),
+            "2024-04-07 02:59:00"
+        );
+    }
+
+    #[test]
+    fn regions_split_by_longitude() {
+        // Louisville is on Eastern time, Paducah on Central
+        assert_eq!(
+            local("2024-01-01T04:30:00Z", "US-KY", -85.8),
+            "2023-12-31 23:30:00"
+        );
+        assert_eq!(
+            local("2024-01-01T04:30:00Z", "US-KY", -88.6),
+            "2023-12-31 22:30:00"
+        );
+    }
+
+    #[test]
+    fn unknown_places_use_nautical_time() {
+        let (date, time) = local_datetime("2024-01-01T02:00:00Z", "XX", None, 0.0, -40.0).unwrap();
+        assert_eq!(format!("{date} {}", time.unwrap()), "2023-12-31 23:00:00");
+        let (date, _) = local_datetime("2023-12-31T23:00:00Z", "XX", None, 0.0, 170.0).unwrap();
+        assert_eq!(date.to_string(), "2024-01-01");
+    }
+
+    #[test]
+    fn local_timestamps_are_taken_as_written() {
+        assert_eq!(
+            local("2024-01-01T00:15:00", "AU-NSW", 151.2),
+            "2024-01-01 00:15:00"
+        );
+    }
+}
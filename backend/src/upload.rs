@@ -1,38 +1,63 @@
 use crate::db::DbPools;
 use axum::body::Bytes;
-use axum::extract::{multipart::Field, Extension, Multipart, Path, State};
+use axum::extract::{multipart::Field, Extension, Multipart, Path, Query, State};
 use axum::http::header;
 use axum::response::IntoResponse;
 use axum::Json;
 use csv_async::AsyncReaderBuilder;
 use futures::{Stream, StreamExt, TryStreamExt};
 use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::io;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Instant;
 use subtle::ConstantTimeEq;
+use tokio::sync::Mutex;
 use tokio_util::io::StreamReader;
 use tracing::{error, info};
 use uuid::Uuid;
 
+use crate::backup::{self, BackupArchive};
+use crate::collections::{collections_containing, spawn_collection_rebuilds};
+use crate::config;
 use crate::db::{self, DbQueryError};
+use crate::edit_tokens::{issue_edit_token, normalise_name, MAX_EDIT_TOKENS_PER_UPLOAD};
 use crate::error::ApiError;
+use crate::import_report::{store_import_issues, ImportIssues};
 use crate::limits::{UploadLimitError, UploadUsageTracker};
-use crate::pipeline::{CsvParser, DbSink, Geocoder, ParsedSighting, BATCH_SIZE};
+use crate::location_privacy::{LocationMode, MAX_SPECIES_RULES};
+use crate::pipeline::{
+    resolve_species_id, sighting_fingerprint, vis_rank_for, DbSink, Geocoder, ParsedSighting,
+    ProcessedSighting, BATCH_SIZE,
+};
 use crate::proto::{pb, Proto};
+use crate::shares::is_share_token;
 use crate::sightings::invalidate_name_index_cache;
+use crate::sources::{self, DateFormat, RowError, SkipReason, SkippedRow};
+use crate::ticks::recompute_tick_flags;
 use crate::tiles::invalidate_upload_cache;
+use crate::view_password::is_password_hash;
 use crate::zip_extract;
 use serde::Deserialize;
-use sqlx::Row;
+use sqlx::{FromRow, QueryBuilder, Row, Sqlite, Transaction};
 
 pub const MAX_UPLOAD_BYTES: usize = 50 * 1024 * 1024;
 pub const MAX_UPLOAD_BODY_BYTES: usize = MAX_UPLOAD_BYTES + (2 * 1024 * 1024); // allow multipart overhead
 const UPLOAD_LIMIT_MB: usize = MAX_UPLOAD_BYTES / (1024 * 1024);
 const MAX_DISPLAY_NAME_LENGTH: usize = 128;
-const INITIAL_DATA_VERSION: i64 = 1;
+pub(crate) const INITIAL_DATA_VERSION: i64 = 1;
+// Rows held back waiting for a date to settle the day/month order, so a file
+// of only ambiguous dates isn't buffered whole
+const MAX_HELD_ROWS: usize = 10_000;
+const APPEND_CLEANUP_BATCH_SIZE: usize = 999 - 1;
+const RESTORE_SIGHTING_BATCH_SIZE: usize = 999 / 12;
+const RESTORE_ISSUE_BATCH_SIZE: usize = 999 / 5;
+const RESTORE_LOCATION_RULE_BATCH_SIZE: usize = 999 / 4;
+// Format of last_accessed_at and expires_at
+pub(crate) const RETENTION_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
 
 fn row_count_value(total_rows: usize) -> i64 {
     i64::try_from(total_rows).unwrap_or(i64::MAX)
@@ -43,11 +68,142 @@ struct UploadIngestor<'a> {
     writer_tracker: &'a UploadUsageTracker,
 }
 
+/// What to do with a row whose `sighting_uuid` already appeared earlier in
+/// the same file.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// Keep the first row and ignore the rest.
+    #[default]
+    Skip,
+    /// Keep the values from the last row.
+    LastWins,
+    /// Reject the whole file.
+    Error,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    duplicates: DuplicatePolicy,
+    // Falls back to the format the upload was created or last replaced with
+    date_format: Option<DateFormat>,
+}
+
+struct ImportOptions<'a> {
+    duplicates: DuplicatePolicy,
+    date_format: DateFormat,
+    // Only set when appending
+    stored: Option<&'a StoredSightings>,
+    written_ids: Option<&'a Arc<Mutex<Vec<i64>>>>,
+}
+
+struct IngestSummary {
+    total_rows: usize,
+    format: &'static str,
+    ignored_columns: Vec<&'static str>,
+    issues: ImportIssues,
+    duplicate_rows: usize,
+    unchanged_rows: usize,
+    // Rows that overwrite an already written sighting with the same
+    // sighting_uuid: changed rows when appending, and later duplicates under
+    // DuplicatePolicy::LastWins.
+    updates: HashMap<Uuid, ParsedSighting>,
+    species_ids: HashSet<i64>,
+}
+
+/// Fingerprints of the sightings already in an upload, keyed by
+/// `sighting_uuid`, so that append only writes rows that are new or changed.
+struct StoredSightings {
+    fingerprints: HashMap<Uuid, u64>,
+    row_count: usize,
+}
+
+#[derive(FromRow)]
+struct StoredSightingRow {
+    sighting_uuid: Vec<u8>,
+    common_name: String,
+    scientific_name: String,
+    count: Option<i64>,
+    latitude: f64,
+    longitude: f64,
+    observed_at: String,
+}
+
+impl StoredSightings {
+    async fn load(pool: &sqlx::SqlitePool, upload_id_blob: &[u8]) -> Result<Self, DbQueryError> {
+        let rows = db::query_with_timeout(
+            sqlx::query_as::<_, StoredSightingRow>(
+                "SELECT s.sighting_uuid, sp.common_name, sp.scientific_name, s.count, s.latitude, s.longitude, s.observed_at
+                FROM sightings s
+                JOIN species sp ON sp.id = s.species_id
+                WHERE s.upload_id = ?",
+            )
+            .bind(upload_id_blob)
+            .fetch_all(pool),
+        )
+        .await?;
+
+        let row_count = rows.len();
+        let fingerprints = rows
+            .into_iter()
+            .filter_map(|row| {
+                let sighting_uuid = Uuid::from_slice(&row.sighting_uuid).ok()?;
+                let fingerprint = sighting_fingerprint(
+                    &row.common_name,
+                    &row.scientific_name,
+                    row.count.unwrap_or(1),
+                    row.latitude,
+                    row.longitude,
+                    &row.observed_at,
+                );
+                Some((sighting_uuid, fingerprint))
+            })
+            .collect();
+
+        Ok(Self {
+            fingerprints,
+            row_count,
+        })
+    }
+}
+
 struct CreateOutcome {
     upload_id: String,
     filename: String,
     total_rows: usize,
+    format: &'static str,
+    ignored_columns: Vec<&'static str>,
+    skipped_rows: usize,
+    skip_summary: Vec<pb::ImportIssueCount>,
+    duplicate_rows: usize,
+    edit_token: String,
+    data_version: i64,
+}
+
+struct AppendOutcome {
+    upload_id: String,
+    row_count: i64,
+    format: &'static str,
+    ignored_columns: Vec<&'static str>,
+    added_rows: usize,
+    updated_rows: usize,
+    unchanged_rows: usize,
+    skipped_rows: usize,
+    skip_summary: Vec<pb::ImportIssueCount>,
+    duplicate_rows: usize,
+    data_version: i64,
+}
+
+struct RestoreOutcome {
+    upload_id: String,
     edit_token: String,
+    named_edit_tokens: Vec<pb::EditToken>,
+    filename: String,
+    title: String,
+    row_count: i64,
+    format: Option<String>,
+    skipped_rows: i64,
     data_version: i64,
 }
 
@@ -55,6 +211,11 @@ struct ReplaceOutcome {
     upload_id: String,
     filename: String,
     total_rows: usize,
+    format: &'static str,
+    ignored_columns: Vec<&'static str>,
+    skipped_rows: usize,
+    skip_summary: Vec<pb::ImportIssueCount>,
+    duplicate_rows: usize,
     data_version: i64,
 }
 
@@ -66,7 +227,12 @@ impl<'a> UploadIngestor<'a> {
         }
     }
 
-    async fn create(&self, field: Field<'_>, filename: String) -> Result<CreateOutcome, ApiError> {
+    async fn create(
+        &self,
+        field: Field<'_>,
+        filename: String,
+        query: ImportQuery,
+    ) -> Result<CreateOutcome, ApiError> {
         let upload_uuid = Uuid::new_v4();
         let upload_id = upload_uuid.to_string();
         let upload_id_blob = upload_uuid.as_bytes();
@@ -75,33 +241,51 @@ impl<'a> UploadIngestor<'a> {
 
         db::query_with_timeout(
             sqlx::query(
-                "INSERT INTO uploads (id, filename, edit_token_hash, data_version) VALUES (?, ?, ?, ?)",
+                "INSERT INTO uploads (id, filename, edit_token_hash, data_version, date_format) VALUES (?, ?, ?, ?, ?)",
             )
             .bind(&upload_id_blob[..])
             .bind(&filename)
             .bind(&edit_token_hash)
             .bind(INITIAL_DATA_VERSION)
+            .bind(query.date_format.map(DateFormat::as_str))
             .execute(self.pools.write()),
         )
         .await
         .map_err(|e| e.into_api_error("creating upload record", "Database error"))?;
 
-        let (total_rows, actual_filename) =
-            match ingest_field(field, self.pools.write(), &upload_id, self.writer_tracker).await {
-                Ok(result) => result,
-                Err(err) => {
-                    if let Err(db_err) = db::query_with_timeout(
-                        sqlx::query("DELETE FROM uploads WHERE id = ?")
-                            .bind(&upload_id_blob[..])
-                            .execute(self.pools.write()),
-                    )
-                    .await
-                    {
-                        db_err.log("deleting failed upload record");
-                    }
-                    return Err(err);
+        let options = ImportOptions {
+            duplicates: query.duplicates,
+            date_format: query.date_format.unwrap_or_default(),
+            stored: None,
+            written_ids: None,
+        };
+        let (summary, actual_filename) = match ingest_field(
+            field,
+            self.pools.write(),
+            &upload_id,
+            self.writer_tracker,
+            &options,
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(err) => {
+                if let Err(db_err) = db::query_with_timeout(
+                    sqlx::query("DELETE FROM uploads WHERE id = ?")
+                        .bind(&upload_id_blob[..])
+                        .execute(self.pools.write()),
+                )
+                .await
+                {
+                    db_err.log("deleting failed upload record");
                 }
-            };
+                return Err(err);
+            }
+        };
+        let total_rows = summary.total_rows;
+        let updates = Geocoder::new()
+            .geocode_batch(summary.updates.into_values().collect())
+            .await?;
 
         if actual_filename != filename {
             if let Err(e) = db::query_with_timeout(
@@ -122,15 +306,28 @@ impl<'a> UploadIngestor<'a> {
                 e.into_api_error("starting upload metadata transaction", "Database error")
             })?;
 
+        apply_updates(&mut tx, &upload_id_blob[..], &updates).await?;
+        recompute_tick_flags(&mut tx, &upload_id_blob[..], None)
+            .await
+            .map_err(|e| e.into_api_error("recomputing ticks", "Database error"))?;
+
         db::query_with_timeout(
-            sqlx::query("UPDATE uploads SET row_count = ? WHERE id = ?")
-                .bind(row_count_value(total_rows))
-                .bind(&upload_id_blob[..])
-                .execute(&mut *tx),
+            sqlx::query(
+                "UPDATE uploads SET row_count = ?, format = ?, skipped_rows = ? WHERE id = ?",
+            )
+            .bind(row_count_value(total_rows))
+            .bind(summary.format)
+            .bind(row_count_value(summary.issues.skipped_rows()))
+            .bind(&upload_id_blob[..])
+            .execute(&mut *tx),
         )
         .await
         .map_err(|e| e.into_api_error("updating upload row_count", "Database error"))?;
 
+        store_import_issues(&mut tx, &upload_id_blob[..], &summary.issues, false)
+            .await
+            .map_err(|e| e.into_api_error("storing import issues", "Database error"))?;
+
         compute_grid_cell_visibility_tx(&mut tx, &upload_id_blob[..])
             .await
             .map_err(|e| e.into_api_error("computing grid cell visibility", "Database error"))?;
@@ -154,6 +351,11 @@ impl<'a> UploadIngestor<'a> {
             upload_id,
             filename,
             total_rows,
+            format: summary.format,
+            ignored_columns: summary.ignored_columns,
+            skipped_rows: summary.issues.skipped_rows(),
+            skip_summary: summary.issues.summary(),
+            duplicate_rows: summary.duplicate_rows,
             edit_token,
             data_version: INITIAL_DATA_VERSION,
         })
@@ -164,9 +366,12 @@ impl<'a> UploadIngestor<'a> {
         upload_uuid: Uuid,
         field: Field<'_>,
         filename: String,
+        query: ImportQuery,
     ) -> Result<ReplaceOutcome, ApiError> {
         let upload_id = upload_uuid.to_string();
         let upload_id_blob = upload_uuid.as_bytes();
+        let date_format =
+            resolve_date_format(self.pools.read(), &upload_id_blob[..], query.date_format).await?;
 
         db::query_with_timeout(
             sqlx::query("DELETE FROM sightings WHERE upload_id = ?")
@@ -176,8 +381,24 @@ impl<'a> UploadIngestor<'a> {
         .await
         .map_err(|e| e.into_api_error("deleting existing sightings", "Database error"))?;
 
-        let (total_rows, actual_filename) =
-            ingest_field(field, self.pools.write(), &upload_id, self.writer_tracker).await?;
+        let options = ImportOptions {
+            duplicates: query.duplicates,
+            date_format,
+            stored: None,
+            written_ids: None,
+        };
+        let (summary, actual_filename) = ingest_field(
+            field,
+            self.pools.write(),
+            &upload_id,
+            self.writer_tracker,
+            &options,
+        )
+        .await?;
+        let total_rows = summary.total_rows;
+        let updates = Geocoder::new()
+            .geocode_batch(summary.updates.into_values().collect())
+            .await?;
 
         let mut tx = db::query_with_timeout(self.pools.write().begin())
             .await
@@ -185,12 +406,20 @@ impl<'a> UploadIngestor<'a> {
                 e.into_api_error("starting upload metadata transaction", "Database error")
             })?;
 
+        apply_updates(&mut tx, &upload_id_blob[..], &updates).await?;
+        recompute_tick_flags(&mut tx, &upload_id_blob[..], None)
+            .await
+            .map_err(|e| e.into_api_error("recomputing ticks", "Database error"))?;
+
         if let Err(e) = db::query_with_timeout(
             sqlx::query(
-                "UPDATE uploads SET row_count = ?, filename = ?, data_version = data_version + 1 WHERE id = ?",
+                "UPDATE uploads SET row_count = ?, filename = ?, format = ?, skipped_rows = ?, date_format = COALESCE(?, date_format), data_version = data_version + 1 WHERE id = ?",
             )
             .bind(row_count_value(total_rows))
             .bind(&actual_filename)
+            .bind(summary.format)
+            .bind(row_count_value(summary.issues.skipped_rows()))
+            .bind(query.date_format.map(DateFormat::as_str))
             .bind(&upload_id_blob[..])
             .execute(&mut *tx),
         )
@@ -199,6 +428,10 @@ impl<'a> UploadIngestor<'a> {
             e.log("updating upload metadata after replace");
         }
 
+        store_import_issues(&mut tx, &upload_id_blob[..], &summary.issues, false)
+            .await
+            .map_err(|e| e.into_api_error("storing import issues", "Database error"))?;
+
         compute_grid_cell_visibility_tx(&mut tx, &upload_id_blob[..])
             .await
             .map_err(|e| e.into_api_error("computing grid cell visibility", "Database error"))?;
@@ -230,9 +463,549 @@ impl<'a> UploadIngestor<'a> {
             upload_id,
             filename,
             total_rows,
+            format: summary.format,
+            ignored_columns: summary.ignored_columns,
+            skipped_rows: summary.issues.skipped_rows(),
+            skip_summary: summary.issues.summary(),
+            duplicate_rows: summary.duplicate_rows,
             data_version,
         })
     }
+
+    /// Adds rows with new `sighting_uuid`s and updates corrected ones, leaving
+    /// everything else in place. Only written rows count towards the quota,
+    /// and ticks are only recomputed for the species they touch.
+    ///
+    /// New rows are written in batches as the file streams in, before the
+    /// transaction that updates ticks and counts, so if anything fails or
+    /// the request is dropped they are deleted again by ID and the upload is
+    /// left as it was.
+    async fn append(
+        &self,
+        upload_uuid: Uuid,
+        field: Field<'_>,
+        query: ImportQuery,
+    ) -> Result<AppendOutcome, ApiError> {
+        let rollback = AppendRollback::new(self.pools.write().clone(), upload_uuid);
+        self.append_rows(upload_uuid, field, query, &rollback.written_ids)
+            .await
+    }
+
+    async fn append_rows(
+        &self,
+        upload_uuid: Uuid,
+        field: Field<'_>,
+        query: ImportQuery,
+        written_ids: &Arc<Mutex<Vec<i64>>>,
+    ) -> Result<AppendOutcome, ApiError> {
+        let upload_id = upload_uuid.to_string();
+        let upload_id_blob = &upload_uuid.as_bytes()[..];
+
+        let stored = StoredSightings::load(self.pools.read(), upload_id_blob)
+            .await
+            .map_err(|e| e.into_api_error("loading existing sightings", "Database error"))?;
+
+        let date_format =
+            resolve_date_format(self.pools.read(), upload_id_blob, query.date_format).await?;
+
+        let options = ImportOptions {
+            duplicates: query.duplicates,
+            date_format,
+            stored: Some(&stored),
+            written_ids: Some(written_ids),
+        };
+        let (summary, _) = ingest_field(
+            field,
+            self.pools.write(),
+            &upload_id,
+            self.writer_tracker,
+            &options,
+        )
+        .await?;
+        let IngestSummary {
+            total_rows: added_rows,
+            format,
+            ignored_columns,
+            issues,
+            duplicate_rows,
+            unchanged_rows,
+            updates,
+            mut species_ids,
+        } = summary;
+        let updated_rows = updates.len();
+        let updates = Geocoder::new()
+            .geocode_batch(updates.into_values().collect())
+            .await?;
+
+        let mut tx = db::query_with_timeout(self.pools.write().begin())
+            .await
+            .map_err(|e| e.into_api_error("starting append transaction", "Database error"))?;
+
+        species_ids.extend(apply_updates(&mut tx, upload_id_blob, &updates).await?);
+        recompute_ticks_for(&mut tx, upload_id_blob, species_ids).await?;
+
+        compute_grid_cell_visibility_tx(&mut tx, upload_id_blob)
+            .await
+            .map_err(|e| e.into_api_error("computing grid cell visibility", "Database error"))?;
+
+        let (row_count, data_version) = db::query_with_timeout(
+            sqlx::query_as::<_, (i64, i64)>(
+                "UPDATE uploads SET
+                    row_count = (SELECT COUNT(*) FROM sightings WHERE upload_id = ?),
+                    format = ?,
+                    skipped_rows = skipped_rows + ?,
+                    data_version = data_version + 1
+                WHERE id = ?
+                RETURNING row_count, data_version",
+            )
+            .bind(upload_id_blob)
+            .bind(format)
+            .bind(row_count_value(issues.skipped_rows()))
+            .bind(upload_id_blob)
+            .fetch_one(&mut *tx),
+        )
+        .await
+        .map_err(|e| e.into_api_error("updating upload metadata after append", "Database error"))?;
+
+        store_import_issues(&mut tx, upload_id_blob, &issues, true)
+            .await
+            .map_err(|e| e.into_api_error("storing import issues", "Database error"))?;
+
+        db::query_with_timeout(tx.commit())
+            .await
+            .map_err(|e| e.into_api_error("committing append transaction", "Database error"))?;
+        // The rows are part of the upload now, so there's nothing to roll back
+        written_ids.lock().await.clear();
+
+        if let Err(e) =
+            crate::bitmaps::compute_and_store_bitmaps(self.pools.write(), upload_id_blob).await
+        {
+            error!("Failed to compute tick bitmaps: {}", e.body.error);
+        }
+
+        info!(
+            "Append complete: {} added, {} updated, {} unchanged (upload_id: {})",
+            added_rows, updated_rows, unchanged_rows, upload_id
+        );
+
+        Ok(AppendOutcome {
+            upload_id,
+            row_count,
+            format,
+            ignored_columns,
+            added_rows,
+            updated_rows,
+            unchanged_rows,
+            skipped_rows: issues.skipped_rows(),
+            skip_summary: issues.summary(),
+            duplicate_rows,
+            data_version,
+        })
+    }
+
+    /// Recreates an upload from a signed backup archive, under its original
+    /// ID if that's still free. Sightings are located from their coordinates
+    /// and time as on import; their stored tick flags are kept when that
+    /// puts every one where it was, and worked out again otherwise.
+    async fn restore(&self, field: Field<'_>) -> Result<RestoreOutcome, ApiError> {
+        let archive = backup::read_archive(read_zip_field(field).await?).await?;
+        let BackupArchive {
+            manifest,
+            species,
+            sightings,
+            issues,
+            located_as_stored,
+        } = archive;
+        let original_uuid = parse_upload_id(&manifest.upload_id)?;
+
+        let location_mode = LocationMode::parse(&manifest.location_mode, manifest.location_km)
+            .map_err(|e| ApiError::bad_request(format!("Invalid location mode in backup: {e}")))?;
+        if manifest.species_location_rules.len() > MAX_SPECIES_RULES
+            || manifest.edit_tokens.len() as i64 > MAX_EDIT_TOKENS_PER_UPLOAD
+        {
+            return Err(ApiError::bad_request(
+                "Backup has more location rules or edit tokens than an upload can have",
+            ));
+        }
+        let token_names = manifest
+            .edit_tokens
+            .iter()
+            .map(|token| normalise_name(&token.name))
+            .collect::<Result<Vec<_>, _>>()?;
+        for rule in &manifest.species_location_rules {
+            LocationMode::parse(&rule.mode, rule.km).map_err(|e| {
+                ApiError::bad_request(format!(
+                    "Invalid location rule for {} in backup: {e}",
+                    rule.scientific_name
+                ))
+            })?;
+        }
+        // Only a hash this server could still make is kept, so one written
+        // under older Argon2 costs doesn't come back
+        if manifest
+            .view_password_hash
+            .as_deref()
+            .is_some_and(|hash| !is_password_hash(hash))
+        {
+            return Err(ApiError::bad_request("Invalid view password in backup"));
+        }
+        self.writer_tracker
+            .reserve_sightings(sightings.len() as u64)
+            .await
+            .map_err(map_quota_error)?;
+
+        let start = Instant::now();
+        let mut tx = db::query_with_timeout(self.pools.write().begin())
+            .await
+            .map_err(|e| e.into_api_error("starting restore transaction", "Database error"))?;
+
+        // The original ID may have been taken again by another restore of
+        // the same archive
+        let original_taken = db::query_with_timeout(
+            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM uploads WHERE id = ?)")
+                .bind(&original_uuid.as_bytes()[..])
+                .fetch_one(&mut *tx),
+        )
+        .await
+        .map_err(|e| e.into_api_error("checking original upload ID", "Database error"))?;
+        let upload_uuid = if original_taken {
+            Uuid::new_v4()
+        } else {
+            original_uuid
+        };
+        // Tokens are always new, so whoever held the original's tokens
+        // doesn't also get the restored upload
+        let edit_token = Uuid::new_v4().to_string();
+        let upload_id_blob = &upload_uuid.as_bytes()[..];
+        let row_count = row_count_value(sightings.len());
+        // An expiry that passed while the upload was backed up would delete
+        // it again on the next retention run
+        let now = chrono::Utc::now()
+            .format(RETENTION_TIMESTAMP_FORMAT)
+            .to_string();
+        let expires_at = manifest
+            .expires_at
+            .as_ref()
+            .filter(|expires_at| **expires_at > now);
+
+        db::query_with_timeout(
+            sqlx::query(
+                "INSERT INTO uploads (id, filename, display_name, created_at, row_count, edit_token_hash, data_version, format, skipped_rows, date_format, pinned, expires_at, location_mode, location_km, sensitive_species, view_password_hash)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(upload_id_blob)
+            .bind(&manifest.filename)
+            .bind(&manifest.display_name)
+            .bind(&manifest.created_at)
+            .bind(row_count)
+            .bind(hash_token(&edit_token))
+            .bind(manifest.data_version)
+            .bind(&manifest.format)
+            .bind(manifest.skipped_rows)
+            .bind(&manifest.date_format)
+            .bind(manifest.pinned)
+            .bind(expires_at)
+            .bind(location_mode.as_str())
+            .bind(location_mode.km())
+            .bind(manifest.sensitive_species)
+            .bind(&manifest.view_password_hash)
+            .execute(&mut *tx),
+        )
+        .await
+        .map_err(|e| e.into_api_error("creating restored upload", "Database error"))?;
+
+        for chunk in manifest
+            .species_location_rules
+            .chunks(RESTORE_LOCATION_RULE_BATCH_SIZE)
+        {
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT INTO species_location_rules (upload_id, scientific_name, mode, km) ",
+            );
+            qb.push_values(chunk, |mut b, rule| {
+                b.push_bind(upload_id_blob)
+                    .push_bind(&rule.scientific_name)
+                    .push_bind(&rule.mode)
+                    .push_bind(rule.km);
+            });
+            db::query_with_timeout(qb.build().execute(&mut *tx))
+                .await
+                .map_err(|e| e.into_api_error("restoring location rules", "Database error"))?;
+        }
+
+        // Named tokens come back under their old names with new values, so
+        // tokens for the original upload don't also work on this one
+        let mut named_edit_tokens = Vec::with_capacity(token_names.len());
+        for name in &token_names {
+            let token = issue_edit_token(&mut *tx, upload_id_blob, name)
+                .await
+                .map_err(|e| e.into_api_error("restoring edit tokens", "Database error"))?;
+            named_edit_tokens.push(token);
+        }
+
+        // Species IDs differ between databases, so they're matched by name
+        let mut species_ids = HashMap::with_capacity(species.len());
+        for entry in &species {
+            let species_id =
+                resolve_species_id(&mut tx, &entry.common_name, &entry.scientific_name)
+                    .await
+                    .map_err(|e| e.into_api_error("resolving species", "Database error"))?;
+            species_ids.insert(entry.id, species_id);
+        }
+        // Stored ticks only hold if every row is located as it was and no two
+        // archived species became one here
+        let keep_ticks = located_as_stored
+            && species_ids.values().collect::<HashSet<_>>().len() == species_ids.len();
+
+        for chunk in sightings.chunks(RESTORE_SIGHTING_BATCH_SIZE) {
+            let mut rows = Vec::with_capacity(chunk.len());
+            for sighting in chunk {
+                let species_id =
+                    species_ids
+                        .get(&sighting.species_id)
+                        .copied()
+                        .ok_or_else(|| {
+                            ApiError::bad_request(format!(
+                                "Backup sighting refers to unknown species {}",
+                                sighting.species_id
+                            ))
+                        })?;
+                let sighting_uuid = Uuid::from_slice(&sighting.sighting_uuid)
+                    .map_err(|_| ApiError::bad_request("Invalid sighting_uuid in backup"))?;
+                let vis_rank = if keep_ticks {
+                    sighting.vis_rank
+                } else {
+                    i64::from(vis_rank_for(&sighting_uuid))
+                };
+                rows.push((species_id, vis_rank, sighting));
+            }
+
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT INTO sightings (upload_id, sighting_uuid, species_id, count, latitude, longitude, country_code, region_code, observed_at, local_date, year, lifer, year_tick, country_tick, vis_rank) ",
+            );
+            qb.push_values(rows, |mut b, (species_id, vis_rank, sighting)| {
+                b.push_bind(upload_id_blob)
+                    .push_bind(&sighting.sighting_uuid)
+                    .push_bind(species_id)
+                    .push_bind(sighting.count)
+                    .push_bind(sighting.latitude)
+                    .push_bind(sighting.longitude)
+                    .push_bind(&sighting.country_code)
+                    .push_bind(&sighting.region_code)
+                    .push_bind(&sighting.observed_at)
+                    .push_bind(&sighting.local_date)
+                    .push_bind(sighting.year)
+                    .push_bind(keep_ticks && sighting.lifer)
+                    .push_bind(keep_ticks && sighting.year_tick)
+                    .push_bind(keep_ticks && sighting.country_tick)
+                    .push_bind(vis_rank);
+            });
+            db::query_with_timeout(qb.build().execute(&mut *tx))
+                .await
+                .map_err(|e| e.into_api_error("restoring sightings", "Database error"))?;
+        }
+
+        for chunk in issues.chunks(RESTORE_ISSUE_BATCH_SIZE) {
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT INTO import_issues (upload_id, data_version, row_number, column_name, reason) ",
+            );
+            qb.push_values(chunk, |mut b, issue| {
+                b.push_bind(upload_id_blob)
+                    .push_bind(issue.data_version)
+                    .push_bind(issue.row_number)
+                    .push_bind(&issue.column_name)
+                    .push_bind(&issue.reason);
+            });
+            db::query_with_timeout(qb.build().execute(&mut *tx))
+                .await
+                .map_err(|e| e.into_api_error("restoring import issues", "Database error"))?;
+        }
+
+        if !keep_ticks {
+            recompute_tick_flags(&mut tx, upload_id_blob, None)
+                .await
+                .map_err(|e| e.into_api_error("recomputing ticks", "Database error"))?;
+            compute_grid_cell_visibility_tx(&mut tx, upload_id_blob)
+                .await
+                .map_err(|e| {
+                    e.into_api_error("computing grid cell visibility", "Database error")
+                })?;
+        }
+
+        db::query_with_timeout(tx.commit())
+            .await
+            .map_err(|e| e.into_api_error("committing restore transaction", "Database error"))?;
+        self.writer_tracker
+            .record_writer_usage(start.elapsed())
+            .await;
+
+        if let Err(e) =
+            crate::bitmaps::compute_and_store_bitmaps(self.pools.write(), upload_id_blob).await
+        {
+            error!("Failed to compute tick bitmaps: {}", e.body.error);
+        }
+
+        let upload_id = upload_uuid.to_string();
+        // Anything cached for a deleted upload that had this ID is stale now
+        invalidate_upload_cache(&upload_id).await;
+        invalidate_name_index_cache(&upload_id);
+        info!(
+            "Restore complete: {} rows from backup of {} (upload_id: {}, ticks kept: {})",
+            row_count, manifest.upload_id, upload_id, keep_ticks
+        );
+
+        Ok(RestoreOutcome {
+            upload_id,
+            edit_token,
+            named_edit_tokens,
+            title: effective_display_name(manifest.display_name, &manifest.filename),
+            filename: manifest.filename,
+            row_count,
+            format: manifest.format,
+            skipped_rows: manifest.skipped_rows,
+            data_version: manifest.data_version,
+        })
+    }
+}
+
+/// Deletes the rows an append wrote unless it committed them, including when
+/// the request is dropped part way through, e.g. on timeout.
+struct AppendRollback {
+    pool: sqlx::SqlitePool,
+    upload_uuid: Uuid,
+    written_ids: Arc<Mutex<Vec<i64>>>,
+}
+
+impl AppendRollback {
+    fn new(pool: sqlx::SqlitePool, upload_uuid: Uuid) -> Self {
+        Self {
+            pool,
+            upload_uuid,
+            written_ids: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl Drop for AppendRollback {
+    fn drop(&mut self) {
+        // Nothing else holds the lock once the append is over
+        let Ok(mut written_ids) = self.written_ids.try_lock() else {
+            return;
+        };
+        if written_ids.is_empty() {
+            return;
+        }
+        let written_ids = std::mem::take(&mut *written_ids);
+        let pool = self.pool.clone();
+        let upload_uuid = self.upload_uuid;
+        tokio::spawn(async move {
+            if let Err(e) = delete_written_sightings(&pool, upload_uuid, &written_ids).await {
+                e.log("deleting rows from failed append");
+            }
+        });
+    }
+}
+
+/// Deletes the given rows of an upload, leaving sightings added meanwhile by
+/// anything else in place.
+async fn delete_written_sightings(
+    pool: &sqlx::SqlitePool,
+    upload_uuid: Uuid,
+    written_ids: &[i64],
+) -> Result<(), DbQueryError> {
+    let mut tx = db::query_with_timeout(pool.begin()).await?;
+    for chunk in written_ids.chunks(APPEND_CLEANUP_BATCH_SIZE) {
+        let mut qb: QueryBuilder<Sqlite> =
+            QueryBuilder::new("DELETE FROM sightings WHERE upload_id = ");
+        qb.push_bind(&upload_uuid.as_bytes()[..]);
+        qb.push(" AND id IN (");
+        let mut separated = qb.separated(", ");
+        for id in chunk {
+            separated.push_bind(*id);
+        }
+        qb.push(")");
+        db::query_with_timeout(qb.build().execute(&mut *tx)).await?;
+    }
+    db::query_with_timeout(tx.commit()).await?;
+    Ok(())
+}
+
+/// Overwrites already written sightings with rows that share their
+/// `sighting_uuid`, returning the old and new species of every row touched.
+async fn apply_updates(
+    tx: &mut Transaction<'_, Sqlite>,
+    upload_id_blob: &[u8],
+    updates: &[ProcessedSighting],
+) -> Result<HashSet<i64>, ApiError> {
+    let mut species_ids = HashSet::new();
+    let mut species_cache: HashMap<(&str, &str), i64> = HashMap::new();
+
+    for sighting in updates {
+        let key = (
+            sighting.common_name.as_str(),
+            sighting.scientific_name.as_str(),
+        );
+        let species_id = match species_cache.get(&key) {
+            Some(&species_id) => species_id,
+            None => {
+                let species_id = resolve_species_id(tx, key.0, key.1)
+                    .await
+                    .map_err(|e| e.into_api_error("resolving species", "Database error"))?;
+                species_cache.insert(key, species_id);
+                species_id
+            }
+        };
+
+        let old_species_id = db::query_with_timeout(
+            sqlx::query_scalar::<_, i64>(
+                "SELECT species_id FROM sightings WHERE upload_id = ? AND sighting_uuid = ?",
+            )
+            .bind(upload_id_blob)
+            .bind(&sighting.sighting_uuid.as_bytes()[..])
+            .fetch_optional(&mut **tx),
+        )
+        .await
+        .map_err(|e| e.into_api_error("loading sighting to update", "Database error"))?;
+        species_ids.extend(old_species_id);
+        species_ids.insert(species_id);
+
+        db::query_with_timeout(
+            sqlx::query(
+                "UPDATE sightings SET species_id = ?, count = ?, latitude = ?, longitude = ?, country_code = ?, region_code = ?, observed_at = ?, local_date = ?, year = ?
+                WHERE upload_id = ? AND sighting_uuid = ?",
+            )
+            .bind(species_id)
+            .bind(sighting.count)
+            .bind(sighting.latitude)
+            .bind(sighting.longitude)
+            .bind(sighting.country_code.as_str())
+            .bind(sighting.region_code.as_deref())
+            .bind(sighting.observed_at.as_str())
+            .bind(sighting.local_date.as_str())
+            .bind(sighting.year)
+            .bind(upload_id_blob)
+            .bind(&sighting.sighting_uuid.as_bytes()[..])
+            .execute(&mut **tx),
+        )
+        .await
+        .map_err(|e| e.into_api_error("updating sighting", "Database error"))?;
+    }
+
+    Ok(species_ids)
+}
+
+async fn recompute_ticks_for(
+    tx: &mut Transaction<'_, Sqlite>,
+    upload_id_blob: &[u8],
+    species_ids: HashSet<i64>,
+) -> Result<(), ApiError> {
+    if species_ids.is_empty() {
+        return Ok(());
+    }
+
+    let species_ids: Vec<i64> = species_ids.into_iter().collect();
+    recompute_tick_flags(tx, upload_id_blob, Some(&species_ids))
+        .await
+        .map_err(|e| e.into_api_error("recomputing ticks", "Database error"))
 }
 
 // No salt needed: tokens are 122-bit random UUIDs, not user-chosen passwords.
@@ -356,13 +1129,14 @@ async fn ingest_csv_field(
     pool: &sqlx::SqlitePool,
     upload_id: &str,
     writer_tracker: &UploadUsageTracker,
-) -> Result<usize, ApiError> {
+    options: &ImportOptions<'_>,
+) -> Result<IngestSummary, ApiError> {
     let stream = field
         .into_stream()
         .map(|result| result.map_err(io::Error::other));
     let limited_stream = SizeLimitedStream::new(stream, MAX_UPLOAD_BYTES);
     let reader = StreamReader::new(limited_stream);
-    read_csv(reader, pool, upload_id, writer_tracker).await
+    read_csv(reader, pool, upload_id, writer_tracker, options).await
 }
 
 async fn ingest_field(
@@ -370,63 +1144,97 @@ async fn ingest_field(
     pool: &sqlx::SqlitePool,
     upload_id: &str,
     writer_tracker: &UploadUsageTracker,
-) -> Result<(usize, String), ApiError> {
+    options: &ImportOptions<'_>,
+) -> Result<(IngestSummary, String), ApiError> {
     let filename = field
         .file_name()
         .map_or_else(|| "unknown".to_string(), ToString::to_string);
 
     if is_zip_file(&filename) {
-        let stream = field
-            .into_stream()
-            .map(|result| result.map_err(io::Error::other));
-
-        let mut size_tracker = 0u64;
-        let chunks: Vec<Bytes> = stream
-            .map(|result| {
-                result.map(|chunk| {
-                    size_tracker += chunk.len() as u64;
-                    if size_tracker > MAX_UPLOAD_BYTES as u64 {
-                        return Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            UploadSizeExceeded,
-                        ));
-                    }
-                    Ok(chunk)
-                })
-            })
-            .try_collect::<Vec<_>>()
-            .await
-            .map_err(|e| ApiError::bad_request(format!("Failed to read upload: {}", e)))?
-            .into_iter()
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|_| {
-                ApiError::bad_request(format!("ZIP exceeds {UPLOAD_LIMIT_MB} MB upload limit"))
-            })?;
-
-        let combined = chunks
-            .iter()
-            .flat_map(|c| c.iter().copied())
-            .collect::<Vec<u8>>();
+        let combined = read_zip_field(field).await?;
+        let size_tracker = combined.len() as u64;
         let cursor = io::Cursor::new(combined);
         let extracted = zip_extract::extract_csv_from_zip(cursor, size_tracker).await?;
 
         let csv_reader = io::Cursor::new(extracted.data);
-        let rows = read_csv(csv_reader, pool, upload_id, writer_tracker).await?;
-        Ok((rows, extracted.filename))
+        let summary = read_csv(csv_reader, pool, upload_id, writer_tracker, options).await?;
+        Ok((summary, extracted.filename))
     } else if is_csv_file(&filename) {
-        let rows = ingest_csv_field(field, pool, upload_id, writer_tracker).await?;
-        Ok((rows, filename))
+        let summary = ingest_csv_field(field, pool, upload_id, writer_tracker, options).await?;
+        Ok((summary, filename))
     } else {
         Err(ApiError::bad_request("File must be a CSV or ZIP file"))
     }
 }
 
+/// Reads a ZIP upload into memory, up to the upload size limit.
+async fn read_zip_field(field: Field<'_>) -> Result<Vec<u8>, ApiError> {
+    let stream = field
+        .into_stream()
+        .map(|result| result.map_err(io::Error::other));
+
+    let mut size_tracker = 0u64;
+    let chunks: Vec<Bytes> = stream
+        .map(|result| {
+            result.map(|chunk| {
+                size_tracker += chunk.len() as u64;
+                if size_tracker > MAX_UPLOAD_BYTES as u64 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        UploadSizeExceeded,
+                    ));
+                }
+                Ok(chunk)
+            })
+        })
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(|e| ApiError::bad_request(format!("Failed to read upload: {}", e)))?
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| {
+            ApiError::bad_request(format!("ZIP exceeds {UPLOAD_LIMIT_MB} MB upload limit"))
+        })?;
+
+    Ok(chunks
+        .iter()
+        .flat_map(|c| c.iter().copied())
+        .collect::<Vec<u8>>())
+}
+
+/// The day/month order to import with: the one requested, or else the one
+/// stored for the upload.
+async fn resolve_date_format(
+    pool: &sqlx::SqlitePool,
+    upload_id_blob: &[u8],
+    requested: Option<DateFormat>,
+) -> Result<DateFormat, ApiError> {
+    if let Some(date_format) = requested {
+        return Ok(date_format);
+    }
+
+    let stored = db::query_with_timeout(
+        sqlx::query_scalar::<_, Option<String>>("SELECT date_format FROM uploads WHERE id = ?")
+            .bind(upload_id_blob)
+            .fetch_optional(pool),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading upload date format", "Database error"))?;
+
+    Ok(stored
+        .flatten()
+        .as_deref()
+        .and_then(DateFormat::parse)
+        .unwrap_or_default())
+}
+
 async fn read_csv<R>(
     reader: R,
     pool: &sqlx::SqlitePool,
     upload_id: &str,
     writer_tracker: &UploadUsageTracker,
-) -> Result<usize, ApiError>
+    options: &ImportOptions<'_>,
+) -> Result<IngestSummary, ApiError>
 where
     R: tokio::io::AsyncRead + Unpin + Send,
 {
@@ -439,37 +1247,83 @@ where
         .await
         .map_err(|err| map_csv_error(err, "Failed to read CSV headers", "Invalid CSV headers"))?;
 
-    let mut parser = CsvParser::new(headers)?;
+    let sources::DetectedSource { format, mut parser } =
+        sources::detect(headers, options.date_format)?;
+    let ignored_columns = parser.ignored_columns();
     let geocoder = Geocoder::new();
-    let mut sink = DbSink::new(upload_id.to_string());
-    let mut pending_rows: Vec<ParsedSighting> = Vec::new();
+    let mut sink = DbSink::new(upload_id.to_string())
+        .with_existing_rows(options.stored.map_or(0, |stored| stored.row_count));
+    if let Some(written_ids) = options.written_ids {
+        sink = sink.with_written_ids(Arc::clone(written_ids));
+    }
+    let mut ingest = Ingest {
+        pool,
+        geocoder: &geocoder,
+        writer_tracker,
+        options,
+        sink,
+        pending_rows: Vec::new(),
+        issues: ImportIssues::default(),
+        seen: HashSet::new(),
+        duplicate_rows: 0,
+        unchanged_rows: 0,
+        updates: HashMap::new(),
+    };
+    // Rows held back, in file order, until a date settles which way round
+    // day and month go (see `DateOrder`). Once one row is held, the rest
+    // are too, so duplicates are still resolved in file order. If
+    // MAX_HELD_ROWS go by without that happening, the configured default
+    // order is used, or failing that the ambiguous dates are skipped.
+    let mut held: Vec<(usize, csv_async::ByteRecord)> = Vec::new();
+    let mut holding = true;
     let mut record = csv_async::ByteRecord::new();
+    let mut row = 0;
 
     while csv_reader
         .read_byte_record(&mut record)
         .await
         .map_err(|err| map_csv_error(err, "Failed to read CSV row", "Invalid CSV data"))?
     {
-        if let Some(parsed) = parser.parse_row(&record)? {
-            writer_tracker
-                .reserve_sightings(1)
-                .await
-                .map_err(map_quota_error)?;
-            pending_rows.push(parsed);
-
-            if pending_rows.len() >= BATCH_SIZE {
-                process_pending_rows(
-                    &mut sink,
-                    pool,
-                    &geocoder,
-                    &mut pending_rows,
-                    writer_tracker,
-                )
-                .await?;
+        row += 1;
+        let result = parser.parse_row(&record, row);
+        if holding
+            && parser.date_format() == DateFormat::Auto
+            && (!held.is_empty() || is_ambiguous_date(&result))
+        {
+            held.push((row, record.clone()));
+            if held.len() < MAX_HELD_ROWS {
+                continue;
             }
+            parser.settle_date_format(config::default_date_format());
+            holding = false;
+            for (held_row, held_record) in std::mem::take(&mut held) {
+                let held_result = parser.parse_row(&held_record, held_row);
+                ingest.accept(held_row, held_result).await?;
+            }
+            continue;
+        }
+        for (held_row, held_record) in std::mem::take(&mut held) {
+            let held_result = parser.parse_row(&held_record, held_row);
+            ingest.accept(held_row, held_result).await?;
         }
+        ingest.accept(row, result).await?;
+    }
+
+    // Nothing settled the order, so ambiguous dates are skipped
+    for (held_row, held_record) in held {
+        let held_result = parser.parse_row(&held_record, held_row);
+        ingest.accept(held_row, held_result).await?;
     }
 
+    let Ingest {
+        mut sink,
+        mut pending_rows,
+        issues,
+        duplicate_rows,
+        unchanged_rows,
+        updates,
+        ..
+    } = ingest;
     process_pending_rows(
         &mut sink,
         pool,
@@ -480,7 +1334,117 @@ where
     .await?;
     flush_with_tracking(&mut sink, pool, writer_tracker).await?;
 
-    Ok(sink.total_rows())
+    Ok(IngestSummary {
+        total_rows: sink.total_rows(),
+        format,
+        ignored_columns,
+        issues,
+        duplicate_rows,
+        unchanged_rows,
+        updates,
+        species_ids: sink.species_ids().collect(),
+    })
+}
+
+const fn is_ambiguous_date(result: &Result<ParsedSighting, RowError>) -> bool {
+    matches!(
+        result,
+        Err(RowError::Skip(SkippedRow {
+            reason: SkipReason::AmbiguousDate,
+            ..
+        }))
+    )
+}
+
+/// The state of one import as parsed rows are accepted, in file order.
+struct Ingest<'a> {
+    pool: &'a sqlx::SqlitePool,
+    geocoder: &'a Geocoder,
+    writer_tracker: &'a UploadUsageTracker,
+    options: &'a ImportOptions<'a>,
+    sink: DbSink,
+    pending_rows: Vec<ParsedSighting>,
+    issues: ImportIssues,
+    seen: HashSet<Uuid>,
+    duplicate_rows: usize,
+    unchanged_rows: usize,
+    updates: HashMap<Uuid, ParsedSighting>,
+}
+
+impl Ingest<'_> {
+    async fn accept(
+        &mut self,
+        row: usize,
+        result: Result<ParsedSighting, RowError>,
+    ) -> Result<(), ApiError> {
+        let parsed = match result {
+            Ok(parsed) => parsed,
+            Err(RowError::Skip(skipped)) => {
+                self.issues.record(skipped);
+                return Ok(());
+            }
+            Err(RowError::Fatal(err)) => return Err(err),
+        };
+
+        if !self.seen.insert(parsed.sighting_uuid) {
+            match self.options.duplicates {
+                DuplicatePolicy::Skip => {
+                    self.duplicate_rows += 1;
+                    return Ok(());
+                }
+                DuplicatePolicy::LastWins => {
+                    self.duplicate_rows += 1;
+                    self.writer_tracker
+                        .reserve_sightings(1)
+                        .await
+                        .map_err(map_quota_error)?;
+                    self.updates.insert(parsed.sighting_uuid, parsed);
+                    return Ok(());
+                }
+                DuplicatePolicy::Error => {
+                    return Err(ApiError::bad_request(format!(
+                        "Row {row} repeats sighting ID {}",
+                        parsed.sighting_uuid
+                    )));
+                }
+            }
+        }
+
+        let stored_fingerprint = self
+            .options
+            .stored
+            .and_then(|stored| stored.fingerprints.get(&parsed.sighting_uuid).copied());
+        if let Some(fingerprint) = stored_fingerprint {
+            if fingerprint == parsed.fingerprint() {
+                self.unchanged_rows += 1;
+                return Ok(());
+            }
+            self.writer_tracker
+                .reserve_sightings(1)
+                .await
+                .map_err(map_quota_error)?;
+            self.updates.insert(parsed.sighting_uuid, parsed);
+            return Ok(());
+        }
+
+        self.writer_tracker
+            .reserve_sightings(1)
+            .await
+            .map_err(map_quota_error)?;
+        self.pending_rows.push(parsed);
+
+        if self.pending_rows.len() >= BATCH_SIZE {
+            process_pending_rows(
+                &mut self.sink,
+                self.pool,
+                self.geocoder,
+                &mut self.pending_rows,
+                self.writer_tracker,
+            )
+            .await?;
+        }
+        Ok(())
+    }
 }
 
 async fn process_pending_rows(
@@ -518,7 +1482,7 @@ async fn flush_with_tracking(
     Ok(())
 }
 
-async fn compute_grid_cell_visibility_tx(
+pub(crate) async fn compute_grid_cell_visibility_tx(
     tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
     upload_id_blob: &[u8],
 ) -> Result<(), DbQueryError> {
@@ -548,6 +1512,7 @@ async fn compute_grid_cell_visibility_tx(
 
 pub async fn upload_csv(
     State(pools): State<DbPools>,
+    Query(query): Query<ImportQuery>,
     Extension(writer_tracker): Extension<UploadUsageTracker>,
     mut multipart: Multipart,
 ) -> impl IntoResponse {
@@ -558,7 +1523,7 @@ pub async fn upload_csv(
             .map_or_else(|| "unknown".to_string(), ToString::to_string);
 
         if is_csv_or_zip_file(&filename) {
-            match ingestor.create(field, filename.clone()).await {
+            match ingestor.create(field, filename.clone(), query).await {
                 Ok(result) => {
                     let response_title = default_display_name(&result.filename);
                     return (
@@ -570,6 +1535,16 @@ pub async fn upload_csv(
                             edit_token: result.edit_token,
                             title: response_title,
                             data_version: result.data_version,
+                            format: result.format.to_string(),
+                            skipped_rows: row_count_value(result.skipped_rows),
+                            skip_summary: result.skip_summary,
+                            duplicate_rows: row_count_value(result.duplicate_rows),
+                            ignored_columns: result
+                                .ignored_columns
+                                .into_iter()
+                                .map(str::to_string)
+                                .collect(),
+                            named_edit_tokens: Vec::new(),
                         }),
                     )
                         .into_response();
@@ -582,6 +1557,47 @@ pub async fn upload_csv(
     ApiError::bad_request("No CSV file found in upload").into_response()
 }
 
+pub async fn restore_upload(
+    State(pools): State<DbPools>,
+    Extension(writer_tracker): Extension<UploadUsageTracker>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let ingestor = UploadIngestor::new(&pools, &writer_tracker);
+    while let Ok(Some(field)) = multipart.next_field().await {
+        let filename = field
+            .file_name()
+            .map_or_else(|| "unknown".to_string(), ToString::to_string);
+
+        if is_zip_file(&filename) {
+            match ingestor.restore(field).await {
+                Ok(result) => {
+                    return (
+                        axum::http::StatusCode::OK,
+                        Proto::new(pb::UploadResponse {
+                            upload_id: result.upload_id,
+                            filename: result.filename,
+                            row_count: result.row_count,
+                            edit_token: result.edit_token,
+                            title: result.title,
+                            data_version: result.data_version,
+                            format: result.format.unwrap_or_default(),
+                            skipped_rows: result.skipped_rows,
+                            skip_summary: Vec::new(),
+                            duplicate_rows: 0,
+                            ignored_columns: Vec::new(),
+                            named_edit_tokens: result.named_edit_tokens,
+                        }),
+                    )
+                        .into_response();
+                }
+                Err(err) => return err.into_response(),
+            }
+        }
+    }
+
+    ApiError::bad_request("No backup archive found in upload").into_response()
+}
+
 fn is_csv_file(filename: &str) -> bool {
     std::path::Path::new(filename)
         .extension()
@@ -598,7 +1614,7 @@ fn is_csv_or_zip_file(filename: &str) -> bool {
     is_csv_file(filename) || is_zip_file(filename)
 }
 
-fn extract_edit_token(headers: &axum::http::HeaderMap) -> Option<String> {
+pub(crate) fn extract_edit_token(headers: &axum::http::HeaderMap) -> Option<String> {
     headers
         .get(header::AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
@@ -606,11 +1622,30 @@ fn extract_edit_token(headers: &axum::http::HeaderMap) -> Option<String> {
         .map(ToString::to_string)
 }
 
-async fn verify_upload_access(
+/// Parses an `upload_id` from a request path.
+pub(crate) fn parse_upload_id(upload_id: &str) -> Result<Uuid, ApiError> {
+    parse_upload_id_field(upload_id, "upload_id")
+}
+
+/// Parses an upload's ID sent under another name, such as `collection_id`.
+pub(crate) fn parse_upload_id_field(value: &str, field: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(value).map_err(|_| ApiError::bad_request(format!("Invalid {field} format")))
+}
+
+/// Which of an upload's edit tokens a request used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EditTokenMatch {
+    /// The token handed out when the upload was created
+    Primary,
+    /// A named token from `edit_tokens`, by ID
+    Named(i64),
+}
+
+pub(crate) async fn find_edit_token(
     pool: &sqlx::SqlitePool,
     upload_id: &str,
     token: &str,
-) -> Result<bool, DbQueryError> {
+) -> Result<Option<EditTokenMatch>, DbQueryError> {
     let upload_uuid = Uuid::parse_str(upload_id)
         .map_err(|_| DbQueryError::Sqlx(sqlx::Error::Decode("Invalid UUID format".into())))?;
     let upload_id_blob = upload_uuid.as_bytes();
@@ -622,29 +1657,72 @@ async fn verify_upload_access(
     .await?;
 
     match hash {
-        Some(Some(stored_hash)) => Ok(verify_token(token, &stored_hash)),
-        Some(None) | None => Ok(false), // Upload exists but has no token (legacy) or doesn't exist
+        Some(Some(stored_hash)) if verify_token(token, &stored_hash) => {
+            return Ok(Some(EditTokenMatch::Primary));
+        }
+        Some(_) => {}
+        None => return Ok(None),
     }
+
+    let named = db::query_with_timeout(
+        sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM edit_tokens WHERE upload_id = ? AND token_hash = ?",
+        )
+        .bind(&upload_id_blob[..])
+        .bind(hash_token(token))
+        .fetch_optional(pool),
+    )
+    .await?;
+    Ok(named.map(EditTokenMatch::Named))
 }
 
-async fn verify_edit_token(
+pub(crate) async fn verify_upload_access(
+    pool: &sqlx::SqlitePool,
+    upload_id: &str,
+    token: &str,
+) -> Result<bool, DbQueryError> {
+    Ok(find_edit_token(pool, upload_id, token).await?.is_some())
+}
+
+/// Checks the request's edit token against all of the upload's tokens and
+/// says which one it was.
+pub(crate) async fn authorise_edit(
     pool: &sqlx::SqlitePool,
     headers: &axum::http::HeaderMap,
     upload_id: &str,
-) -> Result<(), axum::response::Response> {
+) -> Result<EditTokenMatch, ApiError> {
+    if is_share_token(upload_id) {
+        return Err(ApiError::forbidden("Share links are read-only"));
+    }
     let Some(token) = extract_edit_token(headers) else {
-        return Err(ApiError::unauthorised("Missing edit token").into_response());
+        return Err(ApiError::unauthorised("Missing edit token"));
     };
 
-    match verify_upload_access(pool, upload_id, &token).await {
-        Ok(true) => Ok(()),
-        Ok(false) => Err(ApiError::forbidden("Invalid edit token").into_response()),
-        Err(e) => Err(e
-            .into_api_error("verifying edit token", "Database error")
-            .into_response()),
+    match find_edit_token(pool, upload_id, &token).await {
+        Ok(Some(token_match)) => Ok(token_match),
+        Ok(None) => Err(ApiError::forbidden("Invalid edit token")),
+        Err(e) => Err(e.into_api_error("verifying edit token", "Database error")),
     }
 }
 
+pub(crate) async fn check_edit_token(
+    pool: &sqlx::SqlitePool,
+    headers: &axum::http::HeaderMap,
+    upload_id: &str,
+) -> Result<(), ApiError> {
+    authorise_edit(pool, headers, upload_id).await.map(|_| ())
+}
+
+async fn verify_edit_token(
+    pool: &sqlx::SqlitePool,
+    headers: &axum::http::HeaderMap,
+    upload_id: &str,
+) -> Result<(), axum::response::Response> {
+    check_edit_token(pool, headers, upload_id)
+        .await
+        .map_err(IntoResponse::into_response)
+}
+
 #[derive(Deserialize)]
 pub struct RenamePayload {
     display_name: Option<String>,
@@ -660,11 +1738,9 @@ pub async fn rename_upload(
         return response;
     }
 
-    let upload_uuid = match Uuid::parse_str(&upload_id) {
+    let upload_uuid = match parse_upload_id(&upload_id) {
         Ok(uuid) => uuid,
-        Err(_) => {
-            return ApiError::bad_request("Invalid upload_id format").into_response();
-        }
+        Err(err) => return err.into_response(),
     };
     let upload_id_blob = upload_uuid.as_bytes();
 
@@ -688,40 +1764,143 @@ pub async fn rename_upload(
             .into_response();
     }
 
-    let metadata = match db::query_with_timeout(
-        sqlx::query_as::<_, (String, i64, Option<String>, i64)>(
-            "SELECT filename, row_count, display_name, data_version FROM uploads WHERE id = ?",
+    match load_upload_metadata(pools.read(), &upload_uuid).await {
+        Ok(metadata) => Proto::new(metadata).into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RetentionPayload {
+    #[serde(default)]
+    pinned: bool,
+    expires_at: Option<String>,
+}
+
+pub async fn set_retention(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(payload): Json<RetentionPayload>,
+) -> Result<Proto<pb::UploadMetadata>, ApiError> {
+    check_edit_token(pools.read(), &headers, &upload_id).await?;
+
+    let upload_uuid = parse_upload_id(&upload_id)?;
+    let expires_at = payload
+        .expires_at
+        .as_deref()
+        .map(parse_expiry)
+        .transpose()?;
+
+    db::query_with_timeout(
+        sqlx::query(
+            "UPDATE uploads SET pinned = ?, expires_at = ?, data_version = data_version + 1 WHERE id = ?",
         )
-        .bind(&upload_id_blob[..])
-        .fetch_optional(pools.read()),
+        .bind(payload.pinned)
+        .bind(&expires_at)
+        .bind(&upload_uuid.as_bytes()[..])
+        .execute(pools.write()),
     )
     .await
-    {
-        Ok(Some(row)) => row,
-        Ok(None) => return ApiError::not_found("Upload not found").into_response(),
-        Err(e) => {
-            return e
-                .into_api_error("loading upload metadata", "Database error")
-                .into_response()
-        }
+    .map_err(|e| e.into_api_error("updating upload retention", "Database error"))?;
+
+    Ok(Proto::new(
+        load_upload_metadata(pools.read(), &upload_uuid).await?,
+    ))
+}
+
+/// Accepts a date (deleted at the start of that day, UTC) or an RFC 3339
+/// timestamp, which must be in the future.
+pub(crate) fn parse_expiry(value: &str) -> Result<String, ApiError> {
+    let value = value.trim();
+    let expires_at = match chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        Ok(date) => date.and_time(chrono::NaiveTime::MIN).and_utc(),
+        Err(_) => chrono::DateTime::parse_from_rfc3339(value)
+            .map_err(|_| {
+                ApiError::bad_request(
+                    "expires_at must be a date (YYYY-MM-DD) or RFC 3339 timestamp",
+                )
+            })?
+            .to_utc(),
     };
+    if expires_at <= chrono::Utc::now() {
+        return Err(ApiError::bad_request("expires_at must be in the future"));
+    }
+    Ok(expires_at.format(RETENTION_TIMESTAMP_FORMAT).to_string())
+}
 
-    let (filename, row_count, display_name, data_version) = metadata;
-    let title = effective_display_name(display_name, &filename);
+#[derive(FromRow)]
+struct UploadMetadataRow {
+    filename: String,
+    row_count: i64,
+    display_name: Option<String>,
+    data_version: i64,
+    is_collection: bool,
+    pinned: bool,
+    expires_at: Option<String>,
+    last_accessed_at: String,
+    password_protected: bool,
+}
 
-    Proto::new(pb::UploadMetadata {
-        upload_id,
-        filename,
-        row_count,
-        title,
-        data_version,
+pub(crate) async fn load_upload_metadata(
+    pool: &sqlx::SqlitePool,
+    upload_uuid: &Uuid,
+) -> Result<pb::UploadMetadata, ApiError> {
+    let row = db::query_with_timeout(
+        sqlx::query_as::<_, UploadMetadataRow>(
+            "SELECT filename, row_count, display_name, data_version, is_collection, pinned, expires_at, last_accessed_at,
+                view_password_hash IS NOT NULL AS password_protected
+            FROM uploads WHERE id = ?",
+        )
+        .bind(&upload_uuid.as_bytes()[..])
+        .fetch_optional(pool),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading upload metadata", "Database error"))?
+    .ok_or_else(|| ApiError::not_found("Upload not found"))?;
+
+    let deletion_scheduled_at =
+        deletion_scheduled_at(row.pinned, row.expires_at.as_deref(), &row.last_accessed_at);
+    Ok(pb::UploadMetadata {
+        upload_id: upload_uuid.to_string(),
+        title: effective_display_name(row.display_name, &row.filename),
+        filename: row.filename,
+        row_count: row.row_count,
+        data_version: row.data_version,
+        is_collection: row.is_collection,
+        pinned: row.pinned,
+        expires_at: row.expires_at,
+        deletion_scheduled_at,
+        shared: false,
+        password_protected: row.password_protected,
     })
-    .into_response()
+}
+
+/// When the retention task will delete an upload: never if it's pinned, on
+/// its expiry date if it has one, and otherwise once it has gone unviewed for
+/// the retention period.
+pub(crate) fn deletion_scheduled_at(
+    pinned: bool,
+    expires_at: Option<&str>,
+    last_accessed_at: &str,
+) -> Option<String> {
+    if pinned {
+        return None;
+    }
+    if let Some(expires_at) = expires_at {
+        return Some(expires_at.to_string());
+    }
+    let last_accessed =
+        chrono::NaiveDateTime::parse_from_str(last_accessed_at, RETENTION_TIMESTAMP_FORMAT).ok()?;
+    let scheduled =
+        last_accessed.checked_add_signed(chrono::Duration::days(config::retention_days()))?;
+    Some(scheduled.format(RETENTION_TIMESTAMP_FORMAT).to_string())
 }
 
 pub async fn update_csv(
     State(pools): State<DbPools>,
     Path(upload_id): Path<String>,
+    Query(query): Query<ImportQuery>,
     headers: axum::http::HeaderMap,
     Extension(writer_tracker): Extension<UploadUsageTracker>,
     mut multipart: Multipart,
@@ -731,22 +1910,37 @@ pub async fn update_csv(
         return response;
     }
 
-    let upload_uuid = match Uuid::parse_str(&upload_id) {
+    let upload_uuid = match parse_upload_id(&upload_id) {
         Ok(uuid) => uuid,
-        Err(_) => {
-            return ApiError::bad_request("Invalid upload_id format").into_response();
-        }
+        Err(err) => return err.into_response(),
     };
+    match is_collection(pools.read(), &upload_uuid).await {
+        Ok(false) => {}
+        Ok(true) => {
+            return ApiError::bad_request(
+                "Collections are built from their member uploads and can't be replaced",
+            )
+            .into_response();
+        }
+        Err(err) => return err.into_response(),
+    }
     while let Ok(Some(field)) = multipart.next_field().await {
         let filename = field
             .file_name()
             .map_or_else(|| "unknown".to_string(), ToString::to_string);
 
         if is_csv_or_zip_file(&filename) {
-            match ingestor.replace(upload_uuid, field, filename.clone()).await {
+            match ingestor
+                .replace(upload_uuid, field, filename.clone(), query)
+                .await
+            {
                 Ok(result) => {
                     invalidate_upload_cache(&result.upload_id).await;
                     invalidate_name_index_cache(&result.upload_id);
+                    match collections_containing(pools.read(), &upload_uuid.as_bytes()[..]).await {
+                        Ok(ids) => spawn_collection_rebuilds(pools.write().clone(), ids),
+                        Err(e) => e.log("finding collections to rebuild"),
+                    }
                     let response_title = default_display_name(&result.filename);
                     return (
                         axum::http::StatusCode::OK,
@@ -756,6 +1950,15 @@ pub async fn update_csv(
                             row_count: row_count_value(result.total_rows),
                             title: response_title,
                             data_version: result.data_version,
+                            format: result.format.to_string(),
+                            skipped_rows: row_count_value(result.skipped_rows),
+                            skip_summary: result.skip_summary,
+                            duplicate_rows: row_count_value(result.duplicate_rows),
+                            ignored_columns: result
+                                .ignored_columns
+                                .into_iter()
+                                .map(str::to_string)
+                                .collect(),
                         }),
                     )
                         .into_response();
@@ -768,24 +1971,103 @@ pub async fn update_csv(
     ApiError::bad_request("No CSV file found in upload").into_response()
 }
 
-pub async fn delete_upload(
+pub async fn append_csv(
     State(pools): State<DbPools>,
     Path(upload_id): Path<String>,
+    Query(query): Query<ImportQuery>,
     headers: axum::http::HeaderMap,
+    Extension(writer_tracker): Extension<UploadUsageTracker>,
+    mut multipart: Multipart,
 ) -> impl IntoResponse {
+    let ingestor = UploadIngestor::new(&pools, &writer_tracker);
     if let Err(response) = verify_edit_token(pools.read(), &headers, &upload_id).await {
         return response;
     }
 
-    let upload_uuid = match Uuid::parse_str(&upload_id) {
+    let upload_uuid = match parse_upload_id(&upload_id) {
         Ok(uuid) => uuid,
-        Err(_) => {
-            return ApiError::bad_request("Invalid upload_id format").into_response();
+        Err(err) => return err.into_response(),
+    };
+    match is_collection(pools.read(), &upload_uuid).await {
+        Ok(false) => {}
+        Ok(true) => {
+            return ApiError::bad_request(
+                "Collections are built from their member uploads and can't be appended to",
+            )
+            .into_response();
+        }
+        Err(err) => return err.into_response(),
+    }
+    while let Ok(Some(field)) = multipart.next_field().await {
+        let filename = field
+            .file_name()
+            .map_or_else(|| "unknown".to_string(), ToString::to_string);
+
+        if is_csv_or_zip_file(&filename) {
+            match ingestor.append(upload_uuid, field, query).await {
+                Ok(result) => {
+                    invalidate_upload_cache(&result.upload_id).await;
+                    invalidate_name_index_cache(&result.upload_id);
+                    match collections_containing(pools.read(), &upload_uuid.as_bytes()[..]).await {
+                        Ok(ids) => spawn_collection_rebuilds(pools.write().clone(), ids),
+                        Err(e) => e.log("finding collections to rebuild"),
+                    }
+                    return (
+                        axum::http::StatusCode::OK,
+                        Proto::new(pb::AppendResponse {
+                            upload_id: result.upload_id,
+                            row_count: result.row_count,
+                            data_version: result.data_version,
+                            format: result.format.to_string(),
+                            added_rows: row_count_value(result.added_rows),
+                            updated_rows: row_count_value(result.updated_rows),
+                            unchanged_rows: row_count_value(result.unchanged_rows),
+                            skipped_rows: row_count_value(result.skipped_rows),
+                            skip_summary: result.skip_summary,
+                            duplicate_rows: row_count_value(result.duplicate_rows),
+                            ignored_columns: result
+                                .ignored_columns
+                                .into_iter()
+                                .map(str::to_string)
+                                .collect(),
+                        }),
+                    )
+                        .into_response();
+                }
+                Err(err) => return err.into_response(),
+            }
         }
+    }
+
+    ApiError::bad_request("No CSV file found in upload").into_response()
+}
+
+pub async fn delete_upload(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    if let Err(response) = verify_edit_token(pools.read(), &headers, &upload_id).await {
+        return response;
+    }
+
+    let upload_uuid = match parse_upload_id(&upload_id) {
+        Ok(uuid) => uuid,
+        Err(err) => return err.into_response(),
     };
     let upload_id_blob = upload_uuid.as_bytes();
 
-    // CASCADE will delete associated sightings
+    let affected_collections = match collections_containing(pools.read(), &upload_id_blob[..]).await
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            return e
+                .into_api_error("finding collections for upload", "Database error")
+                .into_response()
+        }
+    };
+
+    // CASCADE will delete associated sightings and collection memberships
     match db::query_with_timeout(
         sqlx::query("DELETE FROM uploads WHERE id = ?")
             .bind(&upload_id_blob[..])
@@ -796,6 +2078,7 @@ pub async fn delete_upload(
         Ok(_) => {
             invalidate_upload_cache(&upload_id).await;
             invalidate_name_index_cache(&upload_id);
+            spawn_collection_rebuilds(pools.write().clone(), affected_collections);
 
             info!("Deleted upload: {}", upload_id);
             (
@@ -810,7 +2093,7 @@ pub async fn delete_upload(
     }
 }
 
-fn normalise_display_name(value: Option<String>) -> Result<String, ApiError> {
+pub(crate) fn normalise_display_name(value: Option<String>) -> Result<String, ApiError> {
     let Some(raw) = value else {
         return Err(ApiError::bad_request("display_name is required"));
     };
@@ -854,6 +2137,20 @@ pub(crate) fn effective_display_name(stored: Option<String>, filename: &str) ->
     }
 }
 
+pub(crate) async fn is_collection(
+    pool: &sqlx::SqlitePool,
+    upload_uuid: &Uuid,
+) -> Result<bool, ApiError> {
+    db::query_with_timeout(
+        sqlx::query_scalar::<_, bool>("SELECT is_collection FROM uploads WHERE id = ?")
+            .bind(&upload_uuid.as_bytes()[..])
+            .fetch_optional(pool),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading upload kind", "Database error"))?
+    .ok_or_else(|| ApiError::not_found("Upload not found"))
+}
+
 pub async fn get_upload_data_version(
     pool: &sqlx::SqlitePool,
     upload_uuid: &Uuid,
@@ -879,12 +2176,32 @@ pub async fn delete_old_uploads(
         .ok_or_else(|| {
             DbQueryError::Sqlx(sqlx::Error::Decode("Invalid retention period".into()))
         })?;
-    let cutoff_str = cutoff_date.format("%Y-%m-%dT%H:%M:%SZ").to_string();
-
+    let cutoff_str = cutoff_date.format(RETENTION_TIMESTAMP_FORMAT).to_string();
+    let now_str = chrono::Utc::now()
+        .format(RETENTION_TIMESTAMP_FORMAT)
+        .to_string();
+
+    // Pinned uploads are never due. Unpinned ones are due on their expiry
+    // date if they have one, and otherwise once they've gone unviewed for the
+    // retention period, unless they're a member of a collection that isn't
+    // due itself.
     let rows = db::query_with_timeout(
-        sqlx::query("SELECT id FROM uploads WHERE last_accessed_at < ?")
-            .bind(&cutoff_str)
-            .fetch_all(pool),
+        sqlx::query(
+            "WITH due AS (
+                SELECT id FROM uploads
+                WHERE pinned = 0
+                AND CASE WHEN expires_at IS NULL THEN last_accessed_at < ? ELSE expires_at <= ? END
+            )
+            SELECT id FROM uploads
+            WHERE id IN due
+            AND (expires_at IS NOT NULL OR id NOT IN (
+                SELECT upload_id FROM collection_members
+                WHERE collection_id NOT IN due
+            ))",
+        )
+        .bind(&cutoff_str)
+        .bind(&now_str)
+        .fetch_all(pool),
     )
     .await?;
 
@@ -893,6 +2210,9 @@ pub async fn delete_old_uploads(
         let id_blob: Vec<u8> = row.get("id");
         if let Ok(upload_uuid) = Uuid::from_slice(&id_blob) {
             let upload_id = upload_uuid.to_string();
+            let affected_collections = collections_containing(pool, &id_blob[..])
+                .await
+                .unwrap_or_default();
             match db::query_with_timeout(
                 sqlx::query("DELETE FROM uploads WHERE id = ?")
                     .bind(&id_blob[..])
@@ -903,6 +2223,7 @@ pub async fn delete_old_uploads(
                 Ok(_) => {
                     invalidate_upload_cache(&upload_id).await;
                     invalidate_name_index_cache(&upload_id);
+                    spawn_collection_rebuilds(pool.clone(), affected_collections);
                     deleted_count += 1;
                     info!("Auto-deleted old upload: {}", upload_id);
                 }
@@ -0,0 +1,331 @@
+//! Optional view passwords. An upload with one can only be read by someone
+//! who has unlocked it, or who sends its edit token. Unlocking hands out a
+//! signed session, both as a cookie for same-site deployments and in the
+//! response body for clients to send back in the `X-View-Session` header.
+//! Sessions are tied to the password they were issued for, so changing or
+//! removing it ends them. Guessing is slowed by Argon2 and by limiting
+//! attempts per client and upload; once the limit is reached every attempt
+//! is refused until the window ends, the right password included.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Argon2, Params, ARGON2ID_IDENT};
+use axum::extract::{Extension, Path, State};
+use axum::http::{header, HeaderMap, HeaderValue};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use moka::future::Cache;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::collections::ensure_not_collection_member;
+use crate::config;
+use crate::db::{self, DbPools};
+use crate::error::ApiError;
+use crate::proto::{pb, Proto};
+use crate::shares::resolve_upload_uuid;
+use crate::upload::{check_edit_token, parse_upload_id, RETENTION_TIMESTAMP_FORMAT};
+
+pub const VIEW_SESSION_HEADER: &str = "x-view-session";
+const SESSION_COOKIE_PREFIX: &str = "redgrouse_view_";
+const SESSION_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const MIN_PASSWORD_LENGTH: usize = 6;
+const MAX_PASSWORD_LENGTH: usize = 256;
+// Attempts allowed from one client for one upload without a right password
+// before any more are refused for the window
+const MAX_FAILED_UNLOCKS: u32 = 10;
+const FAILED_UNLOCK_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+type HmacSha256 = Hmac<Sha256>;
+
+static SESSION_SECRET: Lazy<Vec<u8>> = Lazy::new(|| match config::session_secret() {
+    Some(secret) => secret.into_bytes(),
+    None => {
+        warn!("REDGROUSE_SESSION_SECRET is not set; view password sessions end and backups can't be restored after a restart");
+        let mut secret = Uuid::new_v4().as_bytes().to_vec();
+        secret.extend_from_slice(Uuid::new_v4().as_bytes());
+        secret
+    }
+});
+
+/// Keyed by client IP and upload, so someone guessing can't lock everyone
+/// else out of an upload.
+static FAILED_UNLOCKS: Lazy<Cache<(String, Uuid), Arc<AtomicU32>>> = Lazy::new(|| {
+    Cache::builder()
+        .max_capacity(100_000)
+        .time_to_live(FAILED_UNLOCK_WINDOW)
+        .support_invalidation_closures()
+        .build()
+});
+
+/// Unlike edit tokens, which are random, passwords are chosen by people and
+/// can be guessed, so they're stored as Argon2id hashes (a PHC string with
+/// its own salt) that make every guess at a leaked hash slow.
+fn hash_password(password: &str) -> Result<String, ApiError> {
+    let salt = SaltString::encode_b64(Uuid::new_v4().as_bytes())
+        .map_err(|e| ApiError::internal(format!("Failed to salt password: {e}")))?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| ApiError::internal(format!("Failed to hash password: {e}")))
+}
+
+fn verify_password(password: &str, stored_hash: &str) -> bool {
+    PasswordHash::new(stored_hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// Whether a hash from outside, such as a restored backup, is one
+/// [`hash_password`] could have made. Other algorithms and cost parameters
+/// are refused, so a crafted hash can't make checking a password cheap or
+/// make every unlock take a huge amount of memory.
+pub(crate) fn is_password_hash(stored_hash: &str) -> bool {
+    let Ok(hash) = PasswordHash::new(stored_hash) else {
+        return false;
+    };
+    let expected = Params::default();
+    hash.algorithm == ARGON2ID_IDENT
+        && Params::try_from(&hash).is_ok_and(|params| {
+            params.m_cost() == expected.m_cost()
+                && params.t_cost() == expected.t_cost()
+                && params.p_cost() == expected.p_cost()
+        })
+}
+
+/// Argon2 takes tens of milliseconds on purpose, so it runs off the async
+/// workers.
+async fn run_blocking<T: Send + 'static>(
+    task: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, ApiError> {
+    tokio::task::spawn_blocking(task)
+        .await
+        .map_err(|e| ApiError::internal(format!("Password hashing task failed: {e}")))
+}
+
+/// Key for everything the server signs: view password sessions and backup
+/// archives.
+pub(crate) fn session_secret() -> &'static [u8] {
+    &SESSION_SECRET
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+fn session_signature(upload_uuid: &Uuid, password_hash: &str, expires: i64) -> String {
+    let message = format!("{upload_uuid}:{expires}:{password_hash}");
+    hex::encode(hmac_sha256(&SESSION_SECRET, message.as_bytes()))
+}
+
+/// Sessions look like `<expiry as unix seconds>.<signature>`.
+fn sign_session(upload_uuid: &Uuid, password_hash: &str, expires: i64) -> String {
+    format!(
+        "{expires}.{}",
+        session_signature(upload_uuid, password_hash, expires)
+    )
+}
+
+fn session_is_valid(session: &str, upload_uuid: &Uuid, password_hash: &str) -> bool {
+    let Some((expires, signature)) = session.split_once('.') else {
+        return false;
+    };
+    let Ok(expires) = expires.parse::<i64>() else {
+        return false;
+    };
+    if expires <= Utc::now().timestamp() {
+        return false;
+    }
+    let expected = session_signature(upload_uuid, password_hash, expires);
+    expected.as_bytes().ct_eq(signature.as_bytes()).into()
+}
+
+fn cookie_name(upload_uuid: &Uuid) -> String {
+    format!("{SESSION_COOKIE_PREFIX}{}", upload_uuid.simple())
+}
+
+fn session_cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// Whether the request carries an unexpired session for the upload's
+/// current password, in the `X-View-Session` header or the upload's cookie.
+pub(crate) fn has_view_session(
+    headers: &HeaderMap,
+    upload_uuid: &Uuid,
+    password_hash: &str,
+) -> bool {
+    let from_header = headers
+        .get(VIEW_SESSION_HEADER)
+        .and_then(|value| value.to_str().ok());
+    let from_cookie = session_cookie(headers, &cookie_name(upload_uuid));
+    [from_header, from_cookie]
+        .into_iter()
+        .flatten()
+        .any(|session| session_is_valid(session.trim(), upload_uuid, password_hash))
+}
+
+async fn load_password_hash(
+    pool: &sqlx::SqlitePool,
+    upload_uuid: &Uuid,
+) -> Result<Option<String>, ApiError> {
+    db::query_with_timeout(
+        sqlx::query_scalar::<_, Option<String>>(
+            "SELECT view_password_hash FROM uploads WHERE id = ?",
+        )
+        .bind(&upload_uuid.as_bytes()[..])
+        .fetch_optional(pool),
+    )
+    .await
+    .map_err(|e| e.into_api_error("loading view password", "Database error"))?
+    .ok_or_else(|| ApiError::not_found("Upload not found"))
+}
+
+#[derive(Deserialize)]
+pub struct ViewPasswordPayload {
+    password: Option<String>,
+}
+
+pub async fn set_view_password(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<ViewPasswordPayload>,
+) -> Result<Proto<pb::ViewPasswordStatus>, ApiError> {
+    check_edit_token(pools.read(), &headers, &upload_id).await?;
+    let upload_uuid = parse_upload_id(&upload_id)?;
+
+    let password_hash = match payload.password.filter(|p| !p.is_empty()) {
+        Some(password) => {
+            let length = password.chars().count();
+            if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&length) {
+                return Err(ApiError::bad_request(format!(
+                    "Password must be {MIN_PASSWORD_LENGTH} to {MAX_PASSWORD_LENGTH} characters"
+                )));
+            }
+            Some(run_blocking(move || hash_password(&password)).await??)
+        }
+        None => None,
+    };
+    if password_hash.is_some() {
+        ensure_not_collection_member(pools.read(), &upload_uuid).await?;
+    }
+
+    let result = db::query_with_timeout(
+        sqlx::query("UPDATE uploads SET view_password_hash = ? WHERE id = ?")
+            .bind(&password_hash)
+            .bind(&upload_uuid.as_bytes()[..])
+            .execute(pools.write()),
+    )
+    .await
+    .map_err(|e| e.into_api_error("setting view password", "Database error"))?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("Upload not found"));
+    }
+    if let Err(e) = FAILED_UNLOCKS.invalidate_entries_if(move |(_, uuid), _| *uuid == upload_uuid) {
+        warn!("Failed to reset unlock attempts for {upload_uuid}: {e}");
+    }
+
+    Ok(Proto::new(pb::ViewPasswordStatus {
+        password_protected: password_hash.is_some(),
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct UnlockPayload {
+    password: String,
+}
+
+/// Accepts an upload ID or a share token in the path, so people given a
+/// share link to a protected upload can unlock it too.
+pub async fn unlock_upload(
+    State(pools): State<DbPools>,
+    Path(upload_id): Path<String>,
+    client_ip: Option<Extension<String>>,
+    Json(payload): Json<UnlockPayload>,
+) -> Result<Response, ApiError> {
+    let upload_uuid = resolve_upload_uuid(pools.read(), &upload_id).await?;
+    let Some(password_hash) = load_password_hash(pools.read(), &upload_uuid).await? else {
+        return Err(ApiError::bad_request("This upload doesn't have a password"));
+    };
+    let client_ip = client_ip.map_or_else(|| "unknown".to_string(), |Extension(ip)| ip);
+    let attempts_key = (client_ip, upload_uuid);
+
+    // Counted before checking, so a locked out client learns nothing from
+    // its guesses and concurrent guesses can't slip past the limit
+    let attempts = FAILED_UNLOCKS
+        .get_with(attempts_key.clone(), async { Arc::new(AtomicU32::new(0)) })
+        .await;
+    if attempts.fetch_add(1, Ordering::Relaxed) >= MAX_FAILED_UNLOCKS {
+        return Err(ApiError::too_many_requests(
+            "Too many wrong passwords; try again later",
+        ));
+    }
+
+    // Nothing longer can have been set, and Argon2 over huge inputs is slow
+    let password = payload.password;
+    let matches = password.chars().count() <= MAX_PASSWORD_LENGTH && {
+        let stored_hash = password_hash.clone();
+        run_blocking(move || verify_password(&password, &stored_hash)).await?
+    };
+    if !matches {
+        return Err(ApiError::forbidden("Wrong password"));
+    }
+    FAILED_UNLOCKS.invalidate(&attempts_key).await;
+
+    let expires_at = Utc::now() + SESSION_TTL;
+    let session = sign_session(&upload_uuid, &password_hash, expires_at.timestamp());
+    let cookie = format!(
+        "{}={session}; Max-Age={}; Path=/api; HttpOnly; Secure; SameSite=Lax",
+        cookie_name(&upload_uuid),
+        SESSION_TTL.as_secs()
+    );
+    let cookie = HeaderValue::from_str(&cookie)
+        .map_err(|_| ApiError::internal("Failed to build session cookie"))?;
+
+    let mut response = Proto::new(pb::ViewSession {
+        session,
+        expires_at: expires_at.format(RETENTION_TIMESTAMP_FORMAT).to_string(),
+    })
+    .into_response();
+    response.headers_mut().insert(header::SET_COOKIE, cookie);
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upload::hash_token;
+
+    #[test]
+    fn passwords_are_stored_with_argon2() {
+        let hash = hash_password("correct horse").unwrap_or_default();
+        assert!(hash.starts_with("$argon2id$"));
+        assert_ne!(hash, hash_password("correct horse").unwrap_or_default());
+        assert!(verify_password("correct horse", &hash));
+        assert!(!verify_password("battery staple", &hash));
+        assert!(is_password_hash(&hash));
+        assert!(!is_password_hash(&hash.replace("m=19456", "m=4194304")));
+        assert!(!is_password_hash(&hash_token("correct horse")));
+    }
+}
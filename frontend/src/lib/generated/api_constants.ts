@@ -7,9 +7,33 @@ export const UPLOAD_ROUTE = "/api/uploads";
 export const UPLOAD_DETAILS_ROUTE = "/api/uploads/{upload_id}";
 export const UPLOAD_COUNT_ROUTE = "/api/uploads/{upload_id}/count";
 export const UPLOAD_BBOX_ROUTE = "/api/uploads/{upload_id}/bbox";
+export const UPLOAD_APPEND_ROUTE = "/api/uploads/{upload_id}/append";
+export const UPLOAD_BACKUP_ROUTE = "/api/uploads/{upload_id}/backup";
+export const UPLOAD_RESTORE_ROUTE = "/api/uploads/restore";
+export const UPLOAD_RETENTION_ROUTE = "/api/uploads/{upload_id}/retention";
+export const UPLOAD_SHARES_ROUTE = "/api/uploads/{upload_id}/shares";
+export const UPLOAD_SHARE_ROUTE = "/api/uploads/{upload_id}/shares/{share_id}";
+export const UPLOAD_LOCATION_PRIVACY_ROUTE = "/api/uploads/{upload_id}/location-privacy";
+export const UPLOAD_EDIT_TOKENS_ROUTE = "/api/uploads/{upload_id}/edit-tokens";
+export const UPLOAD_EDIT_TOKEN_ROUTE = "/api/uploads/{upload_id}/edit-tokens/{token_id}";
+export const UPLOAD_EDIT_TOKEN_ROTATE_ROUTE = "/api/uploads/{upload_id}/edit-tokens/rotate";
+export const UPLOAD_PASSWORD_ROUTE = "/api/uploads/{upload_id}/password";
+export const UPLOAD_UNLOCK_ROUTE = "/api/uploads/{upload_id}/unlock";
 export const UPLOAD_SIGHTINGS_ROUTE = "/api/uploads/{upload_id}/sightings";
+export const UPLOAD_SIGHTING_ROUTE = "/api/uploads/{upload_id}/sightings/{sighting_id}";
 export const UPLOAD_STATS_ROUTE = "/api/uploads/{upload_id}/stats";
+export const UPLOAD_IMPORT_REPORT_ROUTE = "/api/uploads/{upload_id}/import-report";
+export const UPLOAD_EXPORT_GEOJSON_ROUTE = "/api/uploads/{upload_id}/export.geojson";
+export const UPLOAD_EXPORT_KML_ROUTE = "/api/uploads/{upload_id}/export.kml";
+export const UPLOAD_EXPORT_CSV_ROUTE = "/api/uploads/{upload_id}/export.csv";
+export const UPLOAD_MAP_PNG_ROUTE = "/api/uploads/{upload_id}/map.png";
+export const UPLOAD_MAP_SVG_ROUTE = "/api/uploads/{upload_id}/map.svg";
+export const COLLECTIONS_ROUTE = "/api/collections";
+export const COLLECTION_DETAILS_ROUTE = "/api/collections/{collection_id}";
+export const COLLECTION_MEMBER_ROUTE = "/api/collections/{collection_id}/members/{upload_id}";
 export const TILE_ROUTE = "/api/tiles/{upload_id}/{z}/{x}/{y}";
+export const DENSITY_TILE_ROUTE = "/api/tiles/{upload_id}/density/{z}/{x}/{y}";
+export const CLUSTER_TILE_ROUTE = "/api/tiles/{upload_id}/clusters/{z}/{x}/{y}";
 export const FIELDS_ROUTE = "/api/fields";
 export const FIELD_VALUES_ROUTE = "/api/uploads/{upload_id}/fields/{field}";
 export const DEFAULT_PAGE_SIZE = 100;
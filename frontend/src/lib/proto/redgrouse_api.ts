@@ -20,6 +20,12 @@ export interface UploadMetadata {
   rowCount: number;
   title: string;
   dataVersion: number;
+  isCollection: boolean;
+  pinned: boolean;
+  expiresAt?: string | undefined;
+  deletionScheduledAt?: string | undefined;
+  shared: boolean;
+  passwordProtected: boolean;
 }
 
 export interface UploadResponse {
@@ -29,6 +35,12 @@ export interface UploadResponse {
   editToken: string;
   title: string;
   dataVersion: number;
+  format: string;
+  skippedRows: number;
+  skipSummary: ImportIssueCount[];
+  duplicateRows: number;
+  ignoredColumns: string[];
+  namedEditTokens: EditToken[];
 }
 
 export interface UpdateResponse {
@@ -37,6 +49,114 @@ export interface UpdateResponse {
   rowCount: number;
   title: string;
   dataVersion: number;
+  format: string;
+  skippedRows: number;
+  skipSummary: ImportIssueCount[];
+  duplicateRows: number;
+  ignoredColumns: string[];
+}
+
+export interface AppendResponse {
+  uploadId: string;
+  rowCount: number;
+  dataVersion: number;
+  format: string;
+  addedRows: number;
+  updatedRows: number;
+  unchangedRows: number;
+  skippedRows: number;
+  skipSummary: ImportIssueCount[];
+  duplicateRows: number;
+  ignoredColumns: string[];
+}
+
+export interface CollectionResponse {
+  collectionId: string;
+  title: string;
+  rowCount: number;
+  dataVersion: number;
+  memberUploadIds: string[];
+  editToken?: string | undefined;
+}
+
+export interface ShareLink {
+  shareId: number;
+  token?: string | undefined;
+  label?: string | undefined;
+  filter?: string | undefined;
+  hideLocations: boolean;
+  createdAt: string;
+  expiresAt?: string | undefined;
+}
+
+export interface ShareLinkList {
+  shares: ShareLink[];
+}
+
+export interface EditToken {
+  tokenId?: number | undefined;
+  name?: string | undefined;
+  token?: string | undefined;
+  createdAt: string;
+  current: boolean;
+}
+
+export interface EditTokenList {
+  tokens: EditToken[];
+}
+
+export interface ViewPasswordStatus {
+  passwordProtected: boolean;
+}
+
+export interface ViewSession {
+  session: string;
+  expiresAt: string;
+}
+
+export interface LocationRule {
+  scientificName: string;
+  mode: string;
+  km?: number | undefined;
+  commonName?: string | undefined;
+}
+
+export interface LocationPrivacy {
+  mode: string;
+  km?: number | undefined;
+  sensitiveSpecies: boolean;
+  species: LocationRule[];
+  bundledSpecies: LocationRule[];
+  dataVersion: number;
+}
+
+export interface SightingEditResponse {
+  sightingId: number;
+  dataVersion: number;
+  deleted: boolean;
+}
+
+export interface ImportIssueCount {
+  column: string;
+  reason: string;
+  count: number;
+}
+
+export interface ImportIssue {
+  row: number;
+  column: string;
+  reason: string;
+  dataVersion: number;
+}
+
+export interface ImportReport {
+  uploadId: string;
+  format: string;
+  skippedRows: number;
+  issues: ImportIssue[];
+  truncated: boolean;
+  dataVersion: number;
+  unmatchedSpecies: SpeciesCount[];
 }
 
 export interface DeleteResponse {
@@ -75,14 +195,15 @@ export interface BboxResponse {
 export interface Species {
   commonName: string;
   scientificName: string;
+  taxonId?: number | undefined;
 }
 
 export interface Sighting {
   id: number;
   commonNameIndex?: number | undefined;
   count?: number | undefined;
-  latitude: number;
-  longitude: number;
+  latitude?: number | undefined;
+  longitude?: number | undefined;
   countryCode?: string | undefined;
   regionCode?: string | undefined;
   observedAt: string;
@@ -117,6 +238,13 @@ export interface SpeciesCount {
   count: number;
 }
 
+export interface TaxonRankCount {
+  name: string;
+  species: number;
+  sightings: number;
+  lifers: number;
+}
+
 export interface CountryStats {
   countryCode: string;
   sightings: number;
@@ -147,6 +275,8 @@ export interface StatsResponse {
   lifersTimeline: TimelinePoint[];
   sightingsTimeline: TimelinePoint[];
   longestStreakDays: number;
+  familyStats: TaxonRankCount[];
+  orderStats: TaxonRankCount[];
 }
 
 function createBaseApiErrorBody(): ApiErrorBody {
@@ -164,27 +294,1518 @@ export const ApiErrorBody: MessageFns<ApiErrorBody> = {
     return writer;
   },
 
-  decode(input: BinaryReader | Uint8Array, length?: number): ApiErrorBody {
+  decode(input: BinaryReader | Uint8Array, length?: number): ApiErrorBody {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseApiErrorBody();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.error = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag !== 18) {
+            break;
+          }
+
+          message.code = reader.string();
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<ApiErrorBody>, I>>(base?: I): ApiErrorBody {
+    return ApiErrorBody.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<ApiErrorBody>, I>>(object: I): ApiErrorBody {
+    const message = createBaseApiErrorBody();
+    message.error = object.error ?? "";
+    message.code = object.code ?? undefined;
+    return message;
+  },
+};
+
+function createBaseUploadMetadata(): UploadMetadata {
+  return {
+    uploadId: "",
+    filename: "",
+    rowCount: 0,
+    title: "",
+    dataVersion: 0,
+    isCollection: false,
+    pinned: false,
+    expiresAt: undefined,
+    deletionScheduledAt: undefined,
+    shared: false,
+    passwordProtected: false,
+  };
+}
+
+export const UploadMetadata: MessageFns<UploadMetadata> = {
+  encode(message: UploadMetadata, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.uploadId !== "") {
+      writer.uint32(10).string(message.uploadId);
+    }
+    if (message.filename !== "") {
+      writer.uint32(18).string(message.filename);
+    }
+    if (message.rowCount !== 0) {
+      writer.uint32(24).int64(message.rowCount);
+    }
+    if (message.title !== "") {
+      writer.uint32(34).string(message.title);
+    }
+    if (message.dataVersion !== 0) {
+      writer.uint32(40).int64(message.dataVersion);
+    }
+    if (message.isCollection !== false) {
+      writer.uint32(48).bool(message.isCollection);
+    }
+    if (message.pinned !== false) {
+      writer.uint32(56).bool(message.pinned);
+    }
+    if (message.expiresAt !== undefined) {
+      writer.uint32(66).string(message.expiresAt);
+    }
+    if (message.deletionScheduledAt !== undefined) {
+      writer.uint32(74).string(message.deletionScheduledAt);
+    }
+    if (message.shared !== false) {
+      writer.uint32(80).bool(message.shared);
+    }
+    if (message.passwordProtected !== false) {
+      writer.uint32(88).bool(message.passwordProtected);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): UploadMetadata {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseUploadMetadata();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.uploadId = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag !== 18) {
+            break;
+          }
+
+          message.filename = reader.string();
+          continue;
+        }
+        case 3: {
+          if (tag !== 24) {
+            break;
+          }
+
+          message.rowCount = longToNumber(reader.int64());
+          continue;
+        }
+        case 4: {
+          if (tag !== 34) {
+            break;
+          }
+
+          message.title = reader.string();
+          continue;
+        }
+        case 5: {
+          if (tag !== 40) {
+            break;
+          }
+
+          message.dataVersion = longToNumber(reader.int64());
+          continue;
+        }
+        case 6: {
+          if (tag !== 48) {
+            break;
+          }
+
+          message.isCollection = reader.bool();
+          continue;
+        }
+        case 7: {
+          if (tag !== 56) {
+            break;
+          }
+
+          message.pinned = reader.bool();
+          continue;
+        }
+        case 8: {
+          if (tag !== 66) {
+            break;
+          }
+
+          message.expiresAt = reader.string();
+          continue;
+        }
+        case 9: {
+          if (tag !== 74) {
+            break;
+          }
+
+          message.deletionScheduledAt = reader.string();
+          continue;
+        }
+        case 10: {
+          if (tag !== 80) {
+            break;
+          }
+
+          message.shared = reader.bool();
+          continue;
+        }
+        case 11: {
+          if (tag !== 88) {
+            break;
+          }
+
+          message.passwordProtected = reader.bool();
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<UploadMetadata>, I>>(base?: I): UploadMetadata {
+    return UploadMetadata.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<UploadMetadata>, I>>(object: I): UploadMetadata {
+    const message = createBaseUploadMetadata();
+    message.uploadId = object.uploadId ?? "";
+    message.filename = object.filename ?? "";
+    message.rowCount = object.rowCount ?? 0;
+    message.title = object.title ?? "";
+    message.dataVersion = object.dataVersion ?? 0;
+    message.isCollection = object.isCollection ?? false;
+    message.pinned = object.pinned ?? false;
+    message.expiresAt = object.expiresAt ?? undefined;
+    message.deletionScheduledAt = object.deletionScheduledAt ?? undefined;
+    message.shared = object.shared ?? false;
+    message.passwordProtected = object.passwordProtected ?? false;
+    return message;
+  },
+};
+
+function createBaseUploadResponse(): UploadResponse {
+  return {
+    uploadId: "",
+    filename: "",
+    rowCount: 0,
+    editToken: "",
+    title: "",
+    dataVersion: 0,
+    format: "",
+    skippedRows: 0,
+    skipSummary: [],
+    duplicateRows: 0,
+    ignoredColumns: [],
+    namedEditTokens: [],
+  };
+}
+
+export const UploadResponse: MessageFns<UploadResponse> = {
+  encode(message: UploadResponse, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.uploadId !== "") {
+      writer.uint32(10).string(message.uploadId);
+    }
+    if (message.filename !== "") {
+      writer.uint32(18).string(message.filename);
+    }
+    if (message.rowCount !== 0) {
+      writer.uint32(24).int64(message.rowCount);
+    }
+    if (message.editToken !== "") {
+      writer.uint32(34).string(message.editToken);
+    }
+    if (message.title !== "") {
+      writer.uint32(42).string(message.title);
+    }
+    if (message.dataVersion !== 0) {
+      writer.uint32(48).int64(message.dataVersion);
+    }
+    if (message.format !== "") {
+      writer.uint32(58).string(message.format);
+    }
+    if (message.skippedRows !== 0) {
+      writer.uint32(64).int64(message.skippedRows);
+    }
+    for (const v of message.skipSummary) {
+      ImportIssueCount.encode(v!, writer.uint32(74).fork()).join();
+    }
+    if (message.duplicateRows !== 0) {
+      writer.uint32(80).int64(message.duplicateRows);
+    }
+    for (const v of message.ignoredColumns) {
+      writer.uint32(90).string(v!);
+    }
+    for (const v of message.namedEditTokens) {
+      EditToken.encode(v!, writer.uint32(98).fork()).join();
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): UploadResponse {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseUploadResponse();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.uploadId = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag !== 18) {
+            break;
+          }
+
+          message.filename = reader.string();
+          continue;
+        }
+        case 3: {
+          if (tag !== 24) {
+            break;
+          }
+
+          message.rowCount = longToNumber(reader.int64());
+          continue;
+        }
+        case 4: {
+          if (tag !== 34) {
+            break;
+          }
+
+          message.editToken = reader.string();
+          continue;
+        }
+        case 5: {
+          if (tag !== 42) {
+            break;
+          }
+
+          message.title = reader.string();
+          continue;
+        }
+        case 6: {
+          if (tag !== 48) {
+            break;
+          }
+
+          message.dataVersion = longToNumber(reader.int64());
+          continue;
+        }
+        case 7: {
+          if (tag !== 58) {
+            break;
+          }
+
+          message.format = reader.string();
+          continue;
+        }
+        case 8: {
+          if (tag !== 64) {
+            break;
+          }
+
+          message.skippedRows = longToNumber(reader.int64());
+          continue;
+        }
+        case 9: {
+          if (tag !== 74) {
+            break;
+          }
+
+          message.skipSummary.push(ImportIssueCount.decode(reader, reader.uint32()));
+          continue;
+        }
+        case 10: {
+          if (tag !== 80) {
+            break;
+          }
+
+          message.duplicateRows = longToNumber(reader.int64());
+          continue;
+        }
+        case 11: {
+          if (tag !== 90) {
+            break;
+          }
+
+          message.ignoredColumns.push(reader.string());
+          continue;
+        }
+        case 12: {
+          if (tag !== 98) {
+            break;
+          }
+
+          message.namedEditTokens.push(EditToken.decode(reader, reader.uint32()));
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<UploadResponse>, I>>(base?: I): UploadResponse {
+    return UploadResponse.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<UploadResponse>, I>>(object: I): UploadResponse {
+    const message = createBaseUploadResponse();
+    message.uploadId = object.uploadId ?? "";
+    message.filename = object.filename ?? "";
+    message.rowCount = object.rowCount ?? 0;
+    message.editToken = object.editToken ?? "";
+    message.title = object.title ?? "";
+    message.dataVersion = object.dataVersion ?? 0;
+    message.format = object.format ?? "";
+    message.skippedRows = object.skippedRows ?? 0;
+    message.skipSummary = object.skipSummary?.map((e) => ImportIssueCount.fromPartial(e)) || [];
+    message.duplicateRows = object.duplicateRows ?? 0;
+    message.ignoredColumns = object.ignoredColumns?.map((e) => e) || [];
+    message.namedEditTokens = object.namedEditTokens?.map((e) => EditToken.fromPartial(e)) || [];
+    return message;
+  },
+};
+
+function createBaseUpdateResponse(): UpdateResponse {
+  return {
+    uploadId: "",
+    filename: "",
+    rowCount: 0,
+    title: "",
+    dataVersion: 0,
+    format: "",
+    skippedRows: 0,
+    skipSummary: [],
+    duplicateRows: 0,
+    ignoredColumns: [],
+  };
+}
+
+export const UpdateResponse: MessageFns<UpdateResponse> = {
+  encode(message: UpdateResponse, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.uploadId !== "") {
+      writer.uint32(10).string(message.uploadId);
+    }
+    if (message.filename !== "") {
+      writer.uint32(18).string(message.filename);
+    }
+    if (message.rowCount !== 0) {
+      writer.uint32(24).int64(message.rowCount);
+    }
+    if (message.title !== "") {
+      writer.uint32(34).string(message.title);
+    }
+    if (message.dataVersion !== 0) {
+      writer.uint32(40).int64(message.dataVersion);
+    }
+    if (message.format !== "") {
+      writer.uint32(50).string(message.format);
+    }
+    if (message.skippedRows !== 0) {
+      writer.uint32(56).int64(message.skippedRows);
+    }
+    for (const v of message.skipSummary) {
+      ImportIssueCount.encode(v!, writer.uint32(66).fork()).join();
+    }
+    if (message.duplicateRows !== 0) {
+      writer.uint32(72).int64(message.duplicateRows);
+    }
+    for (const v of message.ignoredColumns) {
+      writer.uint32(82).string(v!);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): UpdateResponse {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseUpdateResponse();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.uploadId = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag !== 18) {
+            break;
+          }
+
+          message.filename = reader.string();
+          continue;
+        }
+        case 3: {
+          if (tag !== 24) {
+            break;
+          }
+
+          message.rowCount = longToNumber(reader.int64());
+          continue;
+        }
+        case 4: {
+          if (tag !== 34) {
+            break;
+          }
+
+          message.title = reader.string();
+          continue;
+        }
+        case 5: {
+          if (tag !== 40) {
+            break;
+          }
+
+          message.dataVersion = longToNumber(reader.int64());
+          continue;
+        }
+        case 6: {
+          if (tag !== 50) {
+            break;
+          }
+
+          message.format = reader.string();
+          continue;
+        }
+        case 7: {
+          if (tag !== 56) {
+            break;
+          }
+
+          message.skippedRows = longToNumber(reader.int64());
+          continue;
+        }
+        case 8: {
+          if (tag !== 66) {
+            break;
+          }
+
+          message.skipSummary.push(ImportIssueCount.decode(reader, reader.uint32()));
+          continue;
+        }
+        case 9: {
+          if (tag !== 72) {
+            break;
+          }
+
+          message.duplicateRows = longToNumber(reader.int64());
+          continue;
+        }
+        case 10: {
+          if (tag !== 82) {
+            break;
+          }
+
+          message.ignoredColumns.push(reader.string());
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<UpdateResponse>, I>>(base?: I): UpdateResponse {
+    return UpdateResponse.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<UpdateResponse>, I>>(object: I): UpdateResponse {
+    const message = createBaseUpdateResponse();
+    message.uploadId = object.uploadId ?? "";
+    message.filename = object.filename ?? "";
+    message.rowCount = object.rowCount ?? 0;
+    message.title = object.title ?? "";
+    message.dataVersion = object.dataVersion ?? 0;
+    message.format = object.format ?? "";
+    message.skippedRows = object.skippedRows ?? 0;
+    message.skipSummary = object.skipSummary?.map((e) => ImportIssueCount.fromPartial(e)) || [];
+    message.duplicateRows = object.duplicateRows ?? 0;
+    message.ignoredColumns = object.ignoredColumns?.map((e) => e) || [];
+    return message;
+  },
+};
+
+function createBaseAppendResponse(): AppendResponse {
+  return {
+    uploadId: "",
+    rowCount: 0,
+    dataVersion: 0,
+    format: "",
+    addedRows: 0,
+    updatedRows: 0,
+    unchangedRows: 0,
+    skippedRows: 0,
+    skipSummary: [],
+    duplicateRows: 0,
+    ignoredColumns: [],
+  };
+}
+
+export const AppendResponse: MessageFns<AppendResponse> = {
+  encode(message: AppendResponse, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.uploadId !== "") {
+      writer.uint32(10).string(message.uploadId);
+    }
+    if (message.rowCount !== 0) {
+      writer.uint32(16).int64(message.rowCount);
+    }
+    if (message.dataVersion !== 0) {
+      writer.uint32(24).int64(message.dataVersion);
+    }
+    if (message.format !== "") {
+      writer.uint32(34).string(message.format);
+    }
+    if (message.addedRows !== 0) {
+      writer.uint32(40).int64(message.addedRows);
+    }
+    if (message.updatedRows !== 0) {
+      writer.uint32(48).int64(message.updatedRows);
+    }
+    if (message.unchangedRows !== 0) {
+      writer.uint32(56).int64(message.unchangedRows);
+    }
+    if (message.skippedRows !== 0) {
+      writer.uint32(64).int64(message.skippedRows);
+    }
+    for (const v of message.skipSummary) {
+      ImportIssueCount.encode(v!, writer.uint32(74).fork()).join();
+    }
+    if (message.duplicateRows !== 0) {
+      writer.uint32(80).int64(message.duplicateRows);
+    }
+    for (const v of message.ignoredColumns) {
+      writer.uint32(90).string(v!);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): AppendResponse {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseAppendResponse();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.uploadId = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag !== 16) {
+            break;
+          }
+
+          message.rowCount = longToNumber(reader.int64());
+          continue;
+        }
+        case 3: {
+          if (tag !== 24) {
+            break;
+          }
+
+          message.dataVersion = longToNumber(reader.int64());
+          continue;
+        }
+        case 4: {
+          if (tag !== 34) {
+            break;
+          }
+
+          message.format = reader.string();
+          continue;
+        }
+        case 5: {
+          if (tag !== 40) {
+            break;
+          }
+
+          message.addedRows = longToNumber(reader.int64());
+          continue;
+        }
+        case 6: {
+          if (tag !== 48) {
+            break;
+          }
+
+          message.updatedRows = longToNumber(reader.int64());
+          continue;
+        }
+        case 7: {
+          if (tag !== 56) {
+            break;
+          }
+
+          message.unchangedRows = longToNumber(reader.int64());
+          continue;
+        }
+        case 8: {
+          if (tag !== 64) {
+            break;
+          }
+
+          message.skippedRows = longToNumber(reader.int64());
+          continue;
+        }
+        case 9: {
+          if (tag !== 74) {
+            break;
+          }
+
+          message.skipSummary.push(ImportIssueCount.decode(reader, reader.uint32()));
+          continue;
+        }
+        case 10: {
+          if (tag !== 80) {
+            break;
+          }
+
+          message.duplicateRows = longToNumber(reader.int64());
+          continue;
+        }
+        case 11: {
+          if (tag !== 90) {
+            break;
+          }
+
+          message.ignoredColumns.push(reader.string());
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<AppendResponse>, I>>(base?: I): AppendResponse {
+    return AppendResponse.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<AppendResponse>, I>>(object: I): AppendResponse {
+    const message = createBaseAppendResponse();
+    message.uploadId = object.uploadId ?? "";
+    message.rowCount = object.rowCount ?? 0;
+    message.dataVersion = object.dataVersion ?? 0;
+    message.format = object.format ?? "";
+    message.addedRows = object.addedRows ?? 0;
+    message.updatedRows = object.updatedRows ?? 0;
+    message.unchangedRows = object.unchangedRows ?? 0;
+    message.skippedRows = object.skippedRows ?? 0;
+    message.skipSummary = object.skipSummary?.map((e) => ImportIssueCount.fromPartial(e)) || [];
+    message.duplicateRows = object.duplicateRows ?? 0;
+    message.ignoredColumns = object.ignoredColumns?.map((e) => e) || [];
+    return message;
+  },
+};
+
+function createBaseCollectionResponse(): CollectionResponse {
+  return { collectionId: "", title: "", rowCount: 0, dataVersion: 0, memberUploadIds: [], editToken: undefined };
+}
+
+export const CollectionResponse: MessageFns<CollectionResponse> = {
+  encode(message: CollectionResponse, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.collectionId !== "") {
+      writer.uint32(10).string(message.collectionId);
+    }
+    if (message.title !== "") {
+      writer.uint32(18).string(message.title);
+    }
+    if (message.rowCount !== 0) {
+      writer.uint32(24).int64(message.rowCount);
+    }
+    if (message.dataVersion !== 0) {
+      writer.uint32(32).int64(message.dataVersion);
+    }
+    for (const v of message.memberUploadIds) {
+      writer.uint32(42).string(v!);
+    }
+    if (message.editToken !== undefined) {
+      writer.uint32(50).string(message.editToken);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): CollectionResponse {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseCollectionResponse();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.collectionId = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag !== 18) {
+            break;
+          }
+
+          message.title = reader.string();
+          continue;
+        }
+        case 3: {
+          if (tag !== 24) {
+            break;
+          }
+
+          message.rowCount = longToNumber(reader.int64());
+          continue;
+        }
+        case 4: {
+          if (tag !== 32) {
+            break;
+          }
+
+          message.dataVersion = longToNumber(reader.int64());
+          continue;
+        }
+        case 5: {
+          if (tag !== 42) {
+            break;
+          }
+
+          message.memberUploadIds.push(reader.string());
+          continue;
+        }
+        case 6: {
+          if (tag !== 50) {
+            break;
+          }
+
+          message.editToken = reader.string();
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<CollectionResponse>, I>>(base?: I): CollectionResponse {
+    return CollectionResponse.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<CollectionResponse>, I>>(object: I): CollectionResponse {
+    const message = createBaseCollectionResponse();
+    message.collectionId = object.collectionId ?? "";
+    message.title = object.title ?? "";
+    message.rowCount = object.rowCount ?? 0;
+    message.dataVersion = object.dataVersion ?? 0;
+    message.memberUploadIds = object.memberUploadIds?.map((e) => e) || [];
+    message.editToken = object.editToken ?? undefined;
+    return message;
+  },
+};
+
+function createBaseShareLink(): ShareLink {
+  return {
+    shareId: 0,
+    token: undefined,
+    label: undefined,
+    filter: undefined,
+    hideLocations: false,
+    createdAt: "",
+    expiresAt: undefined,
+  };
+}
+
+export const ShareLink: MessageFns<ShareLink> = {
+  encode(message: ShareLink, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.shareId !== 0) {
+      writer.uint32(8).int64(message.shareId);
+    }
+    if (message.token !== undefined) {
+      writer.uint32(18).string(message.token);
+    }
+    if (message.label !== undefined) {
+      writer.uint32(26).string(message.label);
+    }
+    if (message.filter !== undefined) {
+      writer.uint32(34).string(message.filter);
+    }
+    if (message.hideLocations !== false) {
+      writer.uint32(40).bool(message.hideLocations);
+    }
+    if (message.createdAt !== "") {
+      writer.uint32(50).string(message.createdAt);
+    }
+    if (message.expiresAt !== undefined) {
+      writer.uint32(58).string(message.expiresAt);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): ShareLink {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseShareLink();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 8) {
+            break;
+          }
+
+          message.shareId = longToNumber(reader.int64());
+          continue;
+        }
+        case 2: {
+          if (tag !== 18) {
+            break;
+          }
+
+          message.token = reader.string();
+          continue;
+        }
+        case 3: {
+          if (tag !== 26) {
+            break;
+          }
+
+          message.label = reader.string();
+          continue;
+        }
+        case 4: {
+          if (tag !== 34) {
+            break;
+          }
+
+          message.filter = reader.string();
+          continue;
+        }
+        case 5: {
+          if (tag !== 40) {
+            break;
+          }
+
+          message.hideLocations = reader.bool();
+          continue;
+        }
+        case 6: {
+          if (tag !== 50) {
+            break;
+          }
+
+          message.createdAt = reader.string();
+          continue;
+        }
+        case 7: {
+          if (tag !== 58) {
+            break;
+          }
+
+          message.expiresAt = reader.string();
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<ShareLink>, I>>(base?: I): ShareLink {
+    return ShareLink.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<ShareLink>, I>>(object: I): ShareLink {
+    const message = createBaseShareLink();
+    message.shareId = object.shareId ?? 0;
+    message.token = object.token ?? undefined;
+    message.label = object.label ?? undefined;
+    message.filter = object.filter ?? undefined;
+    message.hideLocations = object.hideLocations ?? false;
+    message.createdAt = object.createdAt ?? "";
+    message.expiresAt = object.expiresAt ?? undefined;
+    return message;
+  },
+};
+
+function createBaseShareLinkList(): ShareLinkList {
+  return { shares: [] };
+}
+
+export const ShareLinkList: MessageFns<ShareLinkList> = {
+  encode(message: ShareLinkList, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    for (const v of message.shares) {
+      ShareLink.encode(v!, writer.uint32(10).fork()).join();
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): ShareLinkList {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseShareLinkList();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.shares.push(ShareLink.decode(reader, reader.uint32()));
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<ShareLinkList>, I>>(base?: I): ShareLinkList {
+    return ShareLinkList.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<ShareLinkList>, I>>(object: I): ShareLinkList {
+    const message = createBaseShareLinkList();
+    message.shares = object.shares?.map((e) => ShareLink.fromPartial(e)) || [];
+    return message;
+  },
+};
+
+function createBaseEditToken(): EditToken {
+  return { tokenId: undefined, name: undefined, token: undefined, createdAt: "", current: false };
+}
+
+export const EditToken: MessageFns<EditToken> = {
+  encode(message: EditToken, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.tokenId !== undefined) {
+      writer.uint32(8).int64(message.tokenId);
+    }
+    if (message.name !== undefined) {
+      writer.uint32(18).string(message.name);
+    }
+    if (message.token !== undefined) {
+      writer.uint32(26).string(message.token);
+    }
+    if (message.createdAt !== "") {
+      writer.uint32(34).string(message.createdAt);
+    }
+    if (message.current !== false) {
+      writer.uint32(40).bool(message.current);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): EditToken {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseEditToken();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 8) {
+            break;
+          }
+
+          message.tokenId = longToNumber(reader.int64());
+          continue;
+        }
+        case 2: {
+          if (tag !== 18) {
+            break;
+          }
+
+          message.name = reader.string();
+          continue;
+        }
+        case 3: {
+          if (tag !== 26) {
+            break;
+          }
+
+          message.token = reader.string();
+          continue;
+        }
+        case 4: {
+          if (tag !== 34) {
+            break;
+          }
+
+          message.createdAt = reader.string();
+          continue;
+        }
+        case 5: {
+          if (tag !== 40) {
+            break;
+          }
+
+          message.current = reader.bool();
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<EditToken>, I>>(base?: I): EditToken {
+    return EditToken.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<EditToken>, I>>(object: I): EditToken {
+    const message = createBaseEditToken();
+    message.tokenId = object.tokenId ?? undefined;
+    message.name = object.name ?? undefined;
+    message.token = object.token ?? undefined;
+    message.createdAt = object.createdAt ?? "";
+    message.current = object.current ?? false;
+    return message;
+  },
+};
+
+function createBaseEditTokenList(): EditTokenList {
+  return { tokens: [] };
+}
+
+export const EditTokenList: MessageFns<EditTokenList> = {
+  encode(message: EditTokenList, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    for (const v of message.tokens) {
+      EditToken.encode(v!, writer.uint32(10).fork()).join();
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): EditTokenList {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseEditTokenList();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.tokens.push(EditToken.decode(reader, reader.uint32()));
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<EditTokenList>, I>>(base?: I): EditTokenList {
+    return EditTokenList.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<EditTokenList>, I>>(object: I): EditTokenList {
+    const message = createBaseEditTokenList();
+    message.tokens = object.tokens?.map((e) => EditToken.fromPartial(e)) || [];
+    return message;
+  },
+};
+
+function createBaseViewPasswordStatus(): ViewPasswordStatus {
+  return { passwordProtected: false };
+}
+
+export const ViewPasswordStatus: MessageFns<ViewPasswordStatus> = {
+  encode(message: ViewPasswordStatus, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.passwordProtected !== false) {
+      writer.uint32(8).bool(message.passwordProtected);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): ViewPasswordStatus {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseViewPasswordStatus();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 8) {
+            break;
+          }
+
+          message.passwordProtected = reader.bool();
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<ViewPasswordStatus>, I>>(base?: I): ViewPasswordStatus {
+    return ViewPasswordStatus.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<ViewPasswordStatus>, I>>(object: I): ViewPasswordStatus {
+    const message = createBaseViewPasswordStatus();
+    message.passwordProtected = object.passwordProtected ?? false;
+    return message;
+  },
+};
+
+function createBaseViewSession(): ViewSession {
+  return { session: "", expiresAt: "" };
+}
+
+export const ViewSession: MessageFns<ViewSession> = {
+  encode(message: ViewSession, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.session !== "") {
+      writer.uint32(10).string(message.session);
+    }
+    if (message.expiresAt !== "") {
+      writer.uint32(18).string(message.expiresAt);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): ViewSession {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseViewSession();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.session = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag !== 18) {
+            break;
+          }
+
+          message.expiresAt = reader.string();
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<ViewSession>, I>>(base?: I): ViewSession {
+    return ViewSession.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<ViewSession>, I>>(object: I): ViewSession {
+    const message = createBaseViewSession();
+    message.session = object.session ?? "";
+    message.expiresAt = object.expiresAt ?? "";
+    return message;
+  },
+};
+
+function createBaseLocationRule(): LocationRule {
+  return { scientificName: "", mode: "", km: undefined, commonName: undefined };
+}
+
+export const LocationRule: MessageFns<LocationRule> = {
+  encode(message: LocationRule, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.scientificName !== "") {
+      writer.uint32(10).string(message.scientificName);
+    }
+    if (message.mode !== "") {
+      writer.uint32(18).string(message.mode);
+    }
+    if (message.km !== undefined) {
+      writer.uint32(25).double(message.km);
+    }
+    if (message.commonName !== undefined) {
+      writer.uint32(34).string(message.commonName);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): LocationRule {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseLocationRule();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.scientificName = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag !== 18) {
+            break;
+          }
+
+          message.mode = reader.string();
+          continue;
+        }
+        case 3: {
+          if (tag !== 25) {
+            break;
+          }
+
+          message.km = reader.double();
+          continue;
+        }
+        case 4: {
+          if (tag !== 34) {
+            break;
+          }
+
+          message.commonName = reader.string();
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<LocationRule>, I>>(base?: I): LocationRule {
+    return LocationRule.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<LocationRule>, I>>(object: I): LocationRule {
+    const message = createBaseLocationRule();
+    message.scientificName = object.scientificName ?? "";
+    message.mode = object.mode ?? "";
+    message.km = object.km ?? undefined;
+    message.commonName = object.commonName ?? undefined;
+    return message;
+  },
+};
+
+function createBaseLocationPrivacy(): LocationPrivacy {
+  return { mode: "", km: undefined, sensitiveSpecies: false, species: [], bundledSpecies: [], dataVersion: 0 };
+}
+
+export const LocationPrivacy: MessageFns<LocationPrivacy> = {
+  encode(message: LocationPrivacy, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.mode !== "") {
+      writer.uint32(10).string(message.mode);
+    }
+    if (message.km !== undefined) {
+      writer.uint32(17).double(message.km);
+    }
+    if (message.sensitiveSpecies !== false) {
+      writer.uint32(24).bool(message.sensitiveSpecies);
+    }
+    for (const v of message.species) {
+      LocationRule.encode(v!, writer.uint32(34).fork()).join();
+    }
+    for (const v of message.bundledSpecies) {
+      LocationRule.encode(v!, writer.uint32(42).fork()).join();
+    }
+    if (message.dataVersion !== 0) {
+      writer.uint32(48).int64(message.dataVersion);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): LocationPrivacy {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseLocationPrivacy();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.mode = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag !== 17) {
+            break;
+          }
+
+          message.km = reader.double();
+          continue;
+        }
+        case 3: {
+          if (tag !== 24) {
+            break;
+          }
+
+          message.sensitiveSpecies = reader.bool();
+          continue;
+        }
+        case 4: {
+          if (tag !== 34) {
+            break;
+          }
+
+          message.species.push(LocationRule.decode(reader, reader.uint32()));
+          continue;
+        }
+        case 5: {
+          if (tag !== 42) {
+            break;
+          }
+
+          message.bundledSpecies.push(LocationRule.decode(reader, reader.uint32()));
+          continue;
+        }
+        case 6: {
+          if (tag !== 48) {
+            break;
+          }
+
+          message.dataVersion = longToNumber(reader.int64());
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<LocationPrivacy>, I>>(base?: I): LocationPrivacy {
+    return LocationPrivacy.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<LocationPrivacy>, I>>(object: I): LocationPrivacy {
+    const message = createBaseLocationPrivacy();
+    message.mode = object.mode ?? "";
+    message.km = object.km ?? undefined;
+    message.sensitiveSpecies = object.sensitiveSpecies ?? false;
+    message.species = object.species?.map((e) => LocationRule.fromPartial(e)) || [];
+    message.bundledSpecies = object.bundledSpecies?.map((e) => LocationRule.fromPartial(e)) || [];
+    message.dataVersion = object.dataVersion ?? 0;
+    return message;
+  },
+};
+
+function createBaseSightingEditResponse(): SightingEditResponse {
+  return { sightingId: 0, dataVersion: 0, deleted: false };
+}
+
+export const SightingEditResponse: MessageFns<SightingEditResponse> = {
+  encode(message: SightingEditResponse, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.sightingId !== 0) {
+      writer.uint32(8).int64(message.sightingId);
+    }
+    if (message.dataVersion !== 0) {
+      writer.uint32(16).int64(message.dataVersion);
+    }
+    if (message.deleted !== false) {
+      writer.uint32(24).bool(message.deleted);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): SightingEditResponse {
     const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
     const end = length === undefined ? reader.len : reader.pos + length;
-    const message = createBaseApiErrorBody();
+    const message = createBaseSightingEditResponse();
     while (reader.pos < end) {
       const tag = reader.uint32();
       switch (tag >>> 3) {
         case 1: {
-          if (tag !== 10) {
+          if (tag !== 8) {
             break;
           }
 
-          message.error = reader.string();
+          message.sightingId = longToNumber(reader.int64());
           continue;
         }
         case 2: {
-          if (tag !== 18) {
+          if (tag !== 16) {
             break;
           }
 
-          message.code = reader.string();
+          message.dataVersion = longToNumber(reader.int64());
+          continue;
+        }
+        case 3: {
+          if (tag !== 24) {
+            break;
+          }
+
+          message.deleted = reader.bool();
           continue;
         }
       }
@@ -196,45 +1817,40 @@ export const ApiErrorBody: MessageFns<ApiErrorBody> = {
     return message;
   },
 
-  create<I extends Exact<DeepPartial<ApiErrorBody>, I>>(base?: I): ApiErrorBody {
-    return ApiErrorBody.fromPartial(base ?? ({} as any));
+  create<I extends Exact<DeepPartial<SightingEditResponse>, I>>(base?: I): SightingEditResponse {
+    return SightingEditResponse.fromPartial(base ?? ({} as any));
   },
-  fromPartial<I extends Exact<DeepPartial<ApiErrorBody>, I>>(object: I): ApiErrorBody {
-    const message = createBaseApiErrorBody();
-    message.error = object.error ?? "";
-    message.code = object.code ?? undefined;
+  fromPartial<I extends Exact<DeepPartial<SightingEditResponse>, I>>(object: I): SightingEditResponse {
+    const message = createBaseSightingEditResponse();
+    message.sightingId = object.sightingId ?? 0;
+    message.dataVersion = object.dataVersion ?? 0;
+    message.deleted = object.deleted ?? false;
     return message;
   },
 };
 
-function createBaseUploadMetadata(): UploadMetadata {
-  return { uploadId: "", filename: "", rowCount: 0, title: "", dataVersion: 0 };
+function createBaseImportIssueCount(): ImportIssueCount {
+  return { column: "", reason: "", count: 0 };
 }
 
-export const UploadMetadata: MessageFns<UploadMetadata> = {
-  encode(message: UploadMetadata, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
-    if (message.uploadId !== "") {
-      writer.uint32(10).string(message.uploadId);
+export const ImportIssueCount: MessageFns<ImportIssueCount> = {
+  encode(message: ImportIssueCount, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.column !== "") {
+      writer.uint32(10).string(message.column);
     }
-    if (message.filename !== "") {
-      writer.uint32(18).string(message.filename);
-    }
-    if (message.rowCount !== 0) {
-      writer.uint32(24).int64(message.rowCount);
-    }
-    if (message.title !== "") {
-      writer.uint32(34).string(message.title);
+    if (message.reason !== "") {
+      writer.uint32(18).string(message.reason);
     }
-    if (message.dataVersion !== 0) {
-      writer.uint32(40).int64(message.dataVersion);
+    if (message.count !== 0) {
+      writer.uint32(24).int64(message.count);
     }
     return writer;
   },
 
-  decode(input: BinaryReader | Uint8Array, length?: number): UploadMetadata {
+  decode(input: BinaryReader | Uint8Array, length?: number): ImportIssueCount {
     const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
     const end = length === undefined ? reader.len : reader.pos + length;
-    const message = createBaseUploadMetadata();
+    const message = createBaseImportIssueCount();
     while (reader.pos < end) {
       const tag = reader.uint32();
       switch (tag >>> 3) {
@@ -243,7 +1859,7 @@ export const UploadMetadata: MessageFns<UploadMetadata> = {
             break;
           }
 
-          message.uploadId = reader.string();
+          message.column = reader.string();
           continue;
         }
         case 2: {
@@ -251,7 +1867,7 @@ export const UploadMetadata: MessageFns<UploadMetadata> = {
             break;
           }
 
-          message.filename = reader.string();
+          message.reason = reader.string();
           continue;
         }
         case 3: {
@@ -259,23 +1875,7 @@ export const UploadMetadata: MessageFns<UploadMetadata> = {
             break;
           }
 
-          message.rowCount = longToNumber(reader.int64());
-          continue;
-        }
-        case 4: {
-          if (tag !== 34) {
-            break;
-          }
-
-          message.title = reader.string();
-          continue;
-        }
-        case 5: {
-          if (tag !== 40) {
-            break;
-          }
-
-          message.dataVersion = longToNumber(reader.int64());
+          message.count = longToNumber(reader.int64());
           continue;
         }
       }
@@ -287,60 +1887,52 @@ export const UploadMetadata: MessageFns<UploadMetadata> = {
     return message;
   },
 
-  create<I extends Exact<DeepPartial<UploadMetadata>, I>>(base?: I): UploadMetadata {
-    return UploadMetadata.fromPartial(base ?? ({} as any));
+  create<I extends Exact<DeepPartial<ImportIssueCount>, I>>(base?: I): ImportIssueCount {
+    return ImportIssueCount.fromPartial(base ?? ({} as any));
   },
-  fromPartial<I extends Exact<DeepPartial<UploadMetadata>, I>>(object: I): UploadMetadata {
-    const message = createBaseUploadMetadata();
-    message.uploadId = object.uploadId ?? "";
-    message.filename = object.filename ?? "";
-    message.rowCount = object.rowCount ?? 0;
-    message.title = object.title ?? "";
-    message.dataVersion = object.dataVersion ?? 0;
+  fromPartial<I extends Exact<DeepPartial<ImportIssueCount>, I>>(object: I): ImportIssueCount {
+    const message = createBaseImportIssueCount();
+    message.column = object.column ?? "";
+    message.reason = object.reason ?? "";
+    message.count = object.count ?? 0;
     return message;
   },
 };
 
-function createBaseUploadResponse(): UploadResponse {
-  return { uploadId: "", filename: "", rowCount: 0, editToken: "", title: "", dataVersion: 0 };
+function createBaseImportIssue(): ImportIssue {
+  return { row: 0, column: "", reason: "", dataVersion: 0 };
 }
 
-export const UploadResponse: MessageFns<UploadResponse> = {
-  encode(message: UploadResponse, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
-    if (message.uploadId !== "") {
-      writer.uint32(10).string(message.uploadId);
-    }
-    if (message.filename !== "") {
-      writer.uint32(18).string(message.filename);
+export const ImportIssue: MessageFns<ImportIssue> = {
+  encode(message: ImportIssue, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.row !== 0) {
+      writer.uint32(8).int64(message.row);
     }
-    if (message.rowCount !== 0) {
-      writer.uint32(24).int64(message.rowCount);
-    }
-    if (message.editToken !== "") {
-      writer.uint32(34).string(message.editToken);
+    if (message.column !== "") {
+      writer.uint32(18).string(message.column);
     }
-    if (message.title !== "") {
-      writer.uint32(42).string(message.title);
+    if (message.reason !== "") {
+      writer.uint32(26).string(message.reason);
     }
     if (message.dataVersion !== 0) {
-      writer.uint32(48).int64(message.dataVersion);
+      writer.uint32(32).int64(message.dataVersion);
     }
     return writer;
   },
 
-  decode(input: BinaryReader | Uint8Array, length?: number): UploadResponse {
+  decode(input: BinaryReader | Uint8Array, length?: number): ImportIssue {
     const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
     const end = length === undefined ? reader.len : reader.pos + length;
-    const message = createBaseUploadResponse();
+    const message = createBaseImportIssue();
     while (reader.pos < end) {
       const tag = reader.uint32();
       switch (tag >>> 3) {
         case 1: {
-          if (tag !== 10) {
+          if (tag !== 8) {
             break;
           }
 
-          message.uploadId = reader.string();
+          message.row = longToNumber(reader.int64());
           continue;
         }
         case 2: {
@@ -348,35 +1940,19 @@ export const UploadResponse: MessageFns<UploadResponse> = {
             break;
           }
 
-          message.filename = reader.string();
+          message.column = reader.string();
           continue;
         }
         case 3: {
-          if (tag !== 24) {
+          if (tag !== 26) {
             break;
           }
 
-          message.rowCount = longToNumber(reader.int64());
+          message.reason = reader.string();
           continue;
         }
         case 4: {
-          if (tag !== 34) {
-            break;
-          }
-
-          message.editToken = reader.string();
-          continue;
-        }
-        case 5: {
-          if (tag !== 42) {
-            break;
-          }
-
-          message.title = reader.string();
-          continue;
-        }
-        case 6: {
-          if (tag !== 48) {
+          if (tag !== 32) {
             break;
           }
 
@@ -392,49 +1968,61 @@ export const UploadResponse: MessageFns<UploadResponse> = {
     return message;
   },
 
-  create<I extends Exact<DeepPartial<UploadResponse>, I>>(base?: I): UploadResponse {
-    return UploadResponse.fromPartial(base ?? ({} as any));
+  create<I extends Exact<DeepPartial<ImportIssue>, I>>(base?: I): ImportIssue {
+    return ImportIssue.fromPartial(base ?? ({} as any));
   },
-  fromPartial<I extends Exact<DeepPartial<UploadResponse>, I>>(object: I): UploadResponse {
-    const message = createBaseUploadResponse();
-    message.uploadId = object.uploadId ?? "";
-    message.filename = object.filename ?? "";
-    message.rowCount = object.rowCount ?? 0;
-    message.editToken = object.editToken ?? "";
-    message.title = object.title ?? "";
+  fromPartial<I extends Exact<DeepPartial<ImportIssue>, I>>(object: I): ImportIssue {
+    const message = createBaseImportIssue();
+    message.row = object.row ?? 0;
+    message.column = object.column ?? "";
+    message.reason = object.reason ?? "";
     message.dataVersion = object.dataVersion ?? 0;
     return message;
   },
 };
 
-function createBaseUpdateResponse(): UpdateResponse {
-  return { uploadId: "", filename: "", rowCount: 0, title: "", dataVersion: 0 };
+function createBaseImportReport(): ImportReport {
+  return {
+    uploadId: "",
+    format: "",
+    skippedRows: 0,
+    issues: [],
+    truncated: false,
+    dataVersion: 0,
+    unmatchedSpecies: [],
+  };
 }
 
-export const UpdateResponse: MessageFns<UpdateResponse> = {
-  encode(message: UpdateResponse, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+export const ImportReport: MessageFns<ImportReport> = {
+  encode(message: ImportReport, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
     if (message.uploadId !== "") {
       writer.uint32(10).string(message.uploadId);
     }
-    if (message.filename !== "") {
-      writer.uint32(18).string(message.filename);
+    if (message.format !== "") {
+      writer.uint32(18).string(message.format);
     }
-    if (message.rowCount !== 0) {
-      writer.uint32(24).int64(message.rowCount);
+    if (message.skippedRows !== 0) {
+      writer.uint32(24).int64(message.skippedRows);
     }
-    if (message.title !== "") {
-      writer.uint32(34).string(message.title);
+    for (const v of message.issues) {
+      ImportIssue.encode(v!, writer.uint32(34).fork()).join();
+    }
+    if (message.truncated !== false) {
+      writer.uint32(40).bool(message.truncated);
     }
     if (message.dataVersion !== 0) {
-      writer.uint32(40).int64(message.dataVersion);
+      writer.uint32(48).int64(message.dataVersion);
+    }
+    for (const v of message.unmatchedSpecies) {
+      SpeciesCount.encode(v!, writer.uint32(58).fork()).join();
     }
     return writer;
   },
 
-  decode(input: BinaryReader | Uint8Array, length?: number): UpdateResponse {
+  decode(input: BinaryReader | Uint8Array, length?: number): ImportReport {
     const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
     const end = length === undefined ? reader.len : reader.pos + length;
-    const message = createBaseUpdateResponse();
+    const message = createBaseImportReport();
     while (reader.pos < end) {
       const tag = reader.uint32();
       switch (tag >>> 3) {
@@ -451,7 +2039,7 @@ export const UpdateResponse: MessageFns<UpdateResponse> = {
             break;
           }
 
-          message.filename = reader.string();
+          message.format = reader.string();
           continue;
         }
         case 3: {
@@ -459,7 +2047,7 @@ export const UpdateResponse: MessageFns<UpdateResponse> = {
             break;
           }
 
-          message.rowCount = longToNumber(reader.int64());
+          message.skippedRows = longToNumber(reader.int64());
           continue;
         }
         case 4: {
@@ -467,7 +2055,7 @@ export const UpdateResponse: MessageFns<UpdateResponse> = {
             break;
           }
 
-          message.title = reader.string();
+          message.issues.push(ImportIssue.decode(reader, reader.uint32()));
           continue;
         }
         case 5: {
@@ -475,9 +2063,25 @@ export const UpdateResponse: MessageFns<UpdateResponse> = {
             break;
           }
 
+          message.truncated = reader.bool();
+          continue;
+        }
+        case 6: {
+          if (tag !== 48) {
+            break;
+          }
+
           message.dataVersion = longToNumber(reader.int64());
           continue;
         }
+        case 7: {
+          if (tag !== 58) {
+            break;
+          }
+
+          message.unmatchedSpecies.push(SpeciesCount.decode(reader, reader.uint32()));
+          continue;
+        }
       }
       if ((tag & 7) === 4 || tag === 0) {
         break;
@@ -487,16 +2091,18 @@ export const UpdateResponse: MessageFns<UpdateResponse> = {
     return message;
   },
 
-  create<I extends Exact<DeepPartial<UpdateResponse>, I>>(base?: I): UpdateResponse {
-    return UpdateResponse.fromPartial(base ?? ({} as any));
+  create<I extends Exact<DeepPartial<ImportReport>, I>>(base?: I): ImportReport {
+    return ImportReport.fromPartial(base ?? ({} as any));
   },
-  fromPartial<I extends Exact<DeepPartial<UpdateResponse>, I>>(object: I): UpdateResponse {
-    const message = createBaseUpdateResponse();
+  fromPartial<I extends Exact<DeepPartial<ImportReport>, I>>(object: I): ImportReport {
+    const message = createBaseImportReport();
     message.uploadId = object.uploadId ?? "";
-    message.filename = object.filename ?? "";
-    message.rowCount = object.rowCount ?? 0;
-    message.title = object.title ?? "";
+    message.format = object.format ?? "";
+    message.skippedRows = object.skippedRows ?? 0;
+    message.issues = object.issues?.map((e) => ImportIssue.fromPartial(e)) || [];
+    message.truncated = object.truncated ?? false;
     message.dataVersion = object.dataVersion ?? 0;
+    message.unmatchedSpecies = object.unmatchedSpecies?.map((e) => SpeciesCount.fromPartial(e)) || [];
     return message;
   },
 };
@@ -886,7 +2492,7 @@ export const BboxResponse: MessageFns<BboxResponse> = {
 };
 
 function createBaseSpecies(): Species {
-  return { commonName: "", scientificName: "" };
+  return { commonName: "", scientificName: "", taxonId: undefined };
 }
 
 export const Species: MessageFns<Species> = {
@@ -897,6 +2503,9 @@ export const Species: MessageFns<Species> = {
     if (message.scientificName !== "") {
       writer.uint32(18).string(message.scientificName);
     }
+    if (message.taxonId !== undefined) {
+      writer.uint32(24).int64(message.taxonId);
+    }
     return writer;
   },
 
@@ -923,6 +2532,14 @@ export const Species: MessageFns<Species> = {
           message.scientificName = reader.string();
           continue;
         }
+        case 3: {
+          if (tag !== 24) {
+            break;
+          }
+
+          message.taxonId = longToNumber(reader.int64());
+          continue;
+        }
       }
       if ((tag & 7) === 4 || tag === 0) {
         break;
@@ -939,6 +2556,7 @@ export const Species: MessageFns<Species> = {
     const message = createBaseSpecies();
     message.commonName = object.commonName ?? "";
     message.scientificName = object.scientificName ?? "";
+    message.taxonId = object.taxonId ?? undefined;
     return message;
   },
 };
@@ -948,8 +2566,8 @@ function createBaseSighting(): Sighting {
     id: 0,
     commonNameIndex: undefined,
     count: undefined,
-    latitude: 0,
-    longitude: 0,
+    latitude: undefined,
+    longitude: undefined,
     countryCode: undefined,
     regionCode: undefined,
     observedAt: "",
@@ -967,10 +2585,10 @@ export const Sighting: MessageFns<Sighting> = {
     if (message.count !== undefined) {
       writer.uint32(24).int64(message.count);
     }
-    if (message.latitude !== 0) {
+    if (message.latitude !== undefined) {
       writer.uint32(33).double(message.latitude);
     }
-    if (message.longitude !== 0) {
+    if (message.longitude !== undefined) {
       writer.uint32(41).double(message.longitude);
     }
     if (message.countryCode !== undefined) {
@@ -1073,8 +2691,8 @@ export const Sighting: MessageFns<Sighting> = {
     message.id = object.id ?? 0;
     message.commonNameIndex = object.commonNameIndex ?? undefined;
     message.count = object.count ?? undefined;
-    message.latitude = object.latitude ?? 0;
-    message.longitude = object.longitude ?? 0;
+    message.latitude = object.latitude ?? undefined;
+    message.longitude = object.longitude ?? undefined;
     message.countryCode = object.countryCode ?? undefined;
     message.regionCode = object.regionCode ?? undefined;
     message.observedAt = object.observedAt ?? "";
@@ -1422,6 +3040,88 @@ export const SpeciesCount: MessageFns<SpeciesCount> = {
   },
 };
 
+function createBaseTaxonRankCount(): TaxonRankCount {
+  return { name: "", species: 0, sightings: 0, lifers: 0 };
+}
+
+export const TaxonRankCount: MessageFns<TaxonRankCount> = {
+  encode(message: TaxonRankCount, writer: BinaryWriter = new BinaryWriter()): BinaryWriter {
+    if (message.name !== "") {
+      writer.uint32(10).string(message.name);
+    }
+    if (message.species !== 0) {
+      writer.uint32(16).int64(message.species);
+    }
+    if (message.sightings !== 0) {
+      writer.uint32(24).int64(message.sightings);
+    }
+    if (message.lifers !== 0) {
+      writer.uint32(32).int64(message.lifers);
+    }
+    return writer;
+  },
+
+  decode(input: BinaryReader | Uint8Array, length?: number): TaxonRankCount {
+    const reader = input instanceof BinaryReader ? input : new BinaryReader(input);
+    const end = length === undefined ? reader.len : reader.pos + length;
+    const message = createBaseTaxonRankCount();
+    while (reader.pos < end) {
+      const tag = reader.uint32();
+      switch (tag >>> 3) {
+        case 1: {
+          if (tag !== 10) {
+            break;
+          }
+
+          message.name = reader.string();
+          continue;
+        }
+        case 2: {
+          if (tag !== 16) {
+            break;
+          }
+
+          message.species = longToNumber(reader.int64());
+          continue;
+        }
+        case 3: {
+          if (tag !== 24) {
+            break;
+          }
+
+          message.sightings = longToNumber(reader.int64());
+          continue;
+        }
+        case 4: {
+          if (tag !== 32) {
+            break;
+          }
+
+          message.lifers = longToNumber(reader.int64());
+          continue;
+        }
+      }
+      if ((tag & 7) === 4 || tag === 0) {
+        break;
+      }
+      reader.skip(tag & 7);
+    }
+    return message;
+  },
+
+  create<I extends Exact<DeepPartial<TaxonRankCount>, I>>(base?: I): TaxonRankCount {
+    return TaxonRankCount.fromPartial(base ?? ({} as any));
+  },
+  fromPartial<I extends Exact<DeepPartial<TaxonRankCount>, I>>(object: I): TaxonRankCount {
+    const message = createBaseTaxonRankCount();
+    message.name = object.name ?? "";
+    message.species = object.species ?? 0;
+    message.sightings = object.sightings ?? 0;
+    message.lifers = object.lifers ?? 0;
+    return message;
+  },
+};
+
 function createBaseCountryStats(): CountryStats {
   return { countryCode: "", sightings: 0, lifers: 0 };
 }
@@ -1570,6 +3270,8 @@ function createBaseStatsResponse(): StatsResponse {
     lifersTimeline: [],
     sightingsTimeline: [],
     longestStreakDays: 0,
+    familyStats: [],
+    orderStats: [],
   };
 }
 
@@ -1629,6 +3331,12 @@ export const StatsResponse: MessageFns<StatsResponse> = {
     if (message.longestStreakDays !== 0) {
       writer.uint32(144).int64(message.longestStreakDays);
     }
+    for (const v of message.familyStats) {
+      TaxonRankCount.encode(v!, writer.uint32(154).fork()).join();
+    }
+    for (const v of message.orderStats) {
+      TaxonRankCount.encode(v!, writer.uint32(162).fork()).join();
+    }
     return writer;
   },
 
@@ -1783,6 +3491,22 @@ export const StatsResponse: MessageFns<StatsResponse> = {
           message.longestStreakDays = longToNumber(reader.int64());
           continue;
         }
+        case 19: {
+          if (tag !== 154) {
+            break;
+          }
+
+          message.familyStats.push(TaxonRankCount.decode(reader, reader.uint32()));
+          continue;
+        }
+        case 20: {
+          if (tag !== 162) {
+            break;
+          }
+
+          message.orderStats.push(TaxonRankCount.decode(reader, reader.uint32()));
+          continue;
+        }
       }
       if ((tag & 7) === 4 || tag === 0) {
         break;
@@ -1815,6 +3539,8 @@ export const StatsResponse: MessageFns<StatsResponse> = {
     message.lifersTimeline = object.lifersTimeline?.map((e) => TimelinePoint.fromPartial(e)) || [];
     message.sightingsTimeline = object.sightingsTimeline?.map((e) => TimelinePoint.fromPartial(e)) || [];
     message.longestStreakDays = object.longestStreakDays ?? 0;
+    message.familyStats = object.familyStats?.map((e) => TaxonRankCount.fromPartial(e)) || [];
+    message.orderStats = object.orderStats?.map((e) => TaxonRankCount.fromPartial(e)) || [];
     return message;
   },
 };